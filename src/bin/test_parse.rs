@@ -6,16 +6,56 @@ use libmbus::parse::link_layer::Packet;
 use libmbus::utils::{fancy_error, read_test_file};
 
 fn main() {
-	for fname in std::env::args().skip(1) {
-		println!("File {fname:?}:");
+	let (flags, fnames): (Vec<_>, Vec<_>) = std::env::args()
+		.skip(1)
+		.partition(|arg| arg.starts_with("--"));
+	let json = flags.iter().any(|flag| flag == "--json");
+	if let Some(flag) = flags.iter().find(|flag| *flag != "--json") {
+		eprintln!("Unknown option {flag:?}");
+		std::process::exit(2);
+	}
+	if json && !cfg!(feature = "json") {
+		eprintln!("--json needs the \"json\" feature to be enabled");
+		std::process::exit(2);
+	}
+
+	let mut failed = false;
+	for fname in fnames {
+		// Keep stdout as nothing but JSON so it can be piped into other tools
+		if json {
+			eprintln!("File {fname:?}:");
+		} else {
+			println!("File {fname:?}:");
+		}
 
 		let data = read_test_file(&fname).expect("Could not open file");
 
 		let packet = Packet::parse.parse(Bytes::new(&data[..]));
 
 		match packet {
+			Ok(packet) if json => print_json(&packet),
 			Ok(packet) => println!("{packet:#?}"),
-			Err(e) => fancy_error(&e.into_inner()),
+			Err(e) => {
+				failed = true;
+				fancy_error(e.inner());
+				eprintln!("{}", e.inner().hex_dump(&data));
+			}
 		}
 	}
+	if failed {
+		std::process::exit(1);
+	}
+}
+
+#[cfg(feature = "json")]
+fn print_json(packet: &Packet) {
+	println!(
+		"{}",
+		serde_json::to_string_pretty(packet).expect("Packets can always be serialised")
+	);
+}
+
+#[cfg(not(feature = "json"))]
+fn print_json(_packet: &Packet) {
+	unreachable!("--json is rejected without the json feature")
 }
@@ -0,0 +1,237 @@
+// Copyright 2024 Lexi Robinson
+// Licensed under the EUPL-1.2
+
+use crate::parse::application_layer::record::{encode_readout_selection, ReadoutSelector};
+use crate::parse::link_layer::{
+	calculate_checksum, Control, PrimaryControlMessage, ACK_FRAME, FRAME_TAIL, LONG_FRAME_HEADER,
+	SHORT_FRAME_HEADER,
+};
+use crate::parse::transport_layer::control_info::BaudRate;
+
+const CI_APPLICATION_RESET: u8 = 0x50;
+const CI_DATA_SEND: u8 = 0x51;
+/// The length field also counts the control and address bytes
+const MAX_LONG_FRAME_DATA: usize = u8::MAX as usize - 2;
+
+/// Encodes a single character acknowledgement frame
+pub fn encode_ack() -> Vec<u8> {
+	vec![ACK_FRAME]
+}
+
+/// Encodes a fixed length frame, which only carries the control field and the
+/// address
+pub fn encode_short_frame(control: &Control, address: u8) -> Vec<u8> {
+	let control = control.to_byte();
+	vec![
+		SHORT_FRAME_HEADER,
+		control,
+		address,
+		calculate_checksum(&[control, address]),
+		FRAME_TAIL,
+	]
+}
+
+/// Encodes a variable length frame. `data` is everything after the address
+/// byte, starting with the CI field.
+///
+/// # Panics
+///
+/// A long frame can hold at most 253 bytes of data, anything larger than that
+/// cannot be encoded.
+pub fn encode_long_frame(control: &Control, address: u8, data: &[u8]) -> Vec<u8> {
+	let length = u8::try_from(data.len() + 2).expect("long frames can hold at most 253 bytes");
+
+	let mut ret = Vec::with_capacity(data.len() + 6);
+	ret.extend([LONG_FRAME_HEADER, length, length, LONG_FRAME_HEADER]);
+	let body_start = ret.len();
+	ret.extend([control.to_byte(), address]);
+	ret.extend(data);
+	let sum = calculate_checksum(&ret[body_start..]);
+	ret.extend([sum, FRAME_TAIL]);
+	ret
+}
+
+/// The common telegrams a master sends to a slave
+#[derive(Debug, Clone)]
+pub enum Request {
+	/// REQ_UD2 - ask the device for its data
+	RequestUserData2 { address: u8, fcb: bool },
+	/// SND_NKE - reset the device's link layer
+	ResetRemoteLink { address: u8 },
+	/// SND_UD with CI 0x50, optionally with the application subcode that
+	/// specifies what to reset
+	ApplicationReset {
+		address: u8,
+		fcb: bool,
+		subcode: Option<u8>,
+	},
+	/// SND_UD with a CI field of 0xB8 to 0xBF
+	SetBaudRate {
+		address: u8,
+		fcb: bool,
+		rate: BaudRate,
+	},
+	/// SND_UD with CI 0x51 listing the records the next REQ_UD2 should
+	/// return
+	ReadoutSelection {
+		address: u8,
+		fcb: bool,
+		selection: ReadoutSelection,
+	},
+}
+
+/// A list of selectors that has been checked to fit in a readout selection
+/// telegram
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReadoutSelection {
+	selectors: Vec<ReadoutSelector>,
+	data: Vec<u8>,
+}
+
+impl ReadoutSelection {
+	/// Returns `None` if any of the selectors can't be encoded (see
+	/// [`encode_readout_selection`]) or if there are too many of them to fit
+	/// in a long frame
+	pub fn new(selectors: Vec<ReadoutSelector>) -> Option<Self> {
+		let mut data = vec![CI_DATA_SEND];
+		data.extend(encode_readout_selection(&selectors)?);
+		if data.len() > MAX_LONG_FRAME_DATA {
+			return None;
+		}
+		Some(Self { selectors, data })
+	}
+
+	pub fn selectors(&self) -> &[ReadoutSelector] {
+		&self.selectors
+	}
+}
+
+impl Request {
+	/// Builds the complete telegram, ready to be sent on the wire
+	pub fn build(&self) -> Vec<u8> {
+		match self {
+			Self::RequestUserData2 { address, fcb } => encode_short_frame(
+				&Control::Primary {
+					frame_count_bit: *fcb,
+					message: PrimaryControlMessage::RequestUserData2,
+				},
+				*address,
+			),
+			Self::ResetRemoteLink { address } => encode_short_frame(
+				&Control::Primary {
+					frame_count_bit: false,
+					message: PrimaryControlMessage::ResetRemoteLink,
+				},
+				*address,
+			),
+			Self::ApplicationReset {
+				address,
+				fcb,
+				subcode,
+			} => {
+				let mut data = vec![CI_APPLICATION_RESET];
+				data.extend(subcode);
+				encode_long_frame(&send_user_data(*fcb), *address, &data)
+			}
+			Self::SetBaudRate { address, fcb, rate } => {
+				encode_long_frame(&send_user_data(*fcb), *address, &[rate.ci_field()])
+			}
+			Self::ReadoutSelection {
+				address,
+				fcb,
+				selection,
+			} => encode_long_frame(&send_user_data(*fcb), *address, &selection.data),
+		}
+	}
+}
+
+fn send_user_data(fcb: bool) -> Control {
+	Control::Primary {
+		frame_count_bit: fcb,
+		message: PrimaryControlMessage::SendUserDataConfirmed,
+	}
+}
+
+#[cfg(test)]
+mod test_request {
+	use rstest::rstest;
+	use winnow::prelude::*;
+	use winnow::Bytes;
+
+	use super::{ReadoutSelection, Request};
+	use crate::parse::application_layer::dib::{DataFunction, DataInfoBlock, RawDataType};
+	use crate::parse::application_layer::record::ReadoutSelector;
+	use crate::parse::link_layer::Packet;
+	use crate::parse::transport_layer::control_info::BaudRate;
+
+	fn select_all() -> ReadoutSelection {
+		ReadoutSelection::new(vec![ReadoutSelector::All]).unwrap()
+	}
+
+	#[rstest]
+	#[case::req_ud2(Request::RequestUserData2 { address: 0x01, fcb: false }, &[0x10, 0x5B, 0x01, 0x5C, 0x16])]
+	#[case::req_ud2_fcb(Request::RequestUserData2 { address: 0x01, fcb: true }, &[0x10, 0x7B, 0x01, 0x7C, 0x16])]
+	#[case::snd_nke(Request::ResetRemoteLink { address: 0xFE }, &[0x10, 0x40, 0xFE, 0x3E, 0x16])]
+	#[case::application_reset(
+		Request::ApplicationReset { address: 0xFE, fcb: false, subcode: None },
+		&[0x68, 0x03, 0x03, 0x68, 0x53, 0xFE, 0x50, 0xA1, 0x16],
+	)]
+	#[case::application_reset_fcb(
+		Request::ApplicationReset { address: 0xFE, fcb: true, subcode: None },
+		&[0x68, 0x03, 0x03, 0x68, 0x73, 0xFE, 0x50, 0xC1, 0x16],
+	)]
+	#[case::application_reset_subcode(
+		Request::ApplicationReset { address: 0x01, fcb: false, subcode: Some(0x10) },
+		&[0x68, 0x04, 0x04, 0x68, 0x53, 0x01, 0x50, 0x10, 0xB4, 0x16],
+	)]
+	#[case::set_baud_rate(
+		Request::SetBaudRate { address: 0x01, fcb: false, rate: BaudRate::Rate9600 },
+		&[0x68, 0x03, 0x03, 0x68, 0x53, 0x01, 0xBD, 0x11, 0x16],
+	)]
+	#[case::select_all_records(
+		Request::ReadoutSelection { address: 0x01, fcb: false, selection: select_all() },
+		&[0x68, 0x04, 0x04, 0x68, 0x53, 0x01, 0x51, 0x7F, 0x24, 0x16],
+	)]
+	fn test_build(#[case] request: Request, #[case] expected: &[u8]) {
+		let result = request.build();
+
+		assert_eq!(result, expected);
+	}
+
+	#[test]
+	fn test_build_unencodable_selection() {
+		let dib = DataInfoBlock {
+			raw_type: RawDataType::Binary(4),
+			function: DataFunction::InstantaneousValue,
+			storage: 0,
+			tariff: 0,
+			device: 0,
+			is_obis: false,
+			raw: vec![0x04],
+		};
+
+		assert_eq!(
+			ReadoutSelection::new(vec![ReadoutSelector::AnyValue(dib)]),
+			None
+		);
+	}
+
+	#[test]
+	fn test_build_oversized_selection() {
+		assert!(ReadoutSelection::new(vec![ReadoutSelector::All; 252]).is_some());
+		assert_eq!(ReadoutSelection::new(vec![ReadoutSelector::All; 253]), None);
+	}
+
+	#[rstest]
+	#[case::req_ud2(Request::RequestUserData2 { address: 0x01, fcb: true })]
+	#[case::snd_nke(Request::ResetRemoteLink { address: 0xFE })]
+	#[case::application_reset(Request::ApplicationReset { address: 0xFE, fcb: true, subcode: None })]
+	#[case::application_reset_subcode(Request::ApplicationReset { address: 0x01, fcb: false, subcode: Some(0x10) })]
+	#[case::set_baud_rate(Request::SetBaudRate { address: 0x01, fcb: true, rate: BaudRate::Rate2400 })]
+	#[case::select_all_records(Request::ReadoutSelection { address: 0x01, fcb: false, selection: select_all() })]
+	fn test_parses(#[case] request: Request) {
+		let data = request.build();
+
+		Packet::parse.parse(Bytes::new(&data)).unwrap();
+	}
+}
@@ -1,20 +1,100 @@
 // Copyright 2023 Lexi Robinson
 // Licensed under the EUPL-1.2
 
+pub mod encode;
 pub mod parse;
 
 pub mod utils {
 	use crate::parse::error::MBusError;
+	use crate::parse::link_layer::Packet;
+
+	/// The string passed to [`parse_hex`] had something other than a pair of
+	/// hex digits at this byte offset
+	#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+	pub struct InvalidHex {
+		pub position: usize,
+	}
+
+	impl std::fmt::Display for InvalidHex {
+		fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+			write!(f, "invalid hex at position {}", self.position)
+		}
+	}
+
+	impl std::error::Error for InvalidHex {}
+
+	/// Converts a string of hex into bytes. The bytes can be written together
+	/// like `AABB` or separated by whitespace like `AA BB`, but each byte's
+	/// two digits must be next to each other.
+	pub fn parse_hex(data: &str) -> Result<Vec<u8>, InvalidHex> {
+		let mut result = Vec::new();
+		let mut position = 0;
+		while let Some(&next) = data.as_bytes().get(position) {
+			if next.is_ascii_whitespace() {
+				position += 1;
+				continue;
+			}
+			let byte = data
+				.get(position..position + 2)
+				.filter(|pair| pair.bytes().all(|c| c.is_ascii_hexdigit()))
+				.and_then(|pair| u8::from_str_radix(pair, 16).ok())
+				.ok_or(InvalidHex { position })?;
+			result.push(byte);
+			position += 2;
+		}
+		Ok(result)
+	}
+
+	/// Why [`parse_hex_frame`] failed
+	#[derive(Debug, Clone, PartialEq)]
+	pub enum HexFrameError {
+		/// The string wasn't valid hex
+		Hex(InvalidHex),
+		/// The string was valid hex, but the bytes weren't a valid packet
+		Packet(MBusError),
+	}
+
+	impl std::fmt::Display for HexFrameError {
+		fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+			match self {
+				Self::Hex(err) => write!(f, "{err}"),
+				Self::Packet(err) => write!(f, "invalid packet: {err}"),
+			}
+		}
+	}
+
+	impl std::error::Error for HexFrameError {
+		fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+			match self {
+				Self::Hex(err) => Some(err),
+				Self::Packet(_) => None,
+			}
+		}
+	}
+
+	impl From<InvalidHex> for HexFrameError {
+		fn from(value: InvalidHex) -> Self {
+			Self::Hex(value)
+		}
+	}
+
+	impl From<MBusError> for HexFrameError {
+		fn from(value: MBusError) -> Self {
+			Self::Packet(value)
+		}
+	}
+
+	/// Parses a packet written as a hex string, see [`parse_hex`]
+	pub fn parse_hex_frame(data: &str) -> Result<Packet, HexFrameError> {
+		let bytes = parse_hex(data)?;
+		Ok(Packet::try_from(bytes.as_slice())?)
+	}
 
 	pub fn read_test_file(filename: &str) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
 		if filename.ends_with(".hex") {
 			let data = std::fs::read_to_string(filename)?;
 
-			data.trim()
-				.split(' ')
-				.map(|substr| u8::from_str_radix(substr, 16))
-				.collect::<Result<Vec<_>, _>>()
-				.map_err(|e| e.into())
+			parse_hex(&data).map_err(|e| e.into())
 		} else {
 			std::fs::read(filename).map_err(|e| e.into())
 		}
@@ -29,4 +109,56 @@ pub mod utils {
 			eprintln!("{}{}", " ".repeat(n), cause);
 		}
 	}
+
+	#[cfg(test)]
+	mod test_hex {
+		use rstest::rstest;
+
+		use super::{parse_hex, parse_hex_frame, HexFrameError, InvalidHex};
+		use crate::parse::link_layer::Packet;
+
+		#[rstest]
+		#[case::spaced("10 5B 01 5C 16")]
+		#[case::packed("105B015C16")]
+		#[case::lowercase("105b015c16")]
+		#[case::mixed(" 105B 015C16\n")]
+		fn test_parse_hex(#[case] input: &str) {
+			assert_eq!(parse_hex(input), Ok(vec![0x10, 0x5B, 0x01, 0x5C, 0x16]));
+		}
+
+		#[rstest]
+		#[case::odd_length("105B0", 4)]
+		#[case::split_byte("10 5 B", 3)]
+		#[case::not_hex("10 5G", 3)]
+		#[case::prefix("0x10", 0)]
+		fn test_invalid_hex(#[case] input: &str, #[case] position: usize) {
+			assert_eq!(parse_hex(input), Err(InvalidHex { position }));
+		}
+
+		#[rstest]
+		#[case::spaced("10 5B 01 5C 16")]
+		#[case::packed("105B015C16")]
+		fn test_parse_hex_frame(#[case] input: &str) {
+			let packet = parse_hex_frame(input).unwrap();
+
+			assert!(matches!(packet, Packet::Short { address: 1, .. }));
+		}
+
+		#[test]
+		fn test_parse_hex_frame_invalid() {
+			let input = "10 5B 01 XX 16";
+
+			assert_eq!(
+				parse_hex_frame(input).unwrap_err(),
+				HexFrameError::Hex(InvalidHex { position: 9 })
+			);
+		}
+
+		#[test]
+		fn test_parse_hex_frame_bad_packet() {
+			let err = parse_hex_frame("10 5B 01 5D 16").unwrap_err();
+
+			assert!(matches!(err, HexFrameError::Packet(_)));
+		}
+	}
 }
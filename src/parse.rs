@@ -4,8 +4,12 @@
 pub mod application_layer;
 pub mod error;
 pub mod link_layer;
+pub mod options;
+pub mod spans;
 pub mod transport_layer;
 pub mod types;
+pub mod warnings;
+pub mod wmbus;
 
 #[cfg(test)]
 mod test_parse {
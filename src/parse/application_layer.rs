@@ -1,7 +1,10 @@
 // Copyright 2024 Lexi Robinson
 // Licensed under the EUPL-1.2
 pub mod application;
+pub mod data;
 pub mod dib;
 pub mod frame;
 pub mod record;
 pub mod vib;
+
+pub use frame::format_signature;
@@ -13,7 +13,14 @@ use crate::parse::error::{MBResult, MBusError};
 
 use super::record::Record;
 
-#[derive(Debug)]
+/// The payload of an application error (CI 0x6E to 0x70), as per
+/// EN 13757-3:2018 Clause 10.
+///
+/// Not to be confused with
+/// [`ApplicationError`](crate::parse::transport_layer::header::ApplicationError)
+/// which is the two bit summary in the TPL header's status byte.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum ApplicationErrorMessage {
 	Unspecified,
 	CIFieldError,
@@ -87,7 +94,8 @@ impl ApplicationErrorMessage {
 	}
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum MessageApplication {
 	All,
 	UserData,        // Consumption
@@ -108,11 +116,12 @@ pub enum MessageApplication {
 	ManufacturerSpecific(u8),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ApplicationMessage {
 	// Yes, the `ApplicationMessage` type has a `message_application` field
-	message_application: MessageApplication,
-	block_number: u64,
+	pub message_application: MessageApplication,
+	pub block_number: u64,
 }
 
 impl ApplicationMessage {
@@ -171,3 +180,61 @@ impl ApplicationMessage {
 		.parse_next(input)
 	}
 }
+
+#[cfg(test)]
+mod test_application_error_message {
+	use winnow::error::{ErrorKind, StrContext};
+	use winnow::prelude::*;
+	use winnow::Bytes;
+
+	use super::ApplicationErrorMessage;
+	use crate::parse::application_layer::data::DataType;
+	use crate::parse::transport_layer::MBusMessage;
+
+	#[test]
+	fn test_dynamic_error() {
+		let input = Bytes::new(&[0xF0, 0x01, 0x13, 0x05]);
+
+		let result = ApplicationErrorMessage::parse.parse(input).unwrap();
+
+		let ApplicationErrorMessage::DynamicError(record) = result else {
+			panic!("expected a dynamic error, got {result:?}");
+		};
+		assert_eq!(record.data, DataType::Signed(5));
+	}
+
+	#[test]
+	fn test_reserved_code() {
+		let input = Bytes::new(&[0x07]);
+
+		let result = ApplicationErrorMessage::parse.parse(input).unwrap_err();
+
+		let err = result.inner();
+		assert_eq!(err.kind(), ErrorKind::Verify);
+		assert_eq!(
+			err.context().next(),
+			Some(&StrContext::Label("reserved error code"))
+		);
+	}
+
+	#[test]
+	fn test_via_message() {
+		let input = Bytes::new(&[0x70, 0xF0, 0x01, 0x13, 0x05]);
+
+		let result = MBusMessage::parse.parse(input).unwrap();
+
+		assert!(matches!(
+			result,
+			MBusMessage::ApplicationErrorFromDevice(_, ApplicationErrorMessage::DynamicError(_))
+		));
+
+		let input = Bytes::new(&[0x70, 0x07]);
+
+		let result = MBusMessage::parse.parse(input).unwrap_err();
+
+		assert_eq!(
+			result.inner().context().next(),
+			Some(&StrContext::Label("reserved error code"))
+		);
+	}
+}
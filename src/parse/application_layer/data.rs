@@ -0,0 +1,161 @@
+// Copyright 2023 Lexi Robinson
+// Licensed under the EUPL-1.2
+use crate::parse::types::date;
+use crate::parse::types::identification::EnhancedIdentification;
+
+use super::record::Record;
+
+// Note to self, enums always take up the maxmium size so there's no reason to
+// store any of the smaller integer types
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum DataType {
+	Unsigned(u64),                  // Type A, C
+	Signed(i64),                    // Type A, B
+	Real(f32),                      // Type H
+	Double(f64),                    // Non-standard 64 bit Type H
+	DateTimeF(date::TypeFDateTime), // Type F
+	DateTimeI(date::TypeIDateTime), // type I
+	Date(date::TypeGDate),          // type G
+	Time(date::TypeJTime),          // Type J
+	DST(date::TypeKDST),            // Type K
+	String(String),
+	ErrorValue(String),
+	Invalid(Vec<u8>),
+	VariableLengthNumber(Vec<u8>), // Type B, C over 8 bytes, little endian as sent
+	ManufacturerSpecific(Vec<u8>),
+	/// Binary data such as keys and passwords that isn't a number, in the
+	/// order it was sent
+	Opaque(Vec<u8>),
+	/// A Type D bitfield where each bit is a separate on/off state, starting
+	/// from the least significant bit of the first byte
+	Bitfield(Vec<bool>),
+	EnhancedIdentification(EnhancedIdentification),
+	/// The records nested inside a wireless M-Bus data container
+	Container(Vec<Record>),
+	None,
+}
+
+/// Prints the value on its own without any of the enum's structure, so a
+/// number is just the number and a date is `YYYY-MM-DD`. Data that isn't a
+/// value is printed as hex bytes in the order it was sent.
+impl std::fmt::Display for DataType {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::Unsigned(value) => write!(f, "{value}"),
+			Self::Signed(value) => write!(f, "{value}"),
+			Self::Real(value) => write!(f, "{value}"),
+			Self::Double(value) => write!(f, "{value}"),
+			Self::DateTimeF(value) => write!(f, "{value}"),
+			Self::DateTimeI(value) => write!(f, "{value}"),
+			Self::Date(value) => write!(f, "{value}"),
+			Self::Time(value) => write!(f, "{value}"),
+			Self::DST(value) => write!(f, "{value}"),
+			Self::String(value) => write!(f, "\"{value}\""),
+			Self::ErrorValue(value) => write!(f, "{value}"),
+			Self::Invalid(data)
+			| Self::VariableLengthNumber(data)
+			| Self::ManufacturerSpecific(data)
+			| Self::Opaque(data) => {
+				let hex: Vec<_> = data.iter().map(|byte| format!("{byte:02X}")).collect();
+				write!(f, "[{}]", hex.join(" "))
+			}
+			Self::Bitfield(bits) => {
+				write!(f, "0b")?;
+				for bit in bits.iter().rev() {
+					write!(f, "{}", u8::from(*bit))?;
+				}
+				Ok(())
+			}
+			Self::EnhancedIdentification(id) => write!(
+				f,
+				"{:08} {} v{} {}",
+				id.identifier, id.manufacturer, id.version, id.device_type
+			),
+			Self::Container(records) => write!(f, "[{} records]", records.len()),
+			Self::None => write!(f, "-"),
+		}
+	}
+}
+
+#[cfg(test)]
+mod test_display {
+	use rstest::rstest;
+
+	use super::DataType;
+	use crate::parse::transport_layer::header::DeviceType;
+	use crate::parse::transport_layer::manufacturer::ManufacturerCode;
+	use crate::parse::types::date::{TypeFDateTime, TypeGDate, TypeIDateTime, TypeJTime, TypeKDST};
+	use crate::parse::types::identification::EnhancedIdentification;
+
+	#[rstest]
+	#[case::unsigned(DataType::Unsigned(1234), "1234")]
+	#[case::signed(DataType::Signed(-56), "-56")]
+	#[case::real(DataType::Real(1.23), "1.23")]
+	#[case::double(DataType::Double(-0.5), "-0.5")]
+	#[case::date_time_f(
+		DataType::DateTimeF(TypeFDateTime {
+			minute: 26,
+			hour: 15,
+			day: 5,
+			month: 1,
+			year: 24,
+			hundred_year: 1,
+			in_dst: false,
+		}),
+		"2024-01-05 15:26"
+	)]
+	#[case::date_time_i(
+		DataType::DateTimeI(TypeIDateTime {
+			second: 9,
+			minute: 8,
+			hour: 7,
+			day: 13,
+			month: 3,
+			year: 24,
+			day_of_week: 3,
+			week: 11,
+			in_dst: false,
+			leap_year: true,
+			dst_offset: 0,
+		}),
+		"2024-03-13 07:08:09"
+	)]
+	#[case::date(DataType::Date(TypeGDate { day: 13, month: 3, year: 24 }), "2024-03-13")]
+	#[case::last_century(DataType::Date(TypeGDate { day: 31, month: 12, year: 99 }), "1999-12-31")]
+	#[case::time(DataType::Time(TypeJTime { second: 5, minute: 4, hour: 3 }), "03:04:05")]
+	#[case::dst(
+		DataType::DST(TypeKDST {
+			starts_hour: 2,
+			starts_day: 31,
+			starts_month: 3,
+			ends_day: 27,
+			ends_month: 10,
+			enable: true,
+			dst_deviation: 1,
+			local_deviation: 0,
+		}),
+		"03-31 02:00 to 10-27 (+1h)"
+	)]
+	#[case::string(DataType::String("text".to_owned()), "\"text\"")]
+	#[case::error_value(DataType::ErrorValue("12-4".to_owned()), "12-4")]
+	#[case::invalid(DataType::Invalid(vec![0xFF, 0x0A]), "[FF 0A]")]
+	#[case::variable_length_number(DataType::VariableLengthNumber(vec![0x01, 0x02]), "[01 02]")]
+	#[case::manufacturer_specific(DataType::ManufacturerSpecific(vec![0x1F]), "[1F]")]
+	#[case::opaque(DataType::Opaque(vec![]), "[]")]
+	#[case::bitfield(DataType::Bitfield(vec![true, false, false, true, true]), "0b11001")]
+	#[case::enhanced_identification(
+		DataType::EnhancedIdentification(EnhancedIdentification {
+			identifier: 12345678,
+			manufacturer: ManufacturerCode::from_u16_lenient(0x2C2D),
+			version: 8,
+			device_type: DeviceType::GasMeter,
+		}),
+		"12345678 KAM v8 gas meter"
+	)]
+	#[case::container(DataType::Container(vec![]), "[0 records]")]
+	#[case::none(DataType::None, "-")]
+	fn test_display(#[case] value: DataType, #[case] expected: &str) {
+		assert_eq!(value.to_string(), expected);
+	}
+}
@@ -5,19 +5,54 @@
 use crate::parse::error::MBResult;
 use crate::parse::types::BitsInput;
 use winnow::binary::bits;
-use winnow::error::{ErrMode, ParserError, StrContext};
+use winnow::combinator::peek;
+use winnow::error::{AddContext, ErrMode, ErrorKind, ParserError, StrContext};
+use winnow::stream::Stream;
 use winnow::Parser;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum RawDataType {
 	None,
 	Binary(usize),
-	Real,
+	/// EN 13757-3 only defines a 32 bit real, but some meters send 64 bit
+	/// doubles with the same data field code so the width is tracked. See
+	/// [`crate::parse::options::ParseOptions::double_reals`].
+	Real(usize),
 	BCD(usize),
 	LVAR,
+	/// The record is asking for this value to be sent in the response, so it
+	/// doesn't have any data
+	SelectionForReadout,
 }
 
 impl RawDataType {
+	/// How many bytes of data follow the record header, if it's knowable
+	/// without parsing the data
+	pub fn data_width(&self) -> Option<usize> {
+		match self {
+			Self::None | Self::SelectionForReadout => Some(0),
+			Self::Binary(n) | Self::Real(n) | Self::BCD(n) => Some(*n),
+			Self::LVAR => None,
+		}
+	}
+
+	/// The data field code in the bottom nibble of the DIF, if this type can
+	/// be sent
+	fn code(&self) -> Option<u8> {
+		Some(match self {
+			Self::None => 0b0000,
+			Self::Binary(n @ 1..=4) => *n as u8,
+			Self::Binary(6) => 0b0110,
+			Self::Binary(8) => 0b0111,
+			Self::Real(4 | 8) => 0b0101,
+			Self::SelectionForReadout => 0b1000,
+			Self::BCD(n @ (1..=4 | 6)) => 0b1000 + *n as u8,
+			Self::LVAR => 0b1101,
+			_ => return None,
+		})
+	}
+
 	fn parse(input: &mut BitsInput<'_>) -> MBResult<Self> {
 		bits::take(4_usize)
 			.verify_map(|value: u8| match value {
@@ -27,9 +62,9 @@ impl RawDataType {
 				0b1001 | 0b1010 | 0b1011 | 0b1100 | 0b1110 => {
 					Some(Self::BCD((value - 0b1000) as usize))
 				}
-				0b0101 => Some(Self::Real),
+				0b0101 => Some(Self::Real(4)),
 				0b1101 => Some(Self::LVAR),
-				0b1000 => None, // TODO: I have no idea what "Selection for readout" means
+				0b1000 => Some(Self::SelectionForReadout),
 				0b1111 => None, // "This should never happen" but triggering a parse error is better than crashing
 				_ => unreachable!(),
 			})
@@ -37,7 +72,8 @@ impl RawDataType {
 	}
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum DataFunction {
 	InstantaneousValue,
 	MaximumValue,
@@ -45,7 +81,27 @@ pub enum DataFunction {
 	ValueDuringErrorState,
 }
 
+impl std::fmt::Display for DataFunction {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.write_str(match self {
+			Self::InstantaneousValue => "instantaneous value",
+			Self::MaximumValue => "maximum value",
+			Self::MinimumValue => "minimum value",
+			Self::ValueDuringErrorState => "value during error state",
+		})
+	}
+}
+
 impl DataFunction {
+	fn code(&self) -> u8 {
+		match self {
+			Self::InstantaneousValue => 0b00,
+			Self::MaximumValue => 0b01,
+			Self::MinimumValue => 0b10,
+			Self::ValueDuringErrorState => 0b11,
+		}
+	}
+
 	fn parse(input: &mut BitsInput<'_>) -> MBResult<Self> {
 		bits::take(2_usize)
 			.map(|value: u8| match value {
@@ -59,7 +115,8 @@ impl DataFunction {
 	}
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct DataInfoBlock {
 	pub raw_type: RawDataType,
 	pub function: DataFunction,
@@ -75,10 +132,70 @@ pub struct DataInfoBlock {
 	/// If you know what this means and what I should be doing with this
 	/// information, please let me know and I'll update the code.
 	pub is_obis: bool,
+	/// The DIF and DIFE bytes this block was parsed from
+	pub raw: Vec<u8>,
 }
 
 impl DataInfoBlock {
 	pub fn parse(input: &mut BitsInput<'_>) -> MBResult<Self> {
+		Self::parse_inner
+			.with_recognized()
+			.map(|(dib, (raw, _, _))| Self {
+				raw: raw.to_vec(),
+				..dib
+			})
+			.parse_next(input)
+	}
+
+	/// Builds the DIF and DIFE bytes for this block from its fields, using as
+	/// few DIFEs as possible. Returns `None` if the fields can't be
+	/// represented.
+	pub fn encode(&self) -> Option<Vec<u8>> {
+		let mut storage = self.storage >> 1;
+		let mut tariff = self.tariff;
+		let mut device = self.device;
+
+		let mut result = vec![
+			u8::from(self.storage & 1 == 1) << 6
+				| self.function.code() << 4
+				| self.raw_type.code()?,
+		];
+		while storage != 0 || tariff != 0 || device != 0 {
+			if result.len() > 10 {
+				return None;
+			}
+			result.push(
+				((device & 0b1) as u8) << 6
+					| ((tariff & 0b11) as u8) << 4
+					| (storage & 0b1111) as u8,
+			);
+			storage >>= 4;
+			tariff >>= 2;
+			device >>= 1;
+		}
+		if self.is_obis {
+			result.push(0x00);
+		}
+
+		// Every byte but the last needs the extension bit set
+		let last = result.len() - 1;
+		for byte in &mut result[..last] {
+			*byte |= 0b1000_0000;
+		}
+		Some(result)
+	}
+
+	fn parse_inner(input: &mut BitsInput<'_>) -> MBResult<Self> {
+		// A data field of 1111 makes this one of the EN 13757-3 special
+		// functions, such as the manufacturer specific data markers (0x0F and
+		// 0x1F) or an idle filler (0x2F), which the frame deals with. None of
+		// them start a record.
+		peek(bits::take::<_, u8, _, _>(8_usize))
+			.verify(|dif| dif & 0x0F != 0x0F)
+			.context(StrContext::Label("special function DIF"))
+			.void()
+			.parse_next(input)?;
+
 		let (mut extension, mut storage, function, raw_type): (bool, u64, _, _) = (
 			bits::bool,
 			bits::take(1_usize),
@@ -95,7 +212,13 @@ impl DataInfoBlock {
 		let mut i = 1;
 		while extension {
 			if i > 10 {
-				return Err(ErrMode::assert(input, "Packet has more than 10 DIFEs!"));
+				return Err(
+					ErrMode::from_error_kind(input, ErrorKind::Verify).add_context(
+						input,
+						&input.checkpoint(),
+						StrContext::Label("too many DIFEs"),
+					),
+				);
 			}
 
 			let mut dife_device: u16;
@@ -117,9 +240,12 @@ impl DataInfoBlock {
 				break;
 			}
 
-			dife_device <<= i;
-			dife_tariff <<= 2 * i;
-			dife_storage <<= 4 * i;
+			// The DIF has the lowest bit of the storage number, and each
+			// DIFE adds more significant bits on top of what came before
+			let shift = i - 1;
+			dife_device <<= shift;
+			dife_tariff <<= 2 * shift;
+			dife_storage <<= 1 + 4 * shift;
 			i += 1;
 
 			device += dife_device;
@@ -134,6 +260,76 @@ impl DataInfoBlock {
 			tariff,
 			device,
 			is_obis,
+			raw: Vec::new(),
 		})
 	}
 }
+
+#[cfg(test)]
+mod test_data_info_block {
+	use rstest::rstest;
+	use winnow::binary::bits;
+	use winnow::prelude::*;
+	use winnow::Bytes;
+
+	use super::DataInfoBlock;
+
+	#[rstest]
+	#[case::dif_only(&[0x44], 1, 0, 0)]
+	#[case::one_dife(&[0xC4, 0x7F], 0b1_1111, 0b11, 1)]
+	#[case::two_difes(&[0x84, 0xD0, 0x23], 96, 0b1001, 1)]
+	fn test_storage_tariff_device(
+		#[case] input: &[u8],
+		#[case] storage: u64,
+		#[case] tariff: u32,
+		#[case] device: u16,
+	) {
+		let result = bits::bits(DataInfoBlock::parse)
+			.parse(Bytes::new(input))
+			.unwrap();
+
+		assert_eq!(result.storage, storage, "storage");
+		assert_eq!(result.tariff, tariff, "tariff");
+		assert_eq!(result.device, device, "device");
+	}
+
+	#[rstest]
+	#[case::dif_only(&[0x04])]
+	#[case::storage(&[0x44])]
+	#[case::one_dife(&[0xC4, 0x7F])]
+	#[case::two_difes(&[0x84, 0xD0, 0x23])]
+	#[case::device(&[0x84, 0x80, 0x40])]
+	#[case::maximum(&[0x14])]
+	#[case::bcd(&[0x0C])]
+	#[case::lvar(&[0x0D])]
+	#[case::obis(&[0x84, 0x90, 0x00])]
+	fn test_encode(#[case] input: &[u8]) {
+		let result = bits::bits(DataInfoBlock::parse)
+			.parse(Bytes::new(input))
+			.unwrap();
+
+		assert_eq!(result.encode().as_deref(), Some(input));
+	}
+
+	#[rstest]
+	#[case::msd_marker(&[0x0F, 0x04])]
+	#[case::msd_plus_marker(&[0x1F, 0x04])]
+	#[case::idle_filler(&[0x2F, 0x04])]
+	#[case::global_readout(&[0x7F, 0x04])]
+	fn test_special_functions(#[case] input: &[u8]) {
+		bits::bits(DataInfoBlock::parse)
+			.parse_next(&mut Bytes::new(input))
+			.unwrap_err();
+	}
+
+	#[test]
+	fn test_too_many_difes() {
+		let mut input = vec![0x84];
+		input.extend([0x80; 10]);
+		input.push(0x00);
+
+		bits::bits(DataInfoBlock::parse)
+			.parse(Bytes::new(&input))
+			.unwrap_err();
+	}
+}
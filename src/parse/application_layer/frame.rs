@@ -1,18 +1,53 @@
 // Copyright 2024 Lexi Robinson
 // Licensed under the EUPL-1.2
 
+use std::borrow::Cow;
+
 use winnow::binary;
 use winnow::combinator::{alt, eof, repeat, repeat_till};
 use winnow::error::StrContext;
 use winnow::prelude::*;
+use winnow::stream::Stream;
 use winnow::Bytes;
 
+use super::dib::DataInfoBlock;
 use super::record::Record;
-use crate::parse::error::MBResult;
+use super::vib::{Quantity, ValueInfoBlock};
+use crate::parse::application_layer::data::DataType;
+use crate::parse::error::{MBResult, MBusError};
+use crate::parse::options::ParseOptions;
+use crate::parse::wmbus::crc16;
 
-const IDLE_FILLER: u8 = 0x2F;
+pub(crate) const IDLE_FILLER: u8 = 0x2F;
+const MSD_MARKER: u8 = 0x0F;
+const MSD_PLUS_MARKER: u8 = 0x1F;
 
+/// A record that couldn't be parsed by [`Frame::parse_lenient`]
 #[derive(Debug)]
+pub struct RecordError {
+	/// Where in the frame data the broken record started
+	pub offset: usize,
+	/// Everything that was skipped over to get back to a parseable record
+	pub skipped: Vec<u8>,
+	pub error: MBusError,
+}
+
+/// A numeric record from a frame with everything needed to make sense of it
+#[derive(Debug, PartialEq)]
+pub struct Reading<'a> {
+	pub quantity: Quantity,
+	/// The value in the units given by `unit`, see [`Record::scaled_value`]
+	pub value: f64,
+	pub unit: Option<Cow<'a, str>>,
+	pub storage: u64,
+	/// The date or date/time record in the frame with the same storage number
+	/// as this reading, if there is one
+	pub timestamp: Option<&'a DataType>,
+	pub record: &'a Record,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Frame {
 	pub records: Vec<Record>,
 	pub more_data_follows: bool,
@@ -21,11 +56,19 @@ pub struct Frame {
 
 impl Frame {
 	pub fn parse(input: &mut &Bytes) -> MBResult<Self> {
+		Self::parse_with(ParseOptions::default()).parse_next(input)
+	}
+
+	pub fn parse_with<'a>(options: ParseOptions) -> impl Parser<&'a Bytes, Self, MBusError> {
+		move |input: &mut &'a Bytes| Self::parse_records(options, input)
+	}
+
+	fn parse_records(options: ParseOptions, input: &mut &Bytes) -> MBResult<Self> {
 		let idle_filler = repeat::<_, _, (), _, _>(1.., IDLE_FILLER)
 			.context(StrContext::Label("idle filler"))
 			.map(|_| None);
 
-		let record = Record::parse
+		let record = Record::parse_with(options)
 			.context(StrContext::Label("frame record"))
 			.map(Some);
 
@@ -34,9 +77,9 @@ impl Frame {
 			// more records
 			eof.map(|_| false),
 			// Or it can have one of the following bytes
-			0x1F.map(|_| true),
+			MSD_PLUS_MARKER.map(|_| true),
 			// Though it's not legal for this one to exist without some data after it
-			0x0F.map(|_| false),
+			MSD_MARKER.map(|_| false),
 		))
 		.context(StrContext::Label("end of records marker"));
 
@@ -61,3 +104,563 @@ impl Frame {
 			.parse_next(input)
 	}
 }
+
+/// EN 13757-3:2018 Annex G: A compact frame only sends the data fields of a
+/// full frame. The DIFs and VIFs have to be looked up from a format frame
+/// sent earlier with the same format signature, so the data can't be parsed
+/// on its own.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CompactFrame {
+	pub format_signature: u16,
+	/// The CRC of the full frame this was compacted from
+	pub full_frame_crc: u16,
+	pub data: Vec<u8>,
+}
+
+impl CompactFrame {
+	pub fn parse(input: &mut &Bytes) -> MBResult<Self> {
+		(
+			binary::le_u16.context(StrContext::Label("format signature")),
+			binary::le_u16.context(StrContext::Label("full frame CRC")),
+			repeat(0.., binary::u8).context(StrContext::Label("compact frame data")),
+		)
+			.map(|(format_signature, full_frame_crc, data)| Self {
+				format_signature,
+				full_frame_crc,
+				data,
+			})
+			.parse_next(input)
+	}
+}
+
+/// EN 13757-3:2018 Annex G: The format signature of a full frame is the CRC
+/// of the DIF, DIFE, VIF and VIFE bytes of all of its records in order. A
+/// compact frame with the same signature has the same records, so it can be
+/// parsed with the DIBs and VIBs of a full frame or format frame.
+pub fn format_signature<'a>(
+	records: impl IntoIterator<Item = (&'a DataInfoBlock, &'a ValueInfoBlock)>,
+) -> u16 {
+	let format: Vec<u8> = records
+		.into_iter()
+		.flat_map(|(dib, vib)| dib.raw.iter().chain(&vib.raw))
+		.copied()
+		.collect();
+	crc16(&format)
+}
+
+impl Frame {
+	/// The format signature a compact version of this frame would have. See
+	/// [`format_signature`].
+	pub fn format_signature(&self) -> u16 {
+		format_signature(self.records.iter().map(|record| (&record.dib, &record.vib)))
+	}
+}
+
+/// The data a device sent in response to a request
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum DeviceResponse {
+	Full(Frame),
+	Compact(CompactFrame),
+}
+
+impl DeviceResponse {
+	/// The parsed frame, if the device sent a full one
+	pub fn frame(&self) -> Option<&Frame> {
+		match self {
+			Self::Full(frame) => Some(frame),
+			Self::Compact(_) => None,
+		}
+	}
+}
+
+impl Frame {
+	/// Every record with a numeric value, alongside its unit and the time it
+	/// was recorded at
+	pub fn readings(&self) -> impl Iterator<Item = Reading<'_>> {
+		self.records.iter().filter_map(|record| {
+			Some(Reading {
+				quantity: record.quantity(),
+				value: record.scaled_value()?,
+				unit: record.unit(),
+				storage: record.storage(),
+				timestamp: self.timestamp_for(record.storage()),
+				record,
+			})
+		})
+	}
+
+	/// The meter's current date and time, which is the date record in storage
+	/// 0. Readings from the other storage numbers were taken at some point
+	/// before this.
+	pub fn datetime(&self) -> Option<&DataType> {
+		self.timestamp_for(0)
+	}
+
+	fn timestamp_for(&self, storage: u64) -> Option<&DataType> {
+		self.records
+			.iter()
+			.filter(|record| record.storage() == storage)
+			.map(|record| &record.data)
+			.find(|data| {
+				matches!(
+					data,
+					DataType::DateTimeF(_) | DataType::DateTimeI(_) | DataType::Date(_)
+				)
+			})
+	}
+}
+
+impl Frame {
+	/// Parses as many records as possible out of a frame, skipping over any
+	/// that are broken rather than giving up on the entire frame.
+	///
+	/// Parsing stops after the manufacturer specific data marker, so any
+	/// manufacturer specific data will be left in `input`.
+	pub fn parse_lenient(input: &mut &Bytes) -> (Vec<Record>, Vec<RecordError>) {
+		Self::parse_lenient_with(ParseOptions::default(), input)
+	}
+
+	/// The same as [`Frame::parse_lenient`], but parsing each record with
+	/// `options`. Any record the options reject is skipped like a broken one.
+	pub fn parse_lenient_with(
+		options: ParseOptions,
+		input: &mut &Bytes,
+	) -> (Vec<Record>, Vec<RecordError>) {
+		let start_len = input.len();
+		let mut records = Vec::new();
+		let mut errors: Vec<RecordError> = Vec::new();
+		// Set while we're skipping bytes after a bad record
+		let mut resyncing = false;
+
+		while let Some(&next) = input.first() {
+			if next == IDLE_FILLER {
+				input.next_token();
+				resyncing = false;
+				continue;
+			} else if !resyncing && matches!(next, MSD_MARKER | MSD_PLUS_MARKER) {
+				input.next_token();
+				break;
+			}
+
+			let offset = start_len - input.len();
+			let checkpoint = input.checkpoint();
+			let error = match Record::parse_with(options).parse_next(input) {
+				Ok(record) => {
+					records.push(record);
+					resyncing = false;
+					continue;
+				}
+				Err(e) => e.into_inner().unwrap_or_default(),
+			};
+			input.reset(&checkpoint);
+
+			let skipped = Self::skip_bad_record(options, input);
+			if resyncing {
+				if let Some(last) = errors.last_mut() {
+					last.skipped.extend(skipped);
+				}
+			} else {
+				errors.push(RecordError {
+					offset,
+					skipped,
+					error,
+				});
+				resyncing = true;
+			}
+		}
+
+		(records, errors)
+	}
+
+	/// If the record's header is intact and says how long the data is, skip
+	/// the whole record. Otherwise all we can do is skip a byte and try again.
+	fn skip_bad_record(options: ParseOptions, input: &mut &Bytes) -> Vec<u8> {
+		let checkpoint = input.checkpoint();
+		let header = binary::bits::bits::<_, _, MBusError, _, _>((
+			DataInfoBlock::parse,
+			ValueInfoBlock::parse_with(options),
+		))
+		.with_recognized()
+		.parse_next(input);
+
+		if let Ok(((dib, _), header)) = header {
+			if let Some(width) = dib.raw_type.data_width() {
+				if input.len() >= width {
+					let mut skipped = header.to_vec();
+					skipped.extend_from_slice(input.next_slice(width));
+					return skipped;
+				}
+			}
+		}
+
+		input.reset(&checkpoint);
+		input.next_slice(1).to_vec()
+	}
+}
+
+#[cfg(test)]
+mod test_frame_lenient {
+	use winnow::error::StrContext;
+	use winnow::Bytes;
+
+	use super::Frame;
+	use crate::parse::application_layer::data::DataType;
+	use crate::parse::options::ParseOptions;
+
+	#[test]
+	fn test_bad_middle_record() {
+		let data = [
+			0x02, 0x13, 0x05, 0x00, // Good record
+			0x02, 0x6C, 0xFF, 0xFF, // Explicitly invalid Type G date
+			0x02, 0x13, 0x07, 0x00, // Good record
+		];
+		let mut input = Bytes::new(&data);
+
+		let (records, errors) = Frame::parse_lenient(&mut input);
+
+		assert_eq!(records.len(), 2);
+		assert_eq!(records[0].data, DataType::Signed(5));
+		assert_eq!(records[1].data, DataType::Signed(7));
+		assert_eq!(errors.len(), 1);
+		assert_eq!(errors[0].offset, 4);
+		assert_eq!(errors[0].skipped, [0x02, 0x6C, 0xFF, 0xFF]);
+	}
+
+	#[test]
+	fn test_bad_header() {
+		let data = [
+			0x02, 0x13, 0x05, 0x00, // Good record
+			0x8F, 0x0F, // DIF with an invalid data field
+			0x2F, // Idle filler
+			0x02, 0x13, 0x07, 0x00, // Good record
+			0x0F, 0x01, 0x02, // Manufacturer specific data
+		];
+		let mut input = Bytes::new(&data);
+
+		let (records, errors) = Frame::parse_lenient(&mut input);
+
+		assert_eq!(records.len(), 2);
+		assert_eq!(errors.len(), 1);
+		assert_eq!(errors[0].offset, 4);
+		assert_eq!(errors[0].skipped, [0x8F, 0x0F]);
+		assert_eq!(**input, [0x01, 0x02]);
+	}
+
+	#[test]
+	fn test_bad_vif() {
+		let data = [
+			0x02, 0x13, 0x05, 0x00, // Good record
+			0x02, 0xFD, 0x7D, 0xFF, 0xFF, // Second level VIF extension with no VIFE
+			0x02, 0x13, 0x07, 0x00, // Good record
+		];
+		let mut input = Bytes::new(&data);
+
+		let (records, errors) = Frame::parse_lenient(&mut input);
+
+		assert_eq!(records.len(), 2);
+		assert_eq!(records[0].data, DataType::Signed(5));
+		assert_eq!(records[1].data, DataType::Signed(7));
+		assert_eq!(errors.len(), 1);
+		assert_eq!(errors[0].offset, 4);
+		assert_eq!(errors[0].skipped, [0x02, 0xFD, 0x7D, 0xFF, 0xFF]);
+		assert!(errors[0].error.context().any(|context| {
+			*context == StrContext::Label("vife missing for vif extension level 2")
+		}));
+	}
+
+	#[test]
+	fn test_options() {
+		let data = [
+			0x02, 0x13, 0x05, 0x00, // Good record
+			0x04, 0x6D, 0x00, 0x00, 0x01, 0x0F, // Type F date in month 15
+		];
+		let options = ParseOptions {
+			strict_dates: true,
+			..ParseOptions::default()
+		};
+
+		let (records, errors) = Frame::parse_lenient(&mut Bytes::new(&data));
+		assert_eq!(records.len(), 2);
+		assert!(errors.is_empty());
+
+		let (records, errors) = Frame::parse_lenient_with(options, &mut Bytes::new(&data));
+		assert_eq!(records.len(), 1);
+		assert_eq!(errors.len(), 1);
+		assert_eq!(errors[0].offset, 4);
+		assert_eq!(errors[0].skipped, &data[4..]);
+	}
+}
+
+#[cfg(test)]
+mod test_idle_filler {
+	use rstest::rstest;
+	use winnow::prelude::*;
+	use winnow::Bytes;
+
+	use super::Frame;
+	use crate::parse::application_layer::data::DataType;
+
+	#[test]
+	fn test_fillers_everywhere() {
+		let data = [
+			0x2F, 0x2F, // Leading fillers
+			0x02, 0x13, 0x05, 0x00, // Record
+			0x2F, 0x2F, 0x2F, // Several fillers in a row
+			0x02, 0x13, 0x07, 0x00, // Record
+			0x2F, // Filler before the marker
+			0x1F, // More data follows
+			0x2F, 0x2F, // Fillers after the marker are manufacturer data
+		];
+
+		let frame = Frame::parse.parse(Bytes::new(&data)).unwrap();
+
+		let values: Vec<_> = frame.records.iter().map(|record| &record.data).collect();
+		assert_eq!(values, [&DataType::Signed(5), &DataType::Signed(7)]);
+		assert_eq!(
+			frame
+				.records
+				.iter()
+				.map(|record| record.length)
+				.collect::<Vec<_>>(),
+			[4, 4]
+		);
+		assert!(frame.more_data_follows);
+		assert_eq!(frame.manufacturer_specific, [0x2F, 0x2F]);
+	}
+
+	#[rstest]
+	#[case::msd(0x0F, false)]
+	#[case::msd_plus(0x1F, true)]
+	fn test_msd_marker(#[case] marker: u8, #[case] more_data_follows: bool) {
+		let data = [
+			0x02, 0x13, 0x05, 0x00, // Record
+			marker, 0x02, 0x13, 0x07, 0x00, // Looks like a record but isn't
+		];
+
+		let frame = Frame::parse.parse(Bytes::new(&data)).unwrap();
+
+		assert_eq!(frame.records.len(), 1);
+		assert_eq!(frame.more_data_follows, more_data_follows);
+		assert_eq!(frame.manufacturer_specific, [0x02, 0x13, 0x07, 0x00]);
+	}
+
+	#[test]
+	fn test_only_fillers() {
+		let frame = Frame::parse.parse(Bytes::new(&[0x2F, 0x2F, 0x2F])).unwrap();
+
+		assert!(frame.records.is_empty());
+		assert!(!frame.more_data_follows);
+	}
+}
+
+#[cfg(test)]
+mod test_frame_clone {
+	use winnow::prelude::*;
+	use winnow::Bytes;
+
+	use super::DeviceResponse;
+	use crate::parse::link_layer::Packet;
+	use crate::parse::transport_layer::MBusMessage;
+	use crate::utils::read_test_file;
+
+	#[test]
+	fn test_clone_parsed_frame() {
+		let data = read_test_file("./libmbus_test_data/test-frames/oms_frame3.hex").unwrap();
+		let packet = Packet::parse.parse(Bytes::new(&data)).unwrap();
+		let Packet::Long {
+			message: MBusMessage::ResponseFromDevice(_, DeviceResponse::Full(frame)),
+			..
+		} = packet
+		else {
+			panic!("Expected a response from device, got {packet:?}");
+		};
+
+		let cached = frame.clone();
+
+		assert_eq!(cached, frame);
+		assert_eq!(cached.records[0].clone(), frame.records[0]);
+	}
+}
+
+#[cfg(test)]
+mod test_frame_datetime {
+	use winnow::prelude::*;
+	use winnow::Bytes;
+
+	use super::{DeviceResponse, Frame};
+	use crate::parse::application_layer::data::DataType;
+	use crate::parse::link_layer::Packet;
+	use crate::parse::transport_layer::MBusMessage;
+	use crate::parse::types::date::TypeFDateTime;
+	use crate::utils::read_test_file;
+
+	#[test]
+	fn test_type_f() {
+		let data =
+			read_test_file("./libmbus_test_data/test-frames/kamstrup_multical_601.hex").unwrap();
+		let packet = Packet::parse.parse(Bytes::new(&data)).unwrap();
+		let Packet::Long {
+			message: MBusMessage::ResponseFromDevice(_, DeviceResponse::Full(frame)),
+			..
+		} = packet
+		else {
+			panic!("Expected a response from device, got {packet:?}");
+		};
+
+		let Some(DataType::DateTimeF(TypeFDateTime {
+			minute,
+			hour,
+			day,
+			month,
+			..
+		})) = frame.datetime()
+		else {
+			panic!("Expected a Type F date/time, got {:?}", frame.datetime());
+		};
+		assert_eq!((*minute, *hour, *day, *month), (26, 15, 5, 1));
+	}
+
+	#[test]
+	fn test_only_historic_dates() {
+		let data = [
+			0x04, 0x13, 0x01, 0x00, 0x00, 0x00, // 1 l, storage 0
+			0x42, 0x6C, 0x8C, 0x11, // Type G date, storage 1
+		];
+
+		let frame = Frame::parse.parse(Bytes::new(&data)).unwrap();
+
+		assert_eq!(frame.datetime(), None);
+	}
+}
+
+#[cfg(test)]
+mod test_frame_readings {
+	use winnow::prelude::*;
+	use winnow::Bytes;
+
+	use super::Frame;
+	use crate::parse::application_layer::data::DataType;
+	use crate::parse::application_layer::vib::Quantity;
+	use crate::parse::types::date::{TypeFDateTime, TypeGDate};
+
+	#[test]
+	fn test_readings() {
+		let data = [
+			0x04, 0x13, 0x01, 0x00, 0x00, 0x00, // 1 l, storage 0
+			0x04, 0x6D, 0x32, 0x37, 0x1F, 0x15, // Type F date/time, storage 0
+			0x44, 0x13, 0x02, 0x00, 0x00, 0x00, // 2 l, storage 1
+			0x42, 0x6C, 0x8C, 0x11, // Type G date, storage 1
+			0x02, 0xFD, 0x17, 0x00, 0x00, // Error flags, which are a bitfield not a reading
+		];
+
+		let frame = Frame::parse.parse(Bytes::new(&data)).unwrap();
+
+		let readings: Vec<_> = frame
+			.readings()
+			.map(|reading| {
+				(
+					reading.quantity,
+					reading.value,
+					reading.unit.as_deref().map(str::to_owned),
+					reading.storage,
+					reading.timestamp.map(|data| match data {
+						DataType::DateTimeF(TypeFDateTime { day, month, .. }) => (*day, *month),
+						DataType::Date(TypeGDate { day, month, .. }) => (*day, *month),
+						other => panic!("Unexpected timestamp {other:?}"),
+					}),
+				)
+			})
+			.collect();
+		assert_eq!(
+			readings,
+			[
+				(
+					Quantity::Volume,
+					0.001,
+					Some("m³".to_owned()),
+					0,
+					Some((31, 5))
+				),
+				(
+					Quantity::Volume,
+					0.002,
+					Some("m³".to_owned()),
+					1,
+					Some((12, 1))
+				),
+			]
+		);
+	}
+}
+
+#[cfg(test)]
+mod test_format_signature {
+	use winnow::prelude::*;
+	use winnow::Bytes;
+
+	use super::{format_signature, CompactFrame, Frame};
+	use crate::parse::wmbus::crc16;
+
+	fn parse(data: &[u8]) -> Frame {
+		Frame::parse.parse(Bytes::new(data)).unwrap()
+	}
+
+	#[test]
+	fn test_covers_dibs_and_vibs() {
+		let frame = parse(&[
+			0x84, 0x10, 0x13, 0x01, 0x00, 0x00, 0x00, // Volume, tariff 1
+			0x02, 0xFD, 0x17, 0x00, 0x00, // Error flags
+		]);
+
+		let expected = crc16(&[0x84, 0x10, 0x13, 0x02, 0xFD, 0x17]);
+		assert_eq!(frame.format_signature(), expected);
+
+		let blocks = frame
+			.records
+			.iter()
+			.map(|record| (&record.dib, &record.vib));
+		assert_eq!(format_signature(blocks), expected);
+	}
+
+	#[test]
+	fn test_known_format() {
+		// The Kamstrup Multical 21 record layout, which wmbusmeters lists
+		// under the format signature 0xA8ED in its table of compact frame
+		// formats
+		let frame = parse(&[
+			0x02, 0xFF, 0x20, 0x00, 0x00, // Info codes
+			0x04, 0x13, 0x01, 0x00, 0x00, 0x00, // Total volume
+			0x44, 0x13, 0x02, 0x00, 0x00, 0x00, // Target volume
+			0x61, 0x5B, 0x0A, // Minimum flow temperature
+			0x61, 0x67, 0x14, // Minimum external temperature
+		]);
+		let compact = CompactFrame::parse
+			.parse(Bytes::new(&[
+				0xED, 0xA8, // Format signature
+				0x00, 0x00, // Full frame CRC
+				0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x0A, 0x14,
+			]))
+			.unwrap();
+
+		assert_eq!(frame.format_signature(), 0xA8ED);
+		assert_eq!(compact.format_signature, frame.format_signature());
+	}
+
+	#[test]
+	fn test_ignores_data() {
+		let first = parse(&[0x04, 0x13, 0x01, 0x00, 0x00, 0x00, 0x2F, 0x2F]);
+		let second = parse(&[0x04, 0x13, 0x78, 0x56, 0x34, 0x12]);
+
+		assert_eq!(first.format_signature(), second.format_signature());
+	}
+
+	#[test]
+	fn test_layout_changes_signature() {
+		let volume = parse(&[0x04, 0x13, 0x01, 0x00, 0x00, 0x00]);
+		let energy = parse(&[0x04, 0x06, 0x01, 0x00, 0x00, 0x00]);
+
+		assert_ne!(volume.format_signature(), energy.format_signature());
+	}
+}
@@ -1,49 +1,510 @@
 // Copyright 2024 Lexi Robinson
 // Licensed under the EUPL-1.2
 
+use std::borrow::Cow;
+use std::time::Duration;
+
 use libmbus_macros::vif;
 use winnow::binary;
-use winnow::combinator::{alt, repeat};
-use winnow::error::StrContext;
+use winnow::combinator::{alt, eof, preceded, repeat, terminated};
+use winnow::error::{AddContext, ErrMode, ErrorKind, ParserError, StrContext};
 use winnow::prelude::*;
+use winnow::stream::Stream;
+use winnow::token::take;
 use winnow::Bytes;
 
-use crate::parse::error::{MBResult, MBusError};
-use crate::parse::types::date::{TypeFDateTime, TypeGDate, TypeIDateTime, TypeJTime, TypeKDST};
+use crate::parse::error::{parse_within, MBResult, MBusError};
+use crate::parse::options::ParseOptions;
+use crate::parse::types::date::{
+	TypeFDateTime, TypeGDate, TypeIDateTime, TypeJTime, TypeKDST, Weekday, EVERY_YEAR,
+};
+use crate::parse::types::identification::EnhancedIdentification;
 use crate::parse::types::number::{
-	parse_bcd, parse_binary_signed, parse_binary_unsigned, parse_invalid_bcd, parse_real,
+	decode_le_signed, decode_le_unsigned, encode_bcd, encode_binary_signed, encode_binary_unsigned,
+	encode_invalid_bcd, parse_bcd, parse_binary_signed, parse_binary_unsigned, parse_double,
+	parse_invalid_bcd, parse_negative_bcd, parse_positive_bcd, parse_real,
 };
-use crate::parse::types::string::parse_latin1;
-use crate::parse::types::DataType;
+use crate::parse::types::string::{encode_latin1, parse_latin1};
 
-use super::dib::{DataInfoBlock, RawDataType};
-use super::vib::{ValueInfoBlock, ValueType};
+use super::data::DataType;
+use super::dib::{DataFunction, DataInfoBlock, RawDataType};
+use super::frame::IDLE_FILLER;
+use super::vib::{
+	CombinableVife, Quantity, RecordErrorQualifier, ValueInfoBlock, ValueType, VIF_ANY,
+};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Record {
 	pub dib: DataInfoBlock,
 	pub vib: ValueInfoBlock,
 	pub data: DataType,
+	/// How many bytes of the frame the DIB, VIB and data took up together
+	pub length: usize,
 }
 
 impl Record {
 	pub fn parse(input: &mut &Bytes) -> MBResult<Self> {
-		let (dib, vib) =
-			binary::bits::bits((DataInfoBlock::parse, ValueInfoBlock::parse)).parse_next(input)?;
+		Self::parse_with(ParseOptions::default()).parse_next(input)
+	}
+
+	pub fn parse_with<'a>(options: ParseOptions) -> impl Parser<&'a Bytes, Self, MBusError> {
+		let parse_record = move |input: &mut &'a Bytes| {
+			let (dib, vib) =
+				binary::bits::bits((DataInfoBlock::parse, ValueInfoBlock::parse_with(options)))
+					.parse_next(input)?;
+
+			Self::parse_data(options, dib, vib, input)
+		};
+		// Idle fillers can turn up anywhere a record could start, but they're
+		// not part of the record
+		let idle_fillers =
+			repeat::<_, _, (), _, _>(0.., IDLE_FILLER).context(StrContext::Label("idle filler"));
+		preceded(
+			idle_fillers,
+			parse_record
+				.with_recognized()
+				.map(|(record, raw): (Self, &[u8])| Self {
+					length: raw.len(),
+					..record
+				}),
+		)
+	}
+
+	pub fn storage(&self) -> u64 {
+		self.dib.storage
+	}
+
+	pub fn tariff(&self) -> u32 {
+		self.dib.tariff
+	}
+
+	pub fn device(&self) -> u16 {
+		self.dib.device
+	}
+
+	pub fn function(&self) -> &DataFunction {
+		&self.dib.function
+	}
+
+	/// Whether the value was captured while the device was in an error state,
+	/// rather than being a normal reading
+	pub fn is_error_state_value(&self) -> bool {
+		self.dib.function == DataFunction::ValueDuringErrorState
+	}
+
+	/// A human readable description of what the record's value means, such as
+	/// `energy (Wh, 10^3)`. Anything other than an instantaneous value has
+	/// its function added, since a maximum or a value captured during an error
+	/// shouldn't be presented as a current reading.
+	pub fn describe(&self) -> String {
+		match self.dib.function {
+			DataFunction::InstantaneousValue => self.vib.value_type.to_string(),
+			function => format!("{} [{function}]", self.vib.value_type),
+		}
+	}
+
+	/// What sort of physical quantity this record represents
+	pub fn quantity(&self) -> Quantity {
+		self.vib.value_type.quantity()
+	}
+
+	/// Returns true if the record contains a date that was only accepted
+	/// because it was parsed leniently (eg it has a month of 15)
+	pub fn has_nonconformant_date(&self) -> bool {
+		match &self.data {
+			DataType::DateTimeF(date) => !date.is_conformant(),
+			DataType::DateTimeI(date) => !date.is_conformant(),
+			DataType::Date(date) => !date.is_conformant(),
+			_ => false,
+		}
+	}
+
+	/// Returns true if the data field is wider than makes sense for the value
+	/// type, see [`ValueType::max_width`]
+	pub fn has_width_mismatch(&self) -> bool {
+		is_too_wide(&self.dib, &self.vib)
+	}
 
+	/// The error the device reported for this record in its VIFEs, if any. A
+	/// record with an error qualifier still has data, but it shouldn't be
+	/// trusted as a real reading.
+	pub fn error_qualifier(&self) -> Option<RecordErrorQualifier> {
+		self.vib
+			.combinable_vifes()
+			.into_iter()
+			.find_map(|vife| match vife {
+				CombinableVife::Error(qualifier) => Some(qualifier),
+				_ => None,
+			})
+	}
+
+	/// The state of each input or output point, if this is a digital input or
+	/// output record
+	pub fn digital_states(&self) -> Option<Vec<bool>> {
+		match (&self.vib.value_type, &self.data) {
+			(ValueType::DigitalOutput | ValueType::DigitalInput, DataType::Bitfield(states)) => {
+				Some(states.clone())
+			}
+			_ => None,
+		}
+	}
+
+	/// Which of the device's error flags are set, if this is an error flags
+	/// record. Like the remote control bits, what each flag means is up to the
+	/// device.
+	pub fn error_flags(&self) -> Option<Vec<bool>> {
+		match (&self.vib.value_type, &self.data) {
+			(ValueType::ErrorFlags, DataType::Bitfield(flags)) => Some(flags.clone()),
+			_ => None,
+		}
+	}
+
+	/// Which of the device's remote control bits are set, if this is a remote
+	/// control record. EN 13757-3 leaves the meaning of each bit up to the
+	/// device, so check its documentation for which one opens a valve or
+	/// trips a breaker.
+	pub fn remote_control_flags(&self) -> Option<Vec<bool>> {
+		match (&self.vib.value_type, &self.data) {
+			(ValueType::RemoteControl, DataType::Bitfield(flags)) => Some(flags.clone()),
+			_ => None,
+		}
+	}
+
+	/// The record's value as a whole number, including variable length
+	/// numbers too big for a `u64` or `i64`. Those are kept as the little
+	/// endian bytes the meter sent and decoded here as Type C if the value
+	/// type is unsigned or Type B if not. Returns `None` for anything that
+	/// isn't an integer or doesn't fit in an `i128`.
+	pub fn integer_value(&self) -> Option<i128> {
+		match &self.data {
+			DataType::Unsigned(value) => Some((*value).into()),
+			DataType::Signed(value) => Some((*value).into()),
+			DataType::VariableLengthNumber(bytes) if self.vib.value_type.is_unsigned() => {
+				decode_le_unsigned(bytes)?.try_into().ok()
+			}
+			DataType::VariableLengthNumber(bytes) => decode_le_signed(bytes),
+			_ => None,
+		}
+	}
+
+	/// The same as [`Record::integer_value`], but for unsigned values that
+	/// might be above `i128::MAX`. Returns `None` for negative values.
+	pub fn unsigned_integer_value(&self) -> Option<u128> {
+		match &self.data {
+			DataType::VariableLengthNumber(bytes) if self.vib.value_type.is_unsigned() => {
+				decode_le_unsigned(bytes)
+			}
+			_ => self.integer_value()?.try_into().ok(),
+		}
+	}
+
+	/// The record's numeric value multiplied out by the VIF's exponent, in the
+	/// units given by [`Record::unit`]. This doesn't say whether the value is
+	/// a minimum, maximum or error state value; see [`Record::describe`].
+	pub fn scaled_value(&self) -> Option<f64> {
+		let value = match self.data {
+			DataType::Unsigned(value) => value as f64,
+			DataType::Signed(value) => value as f64,
+			DataType::Real(value) => value.into(),
+			DataType::Double(value) => value,
+			DataType::VariableLengthNumber(_) => match self.integer_value() {
+				Some(value) => value as f64,
+				None => self.unsigned_integer_value()? as f64,
+			},
+			_ => return None,
+		};
+		Some(value * 10_f64.powi(self.vib.exponent().into()))
+	}
+
+	/// The same as [`Record::scaled_value`], but without any floating point
+	/// rounding for integer values
+	#[cfg(feature = "decimal")]
+	pub fn scaled_decimal(&self) -> Option<rust_decimal::Decimal> {
+		use rust_decimal::Decimal;
+
+		let value = match self.data {
+			DataType::Unsigned(value) => Decimal::from(value),
+			DataType::Signed(value) => Decimal::from(value),
+			DataType::Real(value) => Decimal::try_from(value).ok()?,
+			DataType::Double(value) => Decimal::try_from(value).ok()?,
+			DataType::VariableLengthNumber(_) => {
+				Decimal::try_from_i128_with_scale(self.integer_value()?, 0).ok()?
+			}
+			_ => return None,
+		};
+		let exponent = self.vib.exponent();
+		let factor = Decimal::from(10_u64.checked_pow(exponent.unsigned_abs().into())?);
+		let value = if exponent < 0 {
+			value.checked_div(factor)?
+		} else {
+			value.checked_mul(factor)?
+		};
+		Some(value.normalize())
+	}
+
+	pub fn unit(&self) -> Option<Cow<'_, str>> {
+		self.vib.unit()
+	}
+
+	/// Whether this is a credit or debit record, where [`Record::scaled_value`]
+	/// is an amount in the meter's local currency. The currency itself isn't
+	/// sent, so it's up to the caller to know which symbol to show.
+	pub fn is_monetary(&self) -> bool {
+		self.vib.value_type.is_monetary()
+	}
+
+	/// The value of a dimensionless record, which is a bare count with no unit
+	/// or scaling
+	pub fn count(&self) -> Option<i64> {
+		if !matches!(self.vib.value_type, ValueType::Dimensionless) {
+			return None;
+		}
+		match self.data {
+			DataType::Unsigned(value) => value.try_into().ok(),
+			DataType::Signed(value) => Some(value),
+			_ => None,
+		}
+	}
+
+	/// The day of the week, if this is a day of week record and the meter has
+	/// set it. 0 means "not specified" and is returned as `None`.
+	pub fn day_of_week(&self) -> Option<Weekday> {
+		match self.vib.value_type {
+			ValueType::DayOfWeek => Weekday::from_number(self.unsigned_value()?.try_into().ok()?),
+			_ => None,
+		}
+	}
+
+	/// The ISO 8601 week of the year from 1 to 53, if this is a week number
+	/// record and the meter has set it
+	pub fn week_number(&self) -> Option<u8> {
+		match self.vib.value_type {
+			ValueType::WeekNumber => self.unsigned_value()?.try_into().ok(),
+			_ => None,
+		}
+		.filter(|week| (1..=53).contains(week))
+	}
+
+	/// The second of the minute, if this is a time point second record. These
+	/// are sent alongside Type F date/times, which only go down to the minute.
+	pub fn time_point_second(&self) -> Option<u8> {
+		match self.vib.value_type {
+			ValueType::TimePointSecond => self.unsigned_value()?.try_into().ok(),
+			_ => None,
+		}
+		.filter(|second| *second <= 59)
+	}
+
+	/// Combines this record's Type F date/time with the seconds from a time
+	/// point second record in the same storage, tariff and subunit, giving a
+	/// date/time with full resolution. The day of the week and week number
+	/// are left unspecified.
+	///
+	/// Type I dates only have two digit years, so this returns `None` for
+	/// years outside 1981 to 2080. The "every year", "every hour" and "every
+	/// minute" values mean the same in both types and are kept as they are.
+	pub fn datetime_with_second(&self, second: &Record) -> Option<TypeIDateTime> {
+		let DataType::DateTimeF(datetime) = &self.data else {
+			return None;
+		};
+		if (self.storage(), self.tariff(), self.device())
+			!= (second.storage(), second.tariff(), second.device())
+		{
+			return None;
+		}
+		let (year, leap_year) = match datetime.full_year() {
+			None => (EVERY_YEAR, false),
+			Some(full_year @ 1981..=2080) => (
+				(full_year % 100) as u8,
+				full_year % 4 == 0 && (full_year % 100 != 0 || full_year % 400 == 0),
+			),
+			Some(_) => return None,
+		};
+		Some(TypeIDateTime {
+			second: second.time_point_second()?,
+			minute: datetime.minute,
+			hour: datetime.hour,
+			day: datetime.day,
+			month: datetime.month,
+			year,
+			day_of_week: 0,
+			week: 0,
+			in_dst: datetime.in_dst,
+			leap_year,
+			dst_offset: 0,
+		})
+	}
+
+	/// The value of a model, hardware, firmware or software version record
+	/// formatted for display. Versions sent as BCD keep their leading zeros,
+	/// so `0x05 0x01` becomes `"0105"` rather than `"105"`.
+	pub fn version_string(&self) -> Option<String> {
+		if !matches!(
+			self.vib.value_type,
+			ValueType::ModelVersion
+				| ValueType::HardwareVersionNumber
+				| ValueType::MetrologyFirmwareVersionNumber
+				| ValueType::OtherSoftwareVersionNumber
+		) {
+			return None;
+		}
+		self.identifier_string()
+	}
+
+	/// The customer's name or number, if this is a customer record. This is
+	/// usually sent as a string but some meters send a BCD number instead.
+	pub fn customer(&self) -> Option<String> {
+		match self.vib.value_type {
+			ValueType::Customer => self.identifier_string(),
+			_ => None,
+		}
+	}
+
+	/// Where the meter is installed, if this is a customer location record.
+	/// See [`Record::customer`].
+	pub fn customer_location(&self) -> Option<String> {
+		match self.vib.value_type {
+			ValueType::CustomerLocation => self.identifier_string(),
+			_ => None,
+		}
+	}
+
+	/// The device's fabrication number or enhanced identification, formatted
+	/// the way it's printed on the meter. Serials sent as BCD keep their
+	/// leading zeros, so `0x56 0x34 0x12 0x00` becomes `"00123456"`.
+	pub fn serial_number(&self) -> Option<String> {
+		match self.vib.value_type {
+			ValueType::FabricationNumber | ValueType::EnhancedIdentification => {
+				self.identifier_string()
+			}
+			_ => None,
+		}
+	}
+
+	/// Formats a record that identifies something rather than measuring it,
+	/// where BCD values are really strings of digits and keep their leading
+	/// zeros
+	fn identifier_string(&self) -> Option<String> {
+		match (&self.data, self.dib.raw_type) {
+			(DataType::Signed(value @ 0..), RawDataType::BCD(n)) => {
+				Some(format!("{value:0width$}", width = 2 * n))
+			}
+			(DataType::Unsigned(value), _) => Some(value.to_string()),
+			(DataType::Signed(value), _) => Some(value.to_string()),
+			(DataType::String(value), _) => Some(value.clone()),
+			(DataType::EnhancedIdentification(id), _) => Some(format!("{:08}", id.identifier)),
+			_ => None,
+		}
+	}
+
+	/// The baud rate the device is configured for, if this is a baud rate
+	/// record
+	pub fn baud_rate(&self) -> Option<u32> {
+		match self.vib.value_type {
+			ValueType::BaudRate => self.unsigned_value(),
+			_ => None,
+		}
+	}
+
+	/// How long the device waits before responding, in bit times, if this is a
+	/// response delay time record
+	pub fn response_delay_bits(&self) -> Option<u32> {
+		match self.vib.value_type {
+			ValueType::ResponseDelayTime => self.unsigned_value(),
+			_ => None,
+		}
+	}
+
+	/// The response delay converted from bit times into real time at the given
+	/// baud rate
+	pub fn response_delay(&self, baud_rate: u32) -> Option<Duration> {
+		if baud_rate == 0 {
+			return None;
+		}
+		let bits = u64::from(self.response_delay_bits()?);
+		Some(Duration::from_nanos(
+			bits * 1_000_000_000 / u64::from(baud_rate),
+		))
+	}
+
+	/// Turns the record back into the bytes that would be sent over the wire.
+	///
+	/// LVAR numbers are sent with as few bytes as possible, so may come out
+	/// shorter than they went in.
+	///
+	/// Returns `None` if the data can't be represented with the DIB's data
+	/// type, if the VIB can't be encoded (see [`ValueInfoBlock::encode`]), or
+	/// if the data can't be encoded at all (such as Type K daylight savings,
+	/// which loses information while parsing).
+	pub fn encode(&self) -> Option<Vec<u8>> {
+		let mut result = self.dib.encode()?;
+		result.extend(self.vib.encode()?);
+		result.extend(encode_data(
+			self.dib.raw_type,
+			&self.vib.value_type,
+			&self.data,
+		)?);
+		Some(result)
+	}
+
+	fn unsigned_value(&self) -> Option<u32> {
+		match self.data {
+			DataType::Unsigned(value) => value.try_into().ok(),
+			DataType::Signed(value) => value.try_into().ok(),
+			_ => None,
+		}
+	}
+
+	fn parse_data(
+		options: ParseOptions,
+		dib: DataInfoBlock,
+		vib: ValueInfoBlock,
+		input: &mut &Bytes,
+	) -> MBResult<Self> {
 		let vib = handle_date_types(&dib, vib);
+		let dib = match dib.raw_type {
+			RawDataType::Real(4) if options.double_reals => DataInfoBlock {
+				raw_type: RawDataType::Real(8),
+				..dib
+			},
+			_ => dib,
+		};
+		if options.strict_widths && is_too_wide(&dib, &vib) {
+			return Err(
+				ErrMode::from_error_kind(input, ErrorKind::Verify).add_context(
+					input,
+					&input.checkpoint(),
+					StrContext::Label("data field too wide for value type"),
+				),
+			);
+		}
 
 		let unsigned = vib.value_type.is_unsigned();
-		let data = match vib.value_type {
-			ValueType::TypeFDateTime => TypeFDateTime::parse
+		let opaque = vib.value_type.is_opaque();
+		// Type D: each bit of the data is a separate on/off state
+		let digital = vib.value_type.is_boolean();
+		// The battery change date is sent as a Type F, G, I or M date depending
+		// on the width, but unlike `VariableDateTime` it's worth keeping the
+		// value type around afterwards
+		let battery_change_date = match (&vib.value_type, dib.raw_type) {
+			(ValueType::DateAndTimeOfBatteryChange, RawDataType::Binary(2)) => {
+				Some(ValueType::TypeGDate)
+			}
+			(ValueType::DateAndTimeOfBatteryChange, RawDataType::Binary(3)) => None,
+			(ValueType::DateAndTimeOfBatteryChange, raw_type) => variable_date_type(raw_type),
+			_ => None,
+		};
+		let data = match battery_change_date.as_ref().unwrap_or(&vib.value_type) {
+			ValueType::TypeFDateTime => TypeFDateTime::parser(options.strict_dates)
 				.map(DataType::DateTimeF)
 				.context(StrContext::Label("Type F Date/Time"))
 				.parse_next(input)?,
-			ValueType::TypeGDate => TypeGDate::parse
+			ValueType::TypeGDate => TypeGDate::parser(options.strict_dates)
 				.map(DataType::Date)
 				.context(StrContext::Label("Type G Date"))
 				.parse_next(input)?,
-			ValueType::TypeIDateTime => TypeIDateTime::parse
+			ValueType::TypeIDateTime => TypeIDateTime::parser(options.strict_dates)
 				.map(DataType::DateTimeI)
 				.context(StrContext::Label("Type I Date/Time"))
 				.parse_next(input)?,
@@ -55,6 +516,26 @@ impl Record {
 				.map(DataType::DST)
 				.context(StrContext::Label("Daylight Savings Type K"))
 				.parse_next(input)?,
+			// Any other width is just the identification number on its own
+			ValueType::EnhancedIdentification if dib.raw_type == RawDataType::Binary(8) => {
+				EnhancedIdentification::parser(options)
+					.map(DataType::EnhancedIdentification)
+					.context(StrContext::Label("Enhanced identification"))
+					.parse_next(input)?
+			}
+			ValueType::WirelessContainer if dib.raw_type == RawDataType::LVAR => {
+				parse_container(options)
+					.context(StrContext::Label("wireless container"))
+					.parse_next(input)?
+			}
+			// Whatever's in here is up to the manufacturer, so all that can be
+			// done is to pull it out in one piece
+			ValueType::ManufacturerSpecificContainer if dib.raw_type == RawDataType::LVAR => {
+				binary::length_take(binary::u8.verify(|n| *n <= 0xBF))
+					.map(|data: &[u8]| DataType::Opaque(data.to_vec()))
+					.context(StrContext::Label("manufacturer specific container"))
+					.parse_next(input)?
+			}
 			// TODO: I've commented this out as it means that these will simply
 			// parse as a large lvar number and it's the caller to parse it
 			// themselves. I need to figure out a good way of handling this.
@@ -67,9 +548,12 @@ impl Record {
 					parse_invalid_bcd(num).map(DataType::ErrorValue),
 				))
 				.parse_next(input)?,
+				RawDataType::Binary(num) if opaque => parse_opaque(num).parse_next(input)?,
+				RawDataType::Binary(num) if digital => parse_bitfield(num).parse_next(input)?,
 				RawDataType::Binary(num) => parse_binary(unsigned, num).parse_next(input)?,
-				RawDataType::Real => parse_real.map(DataType::Real).parse_next(input)?,
-				RawDataType::None => DataType::None,
+				RawDataType::Real(8) => parse_double.map(DataType::Double).parse_next(input)?,
+				RawDataType::Real(_) => parse_real.map(DataType::Real).parse_next(input)?,
+				RawDataType::None | RawDataType::SelectionForReadout => DataType::None,
 				RawDataType::LVAR => {
 					let value = binary::u8
 						.verify(
@@ -79,19 +563,28 @@ impl Record {
 						.context(StrContext::Label("LVAR value"))
 						.parse_next(input)?;
 					match value {
-						// For some unknowable reason, the LVAR value can specify to parse 0 bytes
+						// For some unknowable reason, the LVAR value can specify to parse
+						// 0 bytes. A zero width number is zero rather than missing, as
+						// that's what the encoder sends for zero, so only the "no data"
+						// DIF codes ever produce `DataType::None`.
 						n @ 0x00..=0xBF => {
 							parse_latin1(n).map(DataType::String).parse_next(input)?
 						}
-						n @ 0xC0..=0xC9 => parse_bcd(n - 0xC0)
-							.verify(|v| *v > 0)
+						n @ 0xC0..=0xC9 => parse_positive_bcd(n - 0xC0)
 							.map(DataType::Signed)
 							.parse_next(input)?,
-						n @ 0xD0..=0xD9 => parse_bcd(n - 0xD0)
-							.map(|v| DataType::Signed(if v > 0 { -v } else { v }))
+						n @ 0xD0..=0xD9 => parse_negative_bcd(n - 0xD0)
+							.map(DataType::Signed)
 							.parse_next(input)?,
+						n @ 0xE0..=0xEF if opaque => parse_opaque(n - 0xE0).parse_next(input)?,
+						n @ 0xE0..=0xEF if digital => parse_bitfield(n - 0xE0).parse_next(input)?,
 						n @ 0xE0..=0xE8 => parse_binary(unsigned, n - 0xE0).parse_next(input)?,
 						n @ 0xE9..=0xEF => parse_giant_number(n - 0xE0).parse_next(input)?,
+						n @ 0xF0..=0xF4 if opaque => {
+							parse_opaque(4 * (n - 0xEC)).parse_next(input)?
+						}
+						0xF5 if opaque => parse_opaque(48).parse_next(input)?,
+						0xF6 if opaque => parse_opaque(64).parse_next(input)?,
 						n @ 0xF0..=0xF4 => parse_giant_number(4 * (n - 0xEC)).parse_next(input)?,
 						0xF5 => parse_giant_number(48).parse_next(input)?,
 						0xF6 => parse_giant_number(64).parse_next(input)?,
@@ -101,10 +594,211 @@ impl Record {
 			},
 		};
 
-		Ok(Self { dib, vib, data })
+		Ok(Self {
+			dib,
+			vib,
+			data,
+			length: 0,
+		})
+	}
+}
+
+fn encode_data(raw_type: RawDataType, value_type: &ValueType, data: &DataType) -> Option<Vec<u8>> {
+	let width = raw_type.data_width();
+	let fixed = |data: Vec<u8>| (Some(data.len()) == width).then_some(data);
+	match (raw_type, data) {
+		(RawDataType::None | RawDataType::SelectionForReadout, DataType::None) => Some(vec![]),
+		(RawDataType::BCD(n), DataType::Signed(value)) => encode_bcd(*value, n),
+		(RawDataType::BCD(n), DataType::ErrorValue(value)) => encode_invalid_bcd(value, n),
+		(RawDataType::Binary(n), DataType::Signed(value)) => encode_binary_signed(*value, n),
+		(RawDataType::Binary(n), DataType::Unsigned(value)) => encode_binary_unsigned(*value, n),
+		(RawDataType::Real(4), DataType::Real(value)) => Some(value.to_le_bytes().to_vec()),
+		(RawDataType::Real(8), DataType::Double(value)) => Some(value.to_le_bytes().to_vec()),
+		(RawDataType::Binary(_), DataType::DateTimeF(value)) => fixed(value.encode().to_vec()),
+		(RawDataType::Binary(_), DataType::Date(value)) => fixed(value.encode().to_vec()),
+		(RawDataType::Binary(_), DataType::DateTimeI(value)) => fixed(value.encode().to_vec()),
+		(RawDataType::Binary(_), DataType::Time(value)) => fixed(value.encode().to_vec()),
+		(RawDataType::Binary(_), DataType::Opaque(value)) => fixed(value.clone()),
+		(RawDataType::Binary(_), DataType::Bitfield(states)) => fixed(
+			states
+				.chunks(8)
+				.map(|bits| {
+					bits.iter()
+						.enumerate()
+						.fold(0, |byte, (i, bit)| byte | (u8::from(*bit) << i))
+				})
+				.collect(),
+		),
+		(RawDataType::LVAR, DataType::Opaque(value))
+			if *value_type == ValueType::ManufacturerSpecificContainer =>
+		{
+			let mut bytes = value.clone();
+			bytes.insert(0, u8::try_from(value.len()).ok().filter(|n| *n <= 0xBF)?);
+			Some(bytes)
+		}
+		(RawDataType::LVAR, data) => encode_lvar(data),
+		_ => None,
+	}
+}
+
+fn encode_lvar(data: &DataType) -> Option<Vec<u8>> {
+	let (lvar, mut bytes) = match data {
+		DataType::String(value) => {
+			let bytes = encode_latin1(value)?;
+			(
+				u8::try_from(bytes.len()).ok().filter(|n| *n <= 0xBF)?,
+				bytes,
+			)
+		}
+		DataType::Signed(value) => {
+			let digits = value.unsigned_abs().checked_ilog10().map_or(0, |n| n + 1);
+			let n = digits.div_ceil(2) as usize;
+			let lvar = if *value < 0 { 0xD0 } else { 0xC0 };
+			(lvar + n as u8, encode_bcd(value.checked_abs()?, n)?)
+		}
+		DataType::Unsigned(value) => {
+			let n = (u64::BITS - value.leading_zeros()).div_ceil(8) as usize;
+			(0xE0 + n as u8, value.to_le_bytes()[..n].to_vec())
+		}
+		DataType::Opaque(value) => (binary_lvar(value.len())?, value.clone()),
+		DataType::Container(records) => {
+			let mut bytes = Vec::new();
+			for record in records {
+				bytes.extend(record.encode()?);
+			}
+			(
+				u8::try_from(bytes.len()).ok().filter(|n| *n <= 0xBF)?,
+				bytes,
+			)
+		}
+		DataType::VariableLengthNumber(value) if value.len() >= 9 => {
+			(binary_lvar(value.len())?, value.clone())
+		}
+		_ => return None,
+	};
+	bytes.insert(0, lvar);
+	Some(bytes)
+}
+
+/// The LVAR value for `len` bytes of binary data, if there is one
+fn binary_lvar(len: usize) -> Option<u8> {
+	Some(match len {
+		n @ 0..=15 => 0xE0 + n as u8,
+		n @ (16 | 20 | 24 | 28 | 32) => 0xEC + (n / 4) as u8,
+		48 => 0xF5,
+		64 => 0xF6,
+		_ => return None,
+	})
+}
+
+/// Parses a list of records that ask for a value rather than send one, which a
+/// master sends in a SND_UD to request a partial readout. Each selector is
+/// either the global readout DIF on its own, or a DIB and VIB with no data,
+/// using either the "selection for readout" or the "no data" data field.
+pub fn parse_readout_selection(input: &mut &Bytes) -> MBResult<Vec<ReadoutSelector>> {
+	let idle_fillers = || repeat::<_, _, (), _, _>(0.., IDLE_FILLER);
+	let selector = alt((
+		DIF_GLOBAL_READOUT.value(ReadoutSelector::All),
+		binary::bits::bits((DataInfoBlock::parse, ValueInfoBlock::parse))
+			.verify(|(dib, _): &(DataInfoBlock, ValueInfoBlock)| {
+				dib.raw_type.data_width() == Some(0)
+			})
+			.map(|(dib, vib)| match vib.value_type {
+				ValueType::Any => ReadoutSelector::AnyValue(dib),
+				_ => ReadoutSelector::Value(dib, vib),
+			}),
+	))
+	.context(StrContext::Label("readout selector"));
+	terminated(
+		repeat(0.., preceded(idle_fillers(), selector)),
+		(idle_fillers(), eof),
+	)
+	.parse_next(input)
+}
+
+/// The EN 13757-3 special function DIF asking for every storage number,
+/// tariff, device unit and function field at once
+const DIF_GLOBAL_READOUT: u8 = 0x7F;
+
+/// One entry in a list of records for the master to ask a device for, see
+/// [`encode_readout_selection`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReadoutSelector {
+	/// Every record the device has, sent as the global readout DIF on its own
+	All,
+	/// Every value type matching the DIB, sent with the "any VIF" `0x7E`
+	AnyValue(DataInfoBlock),
+	/// A single record, as returned by [`parse_readout_selection`]
+	Value(DataInfoBlock, ValueInfoBlock),
+}
+
+/// Builds the records a master sends in a SND_UD to request a partial
+/// readout. Returns `None` if any of the DIBs or VIBs can't be encoded or a
+/// DIB would be followed by data.
+pub fn encode_readout_selection(selectors: &[ReadoutSelector]) -> Option<Vec<u8>> {
+	let encode_dib = |dib: &DataInfoBlock| {
+		if dib.raw_type.data_width() != Some(0) {
+			return None;
+		}
+		dib.encode()
+	};
+	let mut result = Vec::new();
+	for selector in selectors {
+		match selector {
+			ReadoutSelector::All => result.push(DIF_GLOBAL_READOUT),
+			ReadoutSelector::AnyValue(dib) => {
+				result.extend(encode_dib(dib)?);
+				result.push(VIF_ANY);
+			}
+			ReadoutSelector::Value(dib, vib) => {
+				result.extend(encode_dib(dib)?);
+				result.extend(vib.encode()?);
+			}
+		}
+	}
+	Some(result)
+}
+
+/// Containers hold a length prefixed list of records, using the same LVAR
+/// lengths as a string
+fn parse_container<'a>(options: ParseOptions) -> impl Parser<&'a Bytes, DataType, MBusError> {
+	move |input: &mut &'a Bytes| {
+		let data = binary::length_take(binary::u8.verify(|n| *n <= 0xBF))
+			.context(StrContext::Label("container length"))
+			.parse_next(input)?;
+		parse_within(
+			terminated(repeat(0.., Record::parse_with(options)), eof).map(DataType::Container),
+			data,
+			input.len(),
+		)
 	}
 }
 
+fn parse_opaque<'a>(bytes: usize) -> impl Parser<&'a Bytes, DataType, MBusError> {
+	take(bytes)
+		.map(|data: &[u8]| DataType::Opaque(data.to_vec()))
+		.context(StrContext::Label("opaque data"))
+}
+
+fn parse_bitfield<'a>(bytes: usize) -> impl Parser<&'a Bytes, DataType, MBusError> {
+	take(bytes)
+		.map(|data: &[u8]| {
+			DataType::Bitfield(
+				data.iter()
+					.flat_map(|byte| (0..8).map(move |bit| byte & (1 << bit) != 0))
+					.collect(),
+			)
+		})
+		.context(StrContext::Label("bitfield"))
+}
+
+/// Parses a Type A/B binary number, as unsigned if the value type can't be
+/// negative and as two's complement otherwise.
+///
+/// There's no way to tell a genuinely negative number apart from a meter that
+/// sends a huge unsigned value for a signed value type, so an 8 byte value
+/// above `i64::MAX` comes out negative. Value types that are known to be
+/// unsigned should be added to [`ValueType::is_unsigned`] to avoid this.
 pub fn parse_binary<'a>(
 	unsigned: bool,
 	bytes: usize,
@@ -126,20 +820,1157 @@ fn parse_giant_number<'a>(bytes: usize) -> impl Parser<&'a Bytes, DataType, MBus
 	repeat(bytes, binary::u8).map(DataType::VariableLengthNumber)
 }
 
+fn is_too_wide(dib: &DataInfoBlock, vib: &ValueInfoBlock) -> bool {
+	match (dib.raw_type.data_width(), vib.value_type.max_width()) {
+		(Some(width), Some(max_width)) => width > max_width,
+		_ => false,
+	}
+}
+
+/// Works out which date format a variable date is in from the data width
+fn variable_date_type(raw_type: RawDataType) -> Option<ValueType> {
+	match raw_type {
+		RawDataType::LVAR => Some(ValueType::TypeMDatetime),
+		RawDataType::Binary(3) => Some(ValueType::TypeJTime),
+		RawDataType::Binary(4) => Some(ValueType::TypeFDateTime),
+		RawDataType::Binary(6) => Some(ValueType::TypeIDateTime),
+		_ => None,
+	}
+}
+
 fn handle_date_types(dib: &DataInfoBlock, mut vib: ValueInfoBlock) -> ValueInfoBlock {
 	vib.value_type = match vib.value_type {
 		ValueType::TypeGDate => match dib.raw_type {
 			RawDataType::Binary(2) => ValueType::TypeGDate,
 			_ => ValueType::Invalid(vif!(E110 1100)),
 		},
-		ValueType::VariableDateTime => match dib.raw_type {
-			RawDataType::LVAR => ValueType::TypeMDatetime,
-			RawDataType::Binary(4) => ValueType::TypeFDateTime,
-			RawDataType::Binary(3) => ValueType::TypeJTime,
-			RawDataType::Binary(5) => ValueType::TypeIDateTime,
-			_ => ValueType::Invalid(vif!(E110 1101)),
-		},
+		ValueType::VariableDateTime => {
+			variable_date_type(dib.raw_type).unwrap_or(ValueType::Invalid(vif!(E110 1101)))
+		}
 		vt => vt,
 	};
 	vib
 }
+
+#[cfg(test)]
+mod test_record {
+	use std::time::Duration;
+
+	use rstest::rstest;
+	use winnow::prelude::*;
+	use winnow::Bytes;
+
+	use super::{encode_readout_selection, parse_readout_selection, ReadoutSelector, Record};
+	use crate::parse::application_layer::data::DataType;
+	use crate::parse::application_layer::dib::{DataFunction, DataInfoBlock, RawDataType};
+	use crate::parse::application_layer::frame::{DeviceResponse, Frame};
+	use crate::parse::application_layer::vib::{
+		RecordErrorQualifier, ValueInfoBlock, ValueType, VolumeUnit,
+	};
+	use crate::parse::link_layer::Packet;
+	use crate::parse::options::ParseOptions;
+	use crate::parse::transport_layer::header::{DeviceType, WaterMeterType};
+	use crate::parse::transport_layer::MBusMessage;
+	use crate::parse::types::date::{TypeFDateTime, TypeGDate, Weekday};
+	use crate::parse::types::identification::EnhancedIdentification;
+	use crate::utils::read_test_file;
+
+	fn volume_blocks(raw_type: RawDataType) -> (DataInfoBlock, ValueInfoBlock) {
+		(
+			DataInfoBlock {
+				raw_type,
+				function: DataFunction::InstantaneousValue,
+				storage: 0,
+				tariff: 0,
+				device: 0,
+				is_obis: false,
+				raw: vec![0x05],
+			},
+			ValueInfoBlock {
+				value_type: ValueType::Volume(VolumeUnit::M3, -3),
+				extra_vifes: None,
+				raw: vec![0x13],
+			},
+		)
+	}
+
+	#[test]
+	fn test_real() {
+		let input = Bytes::new(&[0x05, 0x13, 0x00, 0x00, 0xC0, 0x3F]);
+
+		let result = Record::parse.parse(input).unwrap();
+
+		assert_eq!(result.data, DataType::Real(1.5));
+	}
+
+	#[test]
+	fn test_double() {
+		let mut input = vec![0x05, 0x13];
+		input.extend(1.5_f64.to_le_bytes());
+		let options = ParseOptions {
+			double_reals: true,
+			..ParseOptions::default()
+		};
+
+		let result = Record::parse_with(options)
+			.parse(Bytes::new(&input))
+			.unwrap();
+
+		assert_eq!(result.dib.raw_type, RawDataType::Real(8));
+		assert_eq!(result.data, DataType::Double(1.5));
+		assert_eq!(result.length, input.len());
+		assert_eq!(result.encode(), Some(input));
+	}
+
+	#[test]
+	fn test_double_needs_option() {
+		let mut input = vec![0x05, 0x13];
+		input.extend(1.5_f64.to_le_bytes());
+
+		let (remaining, result) = Record::parse.parse_peek(Bytes::new(&input)).unwrap();
+
+		assert_eq!(result.dib.raw_type, RawDataType::Real(4));
+		assert!(matches!(result.data, DataType::Real(_)));
+		assert_eq!(remaining.len(), 4);
+	}
+
+	#[test]
+	fn test_selection_for_readout() {
+		let mut input = Bytes::new(&[0x08, 0x13, 0x02, 0x13, 0x05, 0x00]);
+
+		let result = Record::parse.parse_next(&mut input).unwrap();
+
+		assert!(matches!(
+			result.dib.raw_type,
+			RawDataType::SelectionForReadout
+		));
+		assert_eq!(result.data, DataType::None);
+		assert_eq!(input.len(), 4, "no data should have been consumed");
+	}
+
+	#[test]
+	fn test_raw_bytes() {
+		// From kamstrup_multical_601.hex
+		let input = Bytes::new(&[0x84, 0x80, 0x40, 0x14, 0x00, 0x00, 0x00, 0x00]);
+
+		let result = Record::parse.parse(input).unwrap();
+
+		assert_eq!(result.dib.raw, [0x84, 0x80, 0x40]);
+		assert_eq!(result.vib.raw, [0x14]);
+	}
+
+	#[test]
+	fn test_raw_bytes_vife() {
+		// From abb_delta.hex
+		let input = Bytes::new(&[0x01, 0xFF, 0x93, 0x00, 0x00]);
+
+		let result = Record::parse.parse(input).unwrap();
+
+		assert_eq!(result.dib.raw, [0x01]);
+		assert_eq!(result.vib.raw, [0xFF, 0x93, 0x00]);
+	}
+
+	#[test]
+	fn test_nonconformant_date() {
+		let input = Bytes::new(&[0x02, 0x6C, 0x01, 0x0F]);
+
+		let result = Record::parse.parse(input).unwrap();
+
+		assert!(result.has_nonconformant_date());
+	}
+
+	#[test]
+	fn test_conformant_date() {
+		let input = Bytes::new(&[0x02, 0x6C, 0x8C, 0x11]);
+
+		let result = Record::parse.parse(input).unwrap();
+
+		assert!(!result.has_nonconformant_date());
+	}
+
+	#[rstest]
+	#[case::b300(&[0x02, 0xFD, 0x1C, 0x2C, 0x01], 300)]
+	#[case::b2400(&[0x02, 0xFD, 0x1C, 0x60, 0x09], 2400)]
+	#[case::b9600(&[0x02, 0xFD, 0x1C, 0x80, 0x25], 9600)]
+	#[case::b38400(&[0x02, 0xFD, 0x1C, 0x00, 0x96], 38400)]
+	fn test_baud_rate(#[case] input: &[u8], #[case] expected: u32) {
+		let result = Record::parse.parse(Bytes::new(input)).unwrap();
+
+		assert_eq!(result.baud_rate(), Some(expected));
+		assert_eq!(result.response_delay_bits(), None);
+	}
+
+	#[rstest]
+	#[case::b9600(9600, Duration::from_millis(50))]
+	#[case::b2400(2400, Duration::from_millis(200))]
+	#[case::b300(300, Duration::from_millis(1600))]
+	fn test_response_delay(#[case] baud_rate: u32, #[case] expected: Duration) {
+		let input = Bytes::new(&[0x02, 0xFD, 0x1D, 0xE0, 0x01]);
+
+		let result = Record::parse.parse(input).unwrap();
+
+		assert_eq!(result.response_delay_bits(), Some(480));
+		assert_eq!(result.response_delay(baud_rate), Some(expected));
+		assert_eq!(result.baud_rate(), None);
+	}
+
+	#[test]
+	fn test_response_delay_zero_baud() {
+		let input = Bytes::new(&[0x02, 0xFD, 0x1D, 0xE0, 0x01]);
+
+		let result = Record::parse.parse(input).unwrap();
+
+		assert_eq!(result.response_delay(0), None);
+	}
+
+	#[rstest]
+	#[case::positive(0xC2, &[0x34, 0x12], 1234)]
+	#[case::positive_zero(0xC0, &[], 0)]
+	#[case::negative(0xD2, &[0x34, 0x12], -1234)]
+	#[case::negative_redundant_sign(0xD2, &[0x34, 0xF2], -234)]
+	fn test_lvar_bcd(#[case] lvar: u8, #[case] digits: &[u8], #[case] expected: i64) {
+		let mut data = vec![0x0D, 0x13, lvar];
+		data.extend(digits);
+
+		let result = Record::parse.parse(Bytes::new(&data)).unwrap();
+
+		assert_eq!(result.data, DataType::Signed(expected));
+	}
+
+	#[rstest]
+	#[case::no_data(&[0x00, 0x13], DataType::None)]
+	#[case::selection(&[0x08, 0x13], DataType::None)]
+	#[case::empty_string(&[0x0D, 0xFD, 0x11, 0x00], DataType::String("".to_owned()))]
+	#[case::positive_bcd(&[0x0D, 0x13, 0xC0], DataType::Signed(0))]
+	#[case::negative_bcd(&[0x0D, 0x13, 0xD0], DataType::Signed(0))]
+	#[case::signed_binary(&[0x0D, 0x13, 0xE0], DataType::Signed(0))]
+	#[case::unsigned_binary(&[0x0D, 0xFD, 0x08, 0xE0], DataType::Unsigned(0))]
+	#[case::opaque(&[0x0D, 0xFD, 0x16, 0xE0], DataType::Opaque(vec![]))]
+	fn test_zero_width(#[case] input: &[u8], #[case] expected: DataType) {
+		let result = Record::parse.parse(Bytes::new(input)).unwrap();
+
+		assert_eq!(result.data, expected);
+		assert_eq!(result.length, input.len());
+	}
+
+	#[rstest]
+	#[case::empty_string(&[0x0D, 0xFD, 0x11, 0x00])]
+	#[case::positive_bcd(&[0x0D, 0x13, 0xC0])]
+	#[case::unsigned_binary(&[0x0D, 0xFD, 0x08, 0xE0])]
+	#[case::opaque(&[0x0D, 0xFD, 0x16, 0xE0])]
+	fn test_encode_zero_width(#[case] input: &[u8]) {
+		let record = Record::parse.parse(Bytes::new(input)).unwrap();
+
+		assert_eq!(record.encode().as_deref(), Some(input));
+	}
+
+	#[rstest]
+	#[case::positive(&[0x0D, 0x13, 0xE9, 0, 0, 0, 0, 0, 0, 0, 0, 0x01], 1 << 64)]
+	#[case::negative(&[0x0D, 0x13, 0xE9, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFE], -(1 << 64) - 1)]
+	#[case::unsigned(&[0x0D, 0xFD, 0x08, 0xE9, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF], (1 << 72) - 1)]
+	fn test_giant_number(#[case] input: &[u8], #[case] expected: i128) {
+		let result = Record::parse.parse(Bytes::new(input)).unwrap();
+
+		assert!(matches!(result.data, DataType::VariableLengthNumber(_)));
+		assert_eq!(result.integer_value(), Some(expected));
+		assert_eq!(result.unsigned_integer_value(), expected.try_into().ok());
+		assert_eq!(
+			result.scaled_value(),
+			Some(expected as f64 * 10_f64.powi(result.vib.exponent().into()))
+		);
+	}
+
+	#[test]
+	fn test_giant_unsigned_number() {
+		let input = [&[0x0D, 0xFD, 0x08, 0xF0][..], &[0xFF; 16]].concat();
+
+		let result = Record::parse.parse(Bytes::new(&input)).unwrap();
+
+		assert_eq!(result.integer_value(), None);
+		assert_eq!(result.unsigned_integer_value(), Some(u128::MAX));
+		assert_eq!(result.scaled_value(), Some(u128::MAX as f64));
+	}
+
+	#[test]
+	fn test_dimensionless() {
+		let input = Bytes::new(&[0x02, 0xFD, 0x3A, 0x2A, 0x00]);
+
+		let result = Record::parse.parse(input).unwrap();
+
+		assert_eq!(result.count(), Some(42));
+		assert_eq!(result.unit().as_deref(), None);
+		assert_eq!(result.scaled_value(), Some(42.0));
+	}
+
+	#[test]
+	fn test_scaled_value() {
+		// 1234 kWh
+		let input = Bytes::new(&[0x02, 0x06, 0xD2, 0x04]);
+
+		let result = Record::parse.parse(input).unwrap();
+
+		assert_eq!(result.scaled_value(), Some(1_234_000.0));
+		assert_eq!(result.unit().as_deref(), Some("Wh"));
+		assert_eq!(result.count(), None);
+	}
+
+	#[rstest]
+	// 10^(nn-3) °C, so 0xFB 0x76 is tenths of a degree
+	#[case::temperature_limit(&[0x02, 0xFB, 0x76, 0xFA, 0x00], 25.0, "°C")]
+	#[case::temperature_limit_thousandths(&[0x02, 0xFB, 0x74, 0xFA, 0x00], 0.25, "°C")]
+	// 10^(nnn-3) W, so 0xFB 0x7D is hundreds of watts
+	#[case::cumulative_max_power(&[0x02, 0xFB, 0x7D, 0x0C, 0x00], 1_200.0, "W")]
+	#[case::cumulative_max_power_milliwatts(&[0x02, 0xFB, 0x78, 0x0C, 0x00], 0.012, "W")]
+	fn test_table_14_units(#[case] input: &[u8], #[case] expected: f64, #[case] unit: &str) {
+		let result = Record::parse.parse(Bytes::new(input)).unwrap();
+
+		assert_eq!(result.scaled_value(), Some(expected));
+		assert_eq!(result.unit().as_deref(), Some(unit));
+	}
+
+	#[rstest]
+	// 10^(nn-3), so 0xFD 0x02 is tenths of a currency unit
+	#[case::credit(&[0x04, 0xFD, 0x02, 0x39, 0x30, 0x00, 0x00], 1_234.5)]
+	#[case::debit(&[0x04, 0xFD, 0x04, 0x39, 0x30, 0x00, 0x00], 12.345)]
+	#[case::whole_units(&[0x04, 0xFD, 0x03, 0x39, 0x30, 0x00, 0x00], 12_345.0)]
+	fn test_monetary(#[case] input: &[u8], #[case] expected: f64) {
+		let result = Record::parse.parse(Bytes::new(input)).unwrap();
+
+		assert!(result.is_monetary());
+		assert_eq!(result.scaled_value(), Some(expected));
+		assert_eq!(result.unit(), None);
+	}
+
+	#[test]
+	fn test_not_monetary() {
+		let result = Record::parse
+			.parse(Bytes::new(&[0x02, 0x06, 0xD2, 0x04]))
+			.unwrap();
+
+		assert!(!result.is_monetary());
+	}
+
+	#[test]
+	fn test_wireless_container() {
+		let input = Bytes::new(&[
+			0x0D, 0xFD, 0x3B, // Wireless container
+			0x0C, // 12 bytes of records
+			0x04, 0x13, 0x2A, 0x00, 0x00, 0x00, // 42 litres
+			0x44, 0x13, 0x15, 0x00, 0x00, 0x00, // 21 litres in storage 1
+		]);
+
+		let result = Record::parse.parse(input).unwrap();
+
+		let DataType::Container(records) = &result.data else {
+			panic!("expected a container, got {:?}", result.data);
+		};
+		let values: Vec<_> = records
+			.iter()
+			.map(|record| (record.storage(), record.data.clone()))
+			.collect();
+		assert_eq!(
+			values,
+			[(0, DataType::Signed(42)), (1, DataType::Signed(21))]
+		);
+		assert_eq!(result.length, 16);
+		assert_eq!(result.encode().as_deref(), Some(input.as_ref()));
+	}
+
+	#[test]
+	fn test_wireless_container_overrun() {
+		let input = Bytes::new(&[
+			0x0D, 0xFD, 0x3B, // Wireless container
+			0x04, // 4 bytes of records, but the record needs 6
+			0x04, 0x13, 0x2A, 0x00, 0x00, 0x00,
+		]);
+
+		Record::parse.parse(input).unwrap_err();
+	}
+
+	#[test]
+	fn test_manufacturer_specific_container() {
+		let input = Bytes::new(&[
+			0x0D, 0xFD, 0x76, // Manufacturer specific container
+			0x03, 0x01, 0x02, 0x03,
+		]);
+
+		let result = Record::parse.parse(input).unwrap();
+
+		assert_eq!(result.data, DataType::Opaque(vec![0x01, 0x02, 0x03]));
+		assert_eq!(result.encode().as_deref(), Some(input.as_ref()));
+	}
+
+	#[test]
+	fn test_plain_text_unit() {
+		// 42 RPM, with the unit sent backwards like every other string
+		let input = Bytes::new(&[0x02, 0x7C, 0x03, b'M', b'P', b'R', 0x2A, 0x00]);
+
+		let result = Record::parse.parse(input).unwrap();
+
+		assert_eq!(result.unit().as_deref(), Some("RPM"));
+		assert_eq!(result.scaled_value(), Some(42.0));
+	}
+
+	#[test]
+	fn test_plain_text_latin1_unit() {
+		// 21 °C, where the degree sign isn't valid ASCII or UTF-8
+		let input = Bytes::new(&[0x01, 0x7C, 0x02, b'C', 0xB0, 0x15]);
+
+		let result = Record::parse.parse(input).unwrap();
+
+		assert_eq!(result.unit().as_deref(), Some("°C"));
+		assert_eq!(result.scaled_value(), Some(21.0));
+	}
+
+	#[cfg(feature = "decimal")]
+	#[test]
+	fn test_scaled_decimal() {
+		// 0.3 m³, which f64 can't represent exactly
+		let input = Bytes::new(&[0x01, 0x15, 0x03]);
+
+		let result = Record::parse.parse(input).unwrap();
+
+		assert_ne!(result.scaled_value(), Some(0.3));
+		assert_eq!(
+			result.scaled_decimal(),
+			Some(rust_decimal::Decimal::new(3, 1))
+		);
+	}
+
+	#[cfg(feature = "decimal")]
+	#[test]
+	fn test_scaled_decimal_positive_exponent() {
+		// 5 * 10^4 Wh
+		let input = Bytes::new(&[0x01, 0x07, 0x05]);
+
+		let result = Record::parse.parse(input).unwrap();
+
+		assert_eq!(
+			result.scaled_decimal(),
+			Some(rust_decimal::Decimal::from(50_000))
+		);
+	}
+
+	#[test]
+	fn test_volume_per_hour() {
+		// 125 litres per hour
+		let input = Bytes::new(&[0x02, 0x93, 0x22, 0x7D, 0x00]);
+
+		let result = Record::parse.parse(input).unwrap();
+
+		assert_eq!(result.unit().as_deref(), Some("m³/h"));
+		assert_eq!(result.scaled_value(), Some(0.125));
+	}
+
+	#[test]
+	fn test_variable_type_i() {
+		let input = Bytes::new(&[0x06, 0x6D, 0x00, 0x1A, 0x2F, 0x65, 0x11, 0x00]);
+
+		let result = Record::parse.parse(input).unwrap();
+
+		assert!(result.vib.value_type.is_date());
+		assert!(matches!(result.data, DataType::DateTimeI(_)));
+	}
+
+	#[rstest]
+	#[case::binary(&[0x04, 0xFD, 0x19, 0x78, 0x56, 0x34, 0x12])]
+	#[case::lvar(&[0x0D, 0xFD, 0x19, 0xE4, 0x78, 0x56, 0x34, 0x12])]
+	fn test_security_key(#[case] input: &[u8]) {
+		let result = Record::parse.parse(Bytes::new(input)).unwrap();
+
+		assert_eq!(result.data, DataType::Opaque(vec![0x78, 0x56, 0x34, 0x12]));
+	}
+
+	#[rstest]
+	#[case::sixteen_bytes(0xF0, 16)]
+	#[case::thirty_two_bytes(0xF4, 32)]
+	#[case::forty_eight_bytes(0xF5, 48)]
+	#[case::sixty_four_bytes(0xF6, 64)]
+	fn test_long_security_key(#[case] lvar: u8, #[case] length: usize) {
+		let key: Vec<u8> = (0..length as u8).collect();
+		let input = [&[0x0D, 0xFD, 0x19, lvar][..], &key].concat();
+
+		let result = Record::parse.parse(Bytes::new(&input)).unwrap();
+
+		assert_eq!(result.data, DataType::Opaque(key));
+		assert_eq!(result.encode(), Some(input));
+	}
+
+	#[test]
+	fn test_bcd_password() {
+		let input = Bytes::new(&[0x0A, 0xFD, 0x16, 0x34, 0x12]);
+
+		let result = Record::parse.parse(input).unwrap();
+
+		assert_eq!(result.data, DataType::Signed(1234));
+	}
+
+	#[rstest]
+	#[case::bcd(&[0x09, 0xFD, 0x0E, 0x11], Some("11"))]
+	#[case::bcd_leading_zero(&[0x0A, 0xFD, 0x0E, 0x05, 0x01], Some("0105"))]
+	#[case::binary(&[0x01, 0xFD, 0x0C, 0x02], Some("2"))]
+	#[case::string(&[0x0D, 0xFD, 0x0F, 0x03, b'0', b'.', b'1'], Some("1.0"))]
+	#[case::not_a_version(&[0x01, 0x13, 0x02], None)]
+	fn test_version_string(#[case] input: &[u8], #[case] expected: Option<&str>) {
+		let result = Record::parse.parse(Bytes::new(input)).unwrap();
+
+		assert_eq!(result.version_string().as_deref(), expected);
+	}
+
+	#[test]
+	fn test_customer_string() {
+		// "Lexi", sent backwards
+		let input = Bytes::new(&[0x0D, 0xFD, 0x11, 0x04, b'i', b'x', b'e', b'L']);
+
+		let result = Record::parse.parse(input).unwrap();
+
+		assert_eq!(result.data, DataType::String("Lexi".to_owned()));
+		assert_eq!(result.customer().as_deref(), Some("Lexi"));
+		assert_eq!(result.customer_location(), None);
+	}
+
+	#[rstest]
+	#[case::string(&[0x0D, 0xFD, 0x10, 0x03, b'1', b'A', b'B'], Some("BA1"))]
+	#[case::bcd(&[0x0C, 0xFD, 0x10, 0x78, 0x56, 0x34, 0x02], Some("02345678"))]
+	#[case::customer(&[0x0D, 0xFD, 0x11, 0x01, b'A'], None)]
+	fn test_customer_location(#[case] input: &[u8], #[case] expected: Option<&str>) {
+		let result = Record::parse.parse(Bytes::new(input)).unwrap();
+
+		assert_eq!(result.customer_location().as_deref(), expected);
+	}
+
+	#[rstest]
+	#[case::bcd(&[0x0C, 0x78, 0x56, 0x34, 0x12, 0x00], Some("00123456"))]
+	#[case::bcd_enhanced(&[0x0C, 0x79, 0x08, 0x07, 0x06, 0x05], Some("05060708"))]
+	#[case::binary(&[0x04, 0x78, 0x40, 0xE2, 0x01, 0x00], Some("123456"))]
+	#[case::enhanced_identification(
+		&[0x07, 0x79, 0x17, 0x58, 0x85, 0x06, 0x2D, 0x2C, 0x08, 0x04],
+		Some("06855817")
+	)]
+	#[case::address(&[0x01, 0x7A, 0x05], None)]
+	fn test_serial_number(#[case] input: &[u8], #[case] expected: Option<&str>) {
+		let result = Record::parse.parse(Bytes::new(input)).unwrap();
+
+		assert_eq!(result.serial_number().as_deref(), expected);
+	}
+
+	#[test]
+	fn test_itron_cf_51_versions() {
+		let data = read_test_file("./libmbus_test_data/test-frames/itron_cf_51.hex").unwrap();
+
+		let packet = Packet::parse.parse(Bytes::new(&data)).unwrap();
+
+		let Packet::Long {
+			message: MBusMessage::ResponseFromDevice(_, DeviceResponse::Full(frame)),
+			..
+		} = packet
+		else {
+			panic!("expected a response, got {packet:?}");
+		};
+		let versions: Vec<_> = frame
+			.records
+			.iter()
+			.filter_map(Record::version_string)
+			.collect();
+		assert_eq!(versions, ["11", "26"]);
+	}
+
+	#[rstest]
+	#[case::instantaneous(&[0x04, 0x06, 0x2A, 0x00, 0x00, 0x00], "energy (Wh, 10^3)", false)]
+	#[case::maximum(
+		&[0x14, 0x06, 0x2A, 0x00, 0x00, 0x00],
+		"energy (Wh, 10^3) [maximum value]",
+		false
+	)]
+	#[case::minimum(
+		&[0x24, 0x06, 0x2A, 0x00, 0x00, 0x00],
+		"energy (Wh, 10^3) [minimum value]",
+		false
+	)]
+	#[case::error_state(
+		&[0x34, 0x06, 0x2A, 0x00, 0x00, 0x00],
+		"energy (Wh, 10^3) [value during error state]",
+		true
+	)]
+	fn test_describe_function(
+		#[case] input: &[u8],
+		#[case] description: &str,
+		#[case] is_error_state: bool,
+	) {
+		let result = Record::parse.parse(Bytes::new(input)).unwrap();
+
+		assert_eq!(result.describe(), description);
+		assert_eq!(result.is_error_state_value(), is_error_state);
+		assert_eq!(result.scaled_value(), Some(42_000.0));
+	}
+
+	#[test]
+	fn test_siemens_rvd235_has_no_any() {
+		let data = read_test_file("./libmbus_test_data/test-frames/siemens_rvd235.hex").unwrap();
+
+		let packet = Packet::parse.parse(Bytes::new(&data)).unwrap();
+
+		let Packet::Long {
+			message: MBusMessage::ResponseFromDevice(_, DeviceResponse::Full(frame)),
+			..
+		} = packet
+		else {
+			panic!("expected a response, got {packet:?}");
+		};
+		assert!(!frame.records.is_empty());
+		for record in frame.records {
+			assert!(
+				!matches!(record.vib.value_type, ValueType::Any),
+				"{record:?}"
+			);
+		}
+	}
+
+	#[rstest]
+	// Volume isn't marked as unsigned, so this wraps around
+	#[case::signed(&[0x07, 0x13], DataType::Signed(-2))]
+	#[case::unsigned(&[0x07, 0x78], DataType::Unsigned(u64::MAX - 1))]
+	fn test_u64_above_i64_max(#[case] header: &[u8], #[case] expected: DataType) {
+		let mut input = header.to_vec();
+		input.extend((u64::MAX - 1).to_le_bytes());
+
+		let result = Record::parse.parse(Bytes::new(&input)).unwrap();
+
+		assert_eq!(result.data, expected);
+	}
+
+	#[test]
+	fn test_leading_idle_filler() {
+		let input = Bytes::new(&[0x2F, 0x2F, 0x02, 0x13, 0x05, 0x00]);
+
+		let result = Record::parse.parse(input).unwrap();
+
+		assert_eq!(result.data, DataType::Signed(5));
+		assert_eq!(result.length, 4);
+	}
+
+	#[test]
+	fn test_readout_selection() {
+		let input = Bytes::new(&[
+			0x08, 0x13, // Current volume
+			0x2F, // Idle filler
+			0x48, 0x6D, // Date and time in storage 1
+		]);
+
+		let result = parse_readout_selection.parse(input).unwrap();
+
+		let selectors: Vec<_> = result
+			.iter()
+			.map(|selector| match selector {
+				ReadoutSelector::Value(dib, vib) => {
+					(dib.raw_type, dib.storage, vib.value_type.clone())
+				}
+				_ => panic!("expected a single value, got {selector:?}"),
+			})
+			.collect();
+		assert_eq!(
+			selectors,
+			[
+				(
+					RawDataType::SelectionForReadout,
+					0,
+					ValueType::Volume(VolumeUnit::M3, -3)
+				),
+				(
+					RawDataType::SelectionForReadout,
+					1,
+					ValueType::VariableDateTime
+				),
+			]
+		);
+	}
+
+	#[test]
+	fn test_encode_all_records() {
+		let result = encode_readout_selection(&[ReadoutSelector::All]).unwrap();
+
+		assert_eq!(result, [0x7F]);
+		assert_eq!(
+			parse_readout_selection.parse(Bytes::new(&result)),
+			Ok(vec![ReadoutSelector::All])
+		);
+	}
+
+	#[test]
+	fn test_encode_readout_selection() {
+		let input = [
+			0x08, 0x13, // Current volume
+			0x48, 0x6D, // Date and time in storage 1
+		];
+		let selectors = parse_readout_selection.parse(Bytes::new(&input)).unwrap();
+
+		assert_eq!(
+			encode_readout_selection(&selectors).as_deref(),
+			Some(input.as_slice())
+		);
+	}
+
+	#[test]
+	fn test_encode_any_value() {
+		let (dib, _) = volume_blocks(RawDataType::SelectionForReadout);
+		let dib = DataInfoBlock { storage: 1, ..dib };
+
+		let result = encode_readout_selection(&[ReadoutSelector::AnyValue(dib)]).unwrap();
+
+		assert_eq!(result, [0x48, 0x7E]);
+		let selectors = parse_readout_selection.parse(Bytes::new(&result)).unwrap();
+		assert!(matches!(
+			selectors[..],
+			[ReadoutSelector::AnyValue(DataInfoBlock { storage: 1, .. })]
+		));
+	}
+
+	#[test]
+	fn test_encode_selection_with_data() {
+		let (dib, vib) = volume_blocks(RawDataType::Binary(4));
+
+		assert_eq!(
+			encode_readout_selection(&[ReadoutSelector::Value(dib, vib)]),
+			None
+		);
+	}
+
+	#[test]
+	fn test_readout_selection_with_data() {
+		let input = Bytes::new(&[0x08, 0x13, 0x01, 0x13, 0x05]);
+
+		parse_readout_selection.parse(input).unwrap_err();
+	}
+
+	#[test]
+	fn test_error_qualifier() {
+		// Volume with a "data overflow" VIFE, meaning the value is above range
+		let input = Bytes::new(&[0x02, 0x93, 0x16, 0xFF, 0x7F]);
+
+		let result = Record::parse.parse(input).unwrap();
+
+		assert_eq!(
+			result.error_qualifier(),
+			Some(RecordErrorQualifier::DataOverflow)
+		);
+	}
+
+	#[rstest]
+	#[case::no_vifes(&[0x02, 0x13, 0x05, 0x00])]
+	#[case::no_error(&[0x02, 0x93, 0x00, 0x05, 0x00])]
+	#[case::per_hour(&[0x02, 0x93, 0x22, 0x05, 0x00])]
+	fn test_no_error_qualifier(#[case] input: &[u8]) {
+		let result = Record::parse.parse(Bytes::new(input)).unwrap();
+
+		assert_eq!(result.error_qualifier(), None);
+	}
+
+	#[test]
+	fn test_whole_record() {
+		let input = Bytes::new(&[0x04, 0x13, 0x2A, 0x00, 0x00, 0x00]);
+
+		let result = Record::parse.parse(input).unwrap();
+
+		assert_eq!(
+			result,
+			Record {
+				dib: DataInfoBlock {
+					raw_type: RawDataType::Binary(4),
+					function: DataFunction::InstantaneousValue,
+					storage: 0,
+					tariff: 0,
+					device: 0,
+					is_obis: false,
+					raw: vec![0x04],
+				},
+				vib: ValueInfoBlock {
+					value_type: ValueType::Volume(VolumeUnit::M3, -3),
+					extra_vifes: None,
+					raw: vec![0x13],
+				},
+				data: DataType::Signed(42),
+				length: 6,
+			}
+		);
+	}
+
+	#[rstest]
+	#[case::no_data(&[0x08, 0x13], 2)]
+	#[case::binary(&[0x04, 0x13, 0x2A, 0x00, 0x00, 0x00], 6)]
+	#[case::difes_and_vifes(&[0x84, 0x80, 0x40, 0x14, 0x00, 0x00, 0x00, 0x00], 8)]
+	#[case::lvar(&[0x0D, 0xFD, 0x11, 0x03, 0x41, 0x42, 0x43], 7)]
+	fn test_length(#[case] input: &[u8], #[case] expected: usize) {
+		let mut trailing = input.to_vec();
+		trailing.extend([0x04, 0x13, 0x00, 0x00, 0x00, 0x00]);
+		let mut stream = Bytes::new(&trailing);
+
+		let result = Record::parse.parse_next(&mut stream).unwrap();
+
+		assert_eq!(result.length, expected);
+		assert_eq!(stream.len(), 6, "only the first record should be consumed");
+	}
+
+	#[test]
+	fn test_enhanced_identification() {
+		let input = Bytes::new(&[0x07, 0x79, 0x78, 0x56, 0x34, 0x12, 0x24, 0x23, 0x2A, 0x07]);
+
+		let result = Record::parse.parse(input).unwrap();
+
+		assert_eq!(
+			result.data,
+			DataType::EnhancedIdentification(EnhancedIdentification {
+				identifier: 12345678,
+				manufacturer: "HYD".parse().unwrap(),
+				version: 0x2A,
+				device_type: DeviceType::WaterMeter(WaterMeterType::Potable),
+			})
+		);
+	}
+
+	#[test]
+	fn test_enhanced_identification_bcd() {
+		// From minol_minocal_wr3.hex
+		let input = Bytes::new(&[0x8C, 0x40, 0x79, 0x78, 0x56, 0x34, 0x12]);
+
+		let result = Record::parse.parse(input).unwrap();
+
+		assert_eq!(result.data, DataType::Signed(12345678));
+	}
+
+	#[rstest]
+	#[case::fabrication_number(&[0x04, 0x78, 0xFE, 0xFF, 0xFF, 0xFF], 4_294_967_294)]
+	#[case::address(&[0x01, 0x7A, 0xFD], 253)]
+	fn test_unsigned_identifiers(#[case] input: &[u8], #[case] expected: u64) {
+		let result = Record::parse.parse(Bytes::new(input)).unwrap();
+
+		assert_eq!(result.data, DataType::Unsigned(expected));
+	}
+
+	#[rstest]
+	#[case::matching(&[0x02, 0x5B, 0x2A, 0x00], false)]
+	#[case::real(&[0x05, 0x5B, 0x00, 0x00, 0x28, 0x42], false)]
+	#[case::mismatching(&[0x07, 0x5B, 0x2A, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], true)]
+	#[case::no_limit(&[0x07, 0x03, 0x2A, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], false)]
+	fn test_width_mismatch(#[case] input: &[u8], #[case] mismatch: bool) {
+		let result = Record::parse.parse(Bytes::new(input)).unwrap();
+		assert_eq!(result.has_width_mismatch(), mismatch);
+
+		let options = ParseOptions {
+			strict_widths: true,
+			..Default::default()
+		};
+		let strict = Record::parse_with(options).parse(Bytes::new(input));
+		assert_eq!(strict.is_err(), mismatch);
+	}
+
+	#[rstest]
+	#[case::parameter_activation(&[0x01, 0xFD, 0x66, 0x81], DataType::Unsigned(0x81))]
+	#[case::supplier_information(
+		&[0x02, 0xFD, 0x67, 0x34, 0x12],
+		DataType::Opaque(vec![0x34, 0x12])
+	)]
+	fn test_supplier_values(#[case] input: &[u8], #[case] expected: DataType) {
+		let result = Record::parse.parse(Bytes::new(input)).unwrap();
+
+		assert_eq!(result.data, expected);
+		assert_eq!(result.encode().as_deref(), Some(input));
+	}
+
+	#[rstest]
+	#[case::reset_counter(&[0x01, 0xFD, 0x60, 0xFF], 0xFF)]
+	#[case::cumulation_counter(&[0x02, 0xFD, 0x61, 0x00, 0x80], 0x8000)]
+	#[case::meter_stopped(&[0x04, 0xFD, 0x75, 0xFF, 0xFF, 0xFF, 0xFF], 0xFFFF_FFFF)]
+	fn test_counters_unsigned(#[case] input: &[u8], #[case] expected: u64) {
+		let result = Record::parse.parse(Bytes::new(input)).unwrap();
+
+		assert_eq!(result.data, DataType::Unsigned(expected));
+		assert_eq!(result.scaled_value(), Some(expected as f64));
+	}
+
+	#[test]
+	fn test_digital_output() {
+		let input = Bytes::new(&[0x02, 0xFD, 0x1A, 0x05, 0x80]);
+
+		let result = Record::parse.parse(input).unwrap();
+
+		let mut expected = vec![false; 16];
+		expected[0] = true;
+		expected[2] = true;
+		expected[15] = true;
+		assert_eq!(result.digital_states(), Some(expected));
+		assert_eq!(result.scaled_value(), None);
+	}
+
+	#[test]
+	fn test_remote_control() {
+		let input = Bytes::new(&[0x01, 0xFD, 0x1F, 0x12]);
+
+		let result = Record::parse.parse(input).unwrap();
+
+		assert_eq!(
+			result.remote_control_flags(),
+			Some(vec![false, true, false, false, true, false, false, false])
+		);
+		assert_eq!(result.digital_states(), None);
+		assert_eq!(result.scaled_value(), None);
+	}
+
+	#[rstest]
+	#[case::binary(
+		&[0x06, 0xFD, 0x73, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06],
+		&[0x01, 0x02, 0x03, 0x04, 0x05, 0x06]
+	)]
+	#[case::lvar(&[0x0D, 0xFD, 0x73, 0xE3, 0x01, 0x02, 0x03], &[0x01, 0x02, 0x03])]
+	fn test_listening_window(#[case] input: &[u8], #[case] expected: &[u8]) {
+		let result = Record::parse.parse(Bytes::new(input)).unwrap();
+
+		assert_eq!(result.vib.value_type, ValueType::ListeningWindowManagement);
+		assert_eq!(result.data, DataType::Opaque(expected.to_vec()));
+		assert_eq!(result.scaled_value(), None);
+		assert_eq!(result.encode().as_deref(), Some(input));
+	}
+
+	#[rstest]
+	#[case::binary(&[0x01, 0xFD, 0x17, 0x81], &[0, 7], 8)]
+	#[case::wide(&[0x03, 0xFD, 0x17, 0x00, 0x00, 0x01], &[16], 24)]
+	#[case::lvar(&[0x0D, 0xFD, 0x17, 0xE2, 0x00, 0x02], &[9], 16)]
+	#[case::zero_width(&[0x0D, 0xFD, 0x17, 0xE0], &[], 0)]
+	fn test_error_flags(#[case] input: &[u8], #[case] set: &[usize], #[case] width: usize) {
+		let result = Record::parse.parse(Bytes::new(input)).unwrap();
+
+		let mut expected = vec![false; width];
+		for bit in set {
+			expected[*bit] = true;
+		}
+		assert_eq!(result.data, DataType::Bitfield(expected.clone()));
+		assert_eq!(result.error_flags(), Some(expected));
+		assert_eq!(result.scaled_value(), None);
+	}
+
+	#[test]
+	fn test_digital_states_other_types() {
+		let input = Bytes::new(&[0x02, 0xFD, 0x17, 0x05, 0x80]);
+
+		let result = Record::parse.parse(input).unwrap();
+
+		assert_eq!(result.digital_states(), None);
+	}
+
+	#[test]
+	fn test_battery_change_date() {
+		let input = Bytes::new(&[0x02, 0xFD, 0x70, 0x8C, 0x11]);
+
+		let result = Record::parse.parse(input).unwrap();
+
+		assert_eq!(result.vib.value_type, ValueType::DateAndTimeOfBatteryChange);
+		assert!(
+			matches!(
+				result.data,
+				DataType::Date(TypeGDate {
+					day: 12,
+					month: 1,
+					..
+				})
+			),
+			"{:?}",
+			result.data
+		);
+	}
+
+	#[test]
+	fn test_battery_change_date_time() {
+		let input = Bytes::new(&[0x04, 0xFD, 0x70, 0x32, 0x37, 0x1F, 0x15]);
+
+		let result = Record::parse.parse(input).unwrap();
+
+		assert_eq!(result.vib.value_type, ValueType::DateAndTimeOfBatteryChange);
+		assert!(
+			matches!(
+				result.data,
+				DataType::DateTimeF(TypeFDateTime {
+					minute: 50,
+					hour: 23,
+					day: 31,
+					month: 5,
+					..
+				})
+			),
+			"{:?}",
+			result.data
+		);
+	}
+
+	#[rstest]
+	#[case::not_specified(0x00, None)]
+	#[case::monday(0x01, Some(Weekday::Monday))]
+	#[case::sunday(0x07, Some(Weekday::Sunday))]
+	#[case::too_big(0x08, None)]
+	fn test_day_of_week(#[case] day: u8, #[case] expected: Option<Weekday>) {
+		let result = Record::parse
+			.parse(Bytes::new(&[0x01, 0xFD, 0x63, day]))
+			.unwrap();
+
+		assert_eq!(result.day_of_week(), expected);
+		assert_eq!(result.week_number(), None);
+	}
+
+	#[rstest]
+	#[case::not_specified(0x00, None)]
+	#[case::first(0x01, Some(1))]
+	#[case::last(0x35, Some(53))]
+	#[case::too_big(0x36, None)]
+	fn test_week_number(#[case] week: u8, #[case] expected: Option<u8>) {
+		let result = Record::parse
+			.parse(Bytes::new(&[0x01, 0xFD, 0x64, week]))
+			.unwrap();
+
+		assert_eq!(result.week_number(), expected);
+		assert_eq!(result.day_of_week(), None);
+	}
+
+	#[test]
+	fn test_datetime_with_second() {
+		// 2008-05-31 23:50 and then 42 seconds
+		let datetime = Record::parse
+			.parse(Bytes::new(&[0x04, 0x6D, 0x32, 0x37, 0x1F, 0x15]))
+			.unwrap();
+		let second = Record::parse
+			.parse(Bytes::new(&[0x01, 0xFD, 0x2B, 0x2A]))
+			.unwrap();
+
+		assert_eq!(second.time_point_second(), Some(42));
+		let result = datetime.datetime_with_second(&second).unwrap();
+		assert_eq!(result.to_string(), "2008-05-31 23:50:42");
+		assert!(result.leap_year);
+		assert_eq!(second.datetime_with_second(&datetime), None);
+	}
+
+	#[rstest]
+	#[case::old_meter([0x32, 0x17, 0xBF, 0xA5], Some("1985-05-31 23:50:42"))]
+	#[case::after_2080([0x32, 0x37, 0xBF, 0xA5], None)]
+	#[case::every_year([0x32, 0x37, 0xFF, 0xF5], Some("****-05-31 23:50:42"))]
+	#[case::every_hour_and_minute([0x3F, 0x3F, 0x1F, 0x15], Some("2008-05-31 31:63:42"))]
+	fn test_datetime_with_second_years(#[case] date: [u8; 4], #[case] expected: Option<&str>) {
+		let datetime = Record::parse
+			.parse(Bytes::new(&[&[0x04, 0x6D][..], &date].concat()))
+			.unwrap();
+		let second = Record::parse
+			.parse(Bytes::new(&[0x01, 0xFD, 0x2B, 0x2A]))
+			.unwrap();
+
+		let result = datetime.datetime_with_second(&second);
+
+		assert_eq!(result.map(|date| date.to_string()).as_deref(), expected);
+	}
+
+	#[test]
+	fn test_datetime_with_second_other_storage() {
+		let datetime = Record::parse
+			.parse(Bytes::new(&[0x44, 0x6D, 0x32, 0x37, 0x1F, 0x15]))
+			.unwrap();
+		let second = Record::parse
+			.parse(Bytes::new(&[0x01, 0xFD, 0x2B, 0x2A]))
+			.unwrap();
+
+		assert_eq!(datetime.datetime_with_second(&second), None);
+	}
+
+	#[rstest]
+	#[case::special_supplier_information(&[0x03, 0xFD, 0x67, 0x01, 0x02, 0x83])]
+	#[case::operator_specific(&[0x03, 0xFD, 0x2A, 0x01, 0x02, 0x83])]
+	#[case::manufacturer_specific(&[0x03, 0x7F, 0x01, 0x02, 0x83])]
+	#[case::manufacturer_specific_lvar(&[0x0D, 0xFF, 0x00, 0xE3, 0x01, 0x02, 0x83])]
+	fn test_vendor_specific(#[case] input: &[u8]) {
+		let result = Record::parse.parse(Bytes::new(input)).unwrap();
+
+		assert_eq!(result.data, DataType::Opaque(vec![0x01, 0x02, 0x83]));
+		assert_eq!(result.scaled_value(), None);
+	}
+
+	#[test]
+	fn test_storage_accessors() {
+		let input = Bytes::new(&[
+			0x04, 0x13, 0x01, 0x00, 0x00, 0x00, // Storage 0
+			0x44, 0x13, 0x02, 0x00, 0x00, 0x00, // Storage 1
+			0x84, 0x10, 0x13, 0x03, 0x00, 0x00, 0x00, // Tariff 1
+			0xD4, 0x41, 0x13, 0x04, 0x00, 0x00, 0x00, // Maximum, storage 3, device 1
+		]);
+
+		let frame = Frame::parse.parse(input).unwrap();
+
+		let summary: Vec<_> = frame
+			.records
+			.iter()
+			.map(|record| {
+				(
+					record.storage(),
+					record.tariff(),
+					record.device(),
+					*record.function(),
+				)
+			})
+			.collect();
+		assert_eq!(
+			summary,
+			[
+				(0, 0, 0, DataFunction::InstantaneousValue),
+				(1, 0, 0, DataFunction::InstantaneousValue),
+				(0, 1, 0, DataFunction::InstantaneousValue),
+				(3, 0, 1, DataFunction::MaximumValue),
+			]
+		);
+	}
+
+	#[test]
+	fn test_encode_round_trip() {
+		let data =
+			read_test_file("./libmbus_test_data/test-frames/kamstrup_multical_601.hex").unwrap();
+
+		let packet = Packet::parse.parse(Bytes::new(&data)).unwrap();
+
+		let Packet::Long {
+			message: MBusMessage::ResponseFromDevice(_, DeviceResponse::Full(frame)),
+			..
+		} = packet
+		else {
+			panic!("expected a response, got {packet:?}");
+		};
+		// Start, length, length, start, C, A, CI and then the 12 byte header
+		let mut input = &data[19..];
+		for record in frame.records {
+			let encoded = record.encode().unwrap();
+			assert_eq!(encoded, input[..record.length], "{record:?}");
+			input = &input[record.length..];
+
+			let reparsed = Record::parse.parse(Bytes::new(&encoded)).unwrap();
+			assert_eq!(reparsed, record);
+		}
+	}
+
+	#[rstest]
+	#[case::lvar_string(&[0x0D, 0xFD, 0x11, 0x03, 0x43, 0x42, 0x41])]
+	#[case::lvar_positive_bcd(&[0x0D, 0x13, 0xC2, 0x34, 0x12])]
+	#[case::lvar_negative_bcd(&[0x0D, 0x13, 0xD1, 0x05])]
+	#[case::lvar_binary(&[0x0D, 0xFD, 0x0C, 0xE2, 0x34, 0x12])]
+	#[case::bcd_error(&[0x0A, 0x13, 0x1A, 0x00])]
+	#[case::real(&[0x05, 0x13, 0x00, 0x00, 0xC0, 0x3F])]
+	#[case::selection(&[0x08, 0x13])]
+	fn test_encode(#[case] input: &[u8]) {
+		let record = Record::parse.parse(Bytes::new(input)).unwrap();
+
+		assert_eq!(record.encode().as_deref(), Some(input));
+	}
+
+	#[test]
+	fn test_encode_mismatched_type() {
+		let (dib, vib) = volume_blocks(RawDataType::Binary(1));
+		let record = Record {
+			dib,
+			vib,
+			data: DataType::Real(1.5),
+			length: 0,
+		};
+
+		assert_eq!(record.encode(), None);
+	}
+}
@@ -2,8 +2,14 @@
 // Licensed under the EUPL-1.2
 #![allow(dead_code)]
 
-use crate::parse::error::MBResult;
-use crate::parse::types::string::parse_length_prefix_ascii;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::sync::{PoisonError, RwLock};
+
+use crate::parse::error::{MBResult, MBusError};
+use crate::parse::options::ParseOptions;
+use crate::parse::transport_layer::manufacturer::{InvalidManufacturerCode, ManufacturerCode};
+use crate::parse::types::string::{encode_latin1, parse_length_prefix_latin1};
 use crate::parse::types::BitsInput;
 use libmbus_macros::vif;
 use winnow::binary::bits;
@@ -15,7 +21,9 @@ const VIF_EXTENSION_1: u8 = 0b0111_1011;
 const VIF_EXTENSION_2: u8 = 0b0111_1101;
 const VIF_ASCII: u8 = 0b0111_1100;
 const VIF_MANUFACTURER: u8 = 0b0111_1111;
-const VIF_ANY: u8 = 0b0111_1110;
+pub(crate) const VIF_ANY: u8 = 0b0111_1110;
+const VIFE_COMBINABLE_EXTENSION: u8 = 0b0111_1100;
+const VIFE_MANUFACTURER: u8 = 0b0111_1111;
 
 const MASK_N: u8 = 0b0000_0001;
 const MASK_NN: u8 = 0b0000_0011;
@@ -24,34 +32,170 @@ const MASK_NNNN: u8 = 0b0000_1111;
 const DURATION_MASK: u8 = 0b0000_0011;
 
 #[allow(dead_code)]
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ValueInfoBlock {
 	pub value_type: ValueType,
 	/// Currently unparsed VIFE that modify the actual value
 	/// TODO: parse them!
 	pub extra_vifes: Option<Vec<u8>>,
+	/// The VIF and VIFE bytes this block was parsed from, including any plain
+	/// text unit
+	pub raw: Vec<u8>,
+}
+
+/// Turns the raw VIFEs following a manufacturer specific VIF into an actual
+/// value type, for manufacturers who have documented what theirs mean.
+/// Returning `None` leaves the value as `ValueType::ManufacturerSpecific`
+pub type ManufacturerVifeHandler = fn(&[u8]) -> Option<ValueType>;
+
+static MANUFACTURER_VIFE_HANDLERS: RwLock<BTreeMap<ManufacturerCode, ManufacturerVifeHandler>> =
+	RwLock::new(BTreeMap::new());
+
+/// Registers a handler that will be called whenever a record with a
+/// manufacturer specific VIF (or manufacturer specific container) is found in
+/// a frame from the manufacturer with the three letter code `code`. Fails if
+/// `code` isn't three uppercase letters, as it could never match.
+///
+/// Registering a second handler for the same manufacturer replaces the first.
+pub fn register_manufacturer_vife(
+	code: &str,
+	handler: ManufacturerVifeHandler,
+) -> Result<(), InvalidManufacturerCode> {
+	MANUFACTURER_VIFE_HANDLERS
+		.write()
+		.unwrap_or_else(PoisonError::into_inner)
+		.insert(code.parse()?, handler);
+	Ok(())
+}
+
+fn manufacturer_vife_handler(code: ManufacturerCode) -> Option<ManufacturerVifeHandler> {
+	MANUFACTURER_VIFE_HANDLERS
+		.read()
+		.unwrap_or_else(PoisonError::into_inner)
+		.get(&code)
+		.copied()
 }
 
 pub fn parse_vif_byte(input: &mut BitsInput<'_>) -> MBResult<(bool, u8)> {
 	(bits::bool, bits::take(7_usize)).parse_next(input)
 }
 
+/// The most VIFEs that will be read after a VIF (not counting the one that
+/// selects an extension table) before the frame is assumed to be corrupt
+pub const MAX_VIFES: usize = 10;
+
 pub fn dump_remaining_vifes(input: &mut BitsInput<'_>) -> MBResult<Vec<u8>> {
-	let mut ret = Vec::new();
-	loop {
-		let (extension, value) = parse_vif_byte
-			.context(StrContext::Label("VIFE"))
-			.parse_next(input)?;
-		ret.push(value);
-		if !extension {
-			break;
+	dump_remaining_vifes_with_limit(MAX_VIFES).parse_next(input)
+}
+
+/// Reads VIFEs until one without the extension bit set, failing if there are
+/// more than `limit` of them
+pub fn dump_remaining_vifes_with_limit<'a>(
+	limit: usize,
+) -> impl Parser<BitsInput<'a>, Vec<u8>, MBusError> {
+	move |input: &mut BitsInput<'a>| {
+		let mut ret = Vec::new();
+		loop {
+			let vife_checkpoint = input.checkpoint();
+			let (extension, value) = parse_vif_byte
+				.context(StrContext::Label("VIFE"))
+				.parse_next(input)?;
+			if ret.len() >= limit {
+				return Err(
+					ErrMode::from_error_kind(input, ErrorKind::Verify).add_context(
+						input,
+						&vife_checkpoint,
+						StrContext::Label("too many VIFEs"),
+					),
+				);
+			}
+			ret.push(value);
+			if !extension {
+				break;
+			}
 		}
+		Ok(ret)
 	}
-	Ok(ret)
 }
 
 impl ValueInfoBlock {
 	pub fn parse(input: &mut BitsInput<'_>) -> MBResult<Self> {
+		Self::parse_with(ParseOptions::default()).parse_next(input)
+	}
+
+	/// Parses the VIB using `options`. If the options say which manufacturer
+	/// the record came from, any VIFE handler registered for them is used to
+	/// decode manufacturer specific value types.
+	pub fn parse_with<'a>(options: ParseOptions) -> impl Parser<BitsInput<'a>, Self, MBusError> {
+		move |input: &mut BitsInput<'a>| {
+			let (mut vib, (raw, _, _)) =
+				(|input: &mut BitsInput<'a>| Self::parse_inner(options, input))
+					.with_recognized()
+					.parse_next(input)?;
+			vib.raw = raw.to_vec();
+			if let (
+				ValueType::ManufacturerSpecific | ValueType::ManufacturerSpecificContainer,
+				Some(handler),
+			) = (
+				&vib.value_type,
+				options.manufacturer.and_then(manufacturer_vife_handler),
+			) {
+				let raw_vifes = vib.extra_vifes.as_deref().unwrap_or_default();
+				if let Some(value_type) = handler(raw_vifes) {
+					vib.value_type = value_type;
+				}
+			}
+			Ok(vib)
+		}
+	}
+
+	/// Builds the VIF and VIFE bytes for this block from its value type and
+	/// any extra VIFEs, by looking the value type up in the VIF tables. Dates
+	/// that were worked out from the data width are sent as a variable date.
+	/// Returns `None` if no VIF decodes to the value type, such as one from a
+	/// manufacturer VIFE handler or one with an exponent out of range.
+	pub fn encode(&self) -> Option<Vec<u8>> {
+		let mut result = match &self.value_type {
+			ValueType::Any => vec![VIF_ANY],
+			ValueType::ManufacturerSpecific => vec![VIF_MANUFACTURER],
+			ValueType::PlainText(_) => vec![VIF_ASCII],
+			ValueType::Invalid(value) => vec![*value],
+			ValueType::RetiredCode(table, value) | ValueType::ReservedCode(table, value) => {
+				if *table == VIFTable::Table12 && *value == VIF_EXTENSION_2 {
+					return None;
+				}
+				[table.prefix(), &[*value]].concat()
+			}
+			ValueType::TypeFDateTime
+			| ValueType::TypeJTime
+			| ValueType::TypeIDateTime
+			| ValueType::TypeMDatetime => vec![vif!(E110 1101)],
+			value_type => find_vif(value_type)?,
+		};
+		let vifes = self.extra_vifes.as_deref().unwrap_or_default();
+
+		// Every byte but the last needs the extension bit set
+		let last = result.len() - 1;
+		for byte in &mut result[..last] {
+			*byte |= 0b1000_0000;
+		}
+		if !vifes.is_empty() {
+			result[last] |= 0b1000_0000;
+		}
+		if let ValueType::PlainText(text) = &self.value_type {
+			let text = encode_latin1(text)?;
+			result.push(u8::try_from(text.len()).ok()?);
+			result.extend(text);
+		}
+		for (i, vife) in vifes.iter().enumerate() {
+			let extension = u8::from(i + 1 < vifes.len()) << 7;
+			result.push(extension | vife & 0b0111_1111);
+		}
+		Some(result)
+	}
+
+	fn parse_inner(options: ParseOptions, input: &mut BitsInput<'_>) -> MBResult<Self> {
 		let vif_checkpoint = input.checkpoint();
 		let (mut extension, raw_value) = parse_vif_byte
 			.context(StrContext::Label("initial VIF"))
@@ -102,7 +246,7 @@ impl ValueInfoBlock {
 				//
 				// Since this is the only examples of plain text VIF data I
 				// have, I'm going to have to trust it, but I'm very confused
-				bits::bytes(parse_length_prefix_ascii)
+				bits::bytes(parse_length_prefix_latin1)
 					.map(ValueType::PlainText)
 					.context(StrContext::Label("plain text VIF data"))
 					.parse_next(input)?
@@ -114,7 +258,7 @@ impl ValueInfoBlock {
 
 		// TODO: These should be parsed (except for the manufacturer!)
 		let extra_vifes = if extension {
-			Some(dump_remaining_vifes(input)?)
+			Some(dump_remaining_vifes_with_limit(options.max_vifes).parse_next(input)?)
 		} else {
 			None
 		};
@@ -122,10 +266,200 @@ impl ValueInfoBlock {
 		Ok(Self {
 			value_type,
 			extra_vifes,
+			raw: Vec::new(),
 		})
 	}
 }
 
+impl ValueInfoBlock {
+	/// Decodes the combinable VIFEs that follow the VIF. Manufacturer specific
+	/// VIFEs are never decoded as they mean whatever the manufacturer wants.
+	pub fn combinable_vifes(&self) -> Vec<CombinableVife> {
+		if matches!(
+			self.value_type,
+			ValueType::ManufacturerSpecific | ValueType::ManufacturerSpecificContainer
+		) {
+			return Vec::new();
+		}
+		let mut ret = Vec::new();
+		for &value in self.extra_vifes.iter().flatten() {
+			ret.push(parse_table_15(value));
+			// Anything after these aren't from table 15
+			if matches!(value, VIFE_COMBINABLE_EXTENSION | VIFE_MANUFACTURER) {
+				break;
+			}
+		}
+		ret
+	}
+
+	/// Which of the VIF tables the value type was decoded from, worked out
+	/// from the raw VIF bytes. Plain text, manufacturer specific and "any"
+	/// VIFs are all part of the primary table, even if a manufacturer VIFE
+	/// handler has decoded them into something else.
+	pub fn source_table(&self) -> VIFTable {
+		let mut raw = self.raw.iter().map(|value| value & 0b0111_1111);
+		match (raw.next(), raw.next()) {
+			(Some(VIF_EXTENSION_2), Some(VIF_EXTENSION_2)) => VIFTable::Table13,
+			(Some(VIF_EXTENSION_2), Some(_)) => VIFTable::Table12,
+			(Some(VIF_EXTENSION_1), Some(_)) => VIFTable::Table14,
+			_ => VIFTable::Table10,
+		}
+	}
+
+	/// The value type's exponent, adjusted by any multiplicative correction
+	/// factors in the VIFEs
+	pub fn exponent(&self) -> Exponent {
+		self.combinable_vifes()
+			.into_iter()
+			.filter_map(|vife| match vife {
+				CombinableVife::Multiplier(exp) => Some(exp),
+				_ => None,
+			})
+			.fold(self.value_type.exponent(), Exponent::saturating_add)
+	}
+
+	/// The value type's unit, turned into a rate by any "per" VIFEs
+	pub fn unit(&self) -> Option<Cow<'_, str>> {
+		let divisors: Vec<_> = self
+			.combinable_vifes()
+			.into_iter()
+			.filter_map(|vife| match vife {
+				CombinableVife::Per(unit) => Some(unit),
+				_ => None,
+			})
+			.collect();
+		let unit = self.value_type.unit();
+		if divisors.is_empty() {
+			return unit.map(Cow::Borrowed);
+		}
+		let mut ret = unit.unwrap_or("1").to_owned();
+		for divisor in divisors {
+			ret.push('/');
+			ret.push_str(divisor);
+		}
+		Some(Cow::Owned(ret))
+	}
+}
+
+/// EN 13757-3:2018 Table 15 — Combinable (orthogonal) VIFE-code extension
+/// table. Only the codes that change the value's unit or scale, or that say
+/// the value is in error, are decoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CombinableVife {
+	/// The value is per this unit, eg m³ per hour
+	Per(&'static str),
+	/// The value needs to be multiplied by ten to the power of this
+	Multiplier(Exponent),
+	/// The device is reporting that something is wrong with this record
+	Error(RecordErrorQualifier),
+	Other(u8),
+}
+
+/// EN 13757-3:2018 Table 18 — Codes for record errors, sent by a device in the
+/// VIFE of a record it couldn't fill in properly
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordErrorQualifier {
+	// DIF errors
+	TooManyDIFEs,
+	StorageNumberNotImplemented,
+	UnitNumberNotImplemented,
+	TariffNumberNotImplemented,
+	FunctionNotImplemented,
+	DataClassNotImplemented,
+	DataSizeNotImplemented,
+	// VIF errors
+	TooManyVIFEs,
+	IllegalVIFGroup,
+	IllegalVIFExponent,
+	VIFDIFMismatch,
+	UnimplementedAction,
+	// Data errors
+	NoDataAvailable, // "undefined value"
+	DataOverflow,    // The value is above the range the device can measure
+	DataUnderflow,   // The value is below the range the device can measure
+	DataError,
+	// Other errors
+	PrematureEndOfRecord,
+	Reserved(u8),
+}
+
+impl RecordErrorQualifier {
+	fn decode(value: u8) -> Self {
+		match value {
+			vif!(E000 0001) => Self::TooManyDIFEs,
+			vif!(E000 0010) => Self::StorageNumberNotImplemented,
+			vif!(E000 0011) => Self::UnitNumberNotImplemented,
+			vif!(E000 0100) => Self::TariffNumberNotImplemented,
+			vif!(E000 0101) => Self::FunctionNotImplemented,
+			vif!(E000 0110) => Self::DataClassNotImplemented,
+			vif!(E000 0111) => Self::DataSizeNotImplemented,
+			vif!(E000 1011) => Self::TooManyVIFEs,
+			vif!(E000 1100) => Self::IllegalVIFGroup,
+			vif!(E000 1101) => Self::IllegalVIFExponent,
+			vif!(E000 1110) => Self::VIFDIFMismatch,
+			vif!(E000 1111) => Self::UnimplementedAction,
+			vif!(E001 0101) => Self::NoDataAvailable,
+			vif!(E001 0110) => Self::DataOverflow,
+			vif!(E001 0111) => Self::DataUnderflow,
+			vif!(E001 1000) => Self::DataError,
+			vif!(E001 1100) => Self::PrematureEndOfRecord,
+			_ => Self::Reserved(value),
+		}
+	}
+}
+
+fn parse_table_15(value: u8) -> CombinableVife {
+	match value {
+		vif!(E010 0000) => CombinableVife::Per("s"),
+		vif!(E010 0001) => CombinableVife::Per("min"),
+		vif!(E010 0010) => CombinableVife::Per("h"),
+		vif!(E010 0011) => CombinableVife::Per("day"),
+		vif!(E010 0100) => CombinableVife::Per("week"),
+		vif!(E010 0101) => CombinableVife::Per("month"),
+		vif!(E010 0110) => CombinableVife::Per("year"),
+		vif!(E010 0111) => CombinableVife::Per("revolution"),
+		vif!(E010 1100) => CombinableVife::Per("l"),
+		vif!(E010 1101) => CombinableVife::Per("m³"),
+		vif!(E010 1110) => CombinableVife::Per("kg"),
+		vif!(E010 1111) => CombinableVife::Per("K"),
+		vif!(E011 0000) => CombinableVife::Per("kWh"),
+		vif!(E011 0001) => CombinableVife::Per("GJ"),
+		vif!(E011 0010) => CombinableVife::Per("kW"),
+		vif!(E011 0011) => CombinableVife::Per("(K·l)"),
+		vif!(E011 0100) => CombinableVife::Per("V"),
+		vif!(E011 0101) => CombinableVife::Per("A"),
+		vif!(E111 0nnn) => CombinableVife::Multiplier(exp(MASK_NNN, value, -6)),
+		vif!(E111 1101) => CombinableVife::Multiplier(3),
+		// E000 0000 is the "no error" code
+		vif!(E000 nnnn) if value & 0x7F != 0 => {
+			CombinableVife::Error(RecordErrorQualifier::decode(value))
+		}
+		vif!(E001 nnnn) => CombinableVife::Error(RecordErrorQualifier::decode(value)),
+		_ => CombinableVife::Other(value),
+	}
+}
+
+/// The VIF bytes that decode to `value_type`, found by trying every code in
+/// each table
+fn find_vif(value_type: &ValueType) -> Option<Vec<u8>> {
+	let tables = [
+		VIFTable::Table10,
+		VIFTable::Table12,
+		VIFTable::Table13,
+		VIFTable::Table14,
+	];
+	tables.into_iter().find_map(|table| {
+		let codes = match table {
+			VIFTable::Table10 => 0..=0b0111_1010,
+			_ => 0..=0b0111_1111,
+		};
+		codes
+			.filter(|code| table != VIFTable::Table12 || *code != VIF_EXTENSION_2)
+			.find(|code| table.decode(*code) == *value_type)
+			.map(|code| [table.prefix(), &[code]].concat())
+	})
+}
+
 fn exp(mask: u8, value: u8, offset: i8) -> Exponent {
 	(value & mask) as i8 + offset
 }
@@ -247,12 +581,18 @@ fn parse_table_14(value: u8) -> ValueType {
 	// "These codes were used until 2004, now they are reserved for future use."
 	match value {
 		vif!(E000 000n) => ValueType::Energy(EnergyUnit::MWh, exp(MASK_N, value, -1)),
-		vif!(E000 001n) => ValueType::ReactiveEnergy(exp(MASK_N, value, 0)),
-		vif!(E000 010n) => ValueType::ApparentEnergy(exp(MASK_N, value, 0)),
+		vif!(E000 001n) => {
+			ValueType::ReactiveEnergy(ReactiveEnergyUnit::KVARh, exp(MASK_N, value, 0))
+		}
+		vif!(E000 010n) => {
+			ValueType::ApparentEnergy(ApparentEnergyUnit::KVAh, exp(MASK_N, value, 0))
+		}
 		vif!(E000 100n) => ValueType::Energy(EnergyUnit::GJ, exp(MASK_N, value, -1)),
 		vif!(E000 11nn) => ValueType::Energy(EnergyUnit::MCal, exp(MASK_NN, value, -1)),
 		vif!(E001 000n) => ValueType::Volume(VolumeUnit::M3, exp(MASK_N, value, 2)),
-		vif!(E001 01nn) => ValueType::ReactivePower(exp(MASK_NN, value, -3)),
+		vif!(E001 01nn) => {
+			ValueType::ReactivePower(ReactivePowerUnit::KVAR, exp(MASK_NN, value, -3))
+		}
 		vif!(E001 100n) => ValueType::Mass(MassUnit::T, exp(MASK_N, value, 2)),
 		vif!(E001 101n) => ValueType::RelativeHumidity(exp(MASK_N, value, -1)),
 		vif!(E010 0000) => ValueType::Volume(VolumeUnit::Feet3, 0),
@@ -263,7 +603,9 @@ fn parse_table_14(value: u8) -> ValueType {
 		vif!(E010 1011) => ValueType::PhaseUI,
 		vif!(E010 11nn) => ValueType::Frequency(exp(MASK_NN, value, -3)),
 		vif!(E011 000n) => ValueType::Power(PowerUnit::GJph, exp(MASK_N, value, -1)),
-		vif!(E011 01nn) => ValueType::ApparentPower(exp(MASK_NN, value, -1)),
+		vif!(E011 01nn) => {
+			ValueType::ApparentPower(ApparentPowerUnit::KVA, exp(MASK_NN, value, -1))
+		}
 		0b0101_1000..=0b0110_0111 => ValueType::RetiredCode(VIFTable::Table14, value),
 		vif!(E110 1000) => ValueType::ResultingPowerFactorK,
 		vif!(E110 1001) => ValueType::ThermalOutputRatingFactorKq,
@@ -279,7 +621,8 @@ fn parse_table_14(value: u8) -> ValueType {
 	}
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum VIFTable {
 	Table10,
 	Table12,
@@ -287,7 +630,29 @@ pub enum VIFTable {
 	Table14,
 }
 
-#[derive(Debug)]
+impl VIFTable {
+	/// The VIF bytes that select this table, without their extension bits
+	fn prefix(&self) -> &'static [u8] {
+		match self {
+			Self::Table10 => &[],
+			Self::Table12 => &[VIF_EXTENSION_2],
+			Self::Table13 => &[VIF_EXTENSION_2, VIF_EXTENSION_2],
+			Self::Table14 => &[VIF_EXTENSION_1],
+		}
+	}
+
+	fn decode(&self, value: u8) -> ValueType {
+		match self {
+			Self::Table10 => parse_table_10(value),
+			Self::Table12 => parse_table_12(value),
+			Self::Table13 => parse_table_13(value),
+			Self::Table14 => parse_table_14(value),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum DurationType {
 	Seconds,
 	Minutes,
@@ -317,9 +682,21 @@ impl DurationType {
 			_ => unreachable!(),
 		}
 	}
+
+	pub fn symbol(&self) -> &'static str {
+		match self {
+			Self::Seconds => "s",
+			Self::Minutes => "min",
+			Self::Hours => "h",
+			Self::Days => "d",
+			Self::Months => "month",
+			Self::Years => "year",
+		}
+	}
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum EnergyUnit {
 	Wh,   // Wh
 	J,    // J
@@ -328,7 +705,20 @@ pub enum EnergyUnit {
 	GJ,   // GJ
 }
 
-#[derive(Debug)]
+impl EnergyUnit {
+	pub fn symbol(&self) -> &'static str {
+		match self {
+			Self::Wh => "Wh",
+			Self::J => "J",
+			Self::MWh => "MWh",
+			Self::MCal => "MCal",
+			Self::GJ => "GJ",
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum PowerUnit {
 	W,    // W
 	Jph,  // J/h
@@ -336,21 +726,125 @@ pub enum PowerUnit {
 	GJph, // GJ/h
 }
 
-#[derive(Debug)]
+impl PowerUnit {
+	pub fn symbol(&self) -> &'static str {
+		match self {
+			Self::W => "W",
+			Self::Jph => "J/h",
+			Self::MW => "MW",
+			Self::GJph => "GJ/h",
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum VolumeUnit {
 	M3,    // m³
 	Feet3, // feet³
 }
 
-#[derive(Debug)]
+impl VolumeUnit {
+	pub fn symbol(&self) -> &'static str {
+		match self {
+			Self::M3 => "m³",
+			Self::Feet3 => "feet³",
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum MassUnit {
 	Kg, // kg
 	T,  // t
 }
 
+impl MassUnit {
+	pub fn symbol(&self) -> &'static str {
+		match self {
+			Self::Kg => "kg",
+			Self::T => "t",
+		}
+	}
+}
+
+/// The VIF tables only have codes for the kilo versions of the reactive and
+/// apparent units, so that's all these hold
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum ReactiveEnergyUnit {
+	KVARh, // kVARh
+}
+
+impl ReactiveEnergyUnit {
+	pub fn symbol(&self) -> &'static str {
+		match self {
+			Self::KVARh => "kVARh",
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum ApparentEnergyUnit {
+	KVAh, // kVAh
+}
+
+impl ApparentEnergyUnit {
+	pub fn symbol(&self) -> &'static str {
+		match self {
+			Self::KVAh => "kVAh",
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum ReactivePowerUnit {
+	KVAR, // kVAR
+}
+
+impl ReactivePowerUnit {
+	pub fn symbol(&self) -> &'static str {
+		match self {
+			Self::KVAR => "kVAR",
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum ApparentPowerUnit {
+	KVA, // kVA
+}
+
+impl ApparentPowerUnit {
+	pub fn symbol(&self) -> &'static str {
+		match self {
+			Self::KVA => "kVA",
+		}
+	}
+}
+
 pub type Exponent = i8;
 
-#[derive(Debug)]
+/// A broad grouping of what physical quantity a value represents, regardless
+/// of the actual unit it's measured in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quantity {
+	Energy,
+	Volume,
+	Power,
+	Temperature,
+	Time,
+	Identification,
+	Diagnostic,
+	Other,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum ValueType {
 	// Special
 	Any,
@@ -450,14 +944,14 @@ pub enum ValueType {
 	// Table 13 — 2nd level VIFE code extension table
 	CurrentlySelectedApplication,
 	// Table 14 — Alternate extended VIF-code table
-	ReactiveEnergy(Exponent),
-	ApparentEnergy(Exponent),
-	ReactivePower(Exponent),
+	ReactiveEnergy(ReactiveEnergyUnit, Exponent),
+	ApparentEnergy(ApparentEnergyUnit, Exponent),
+	ReactivePower(ReactivePowerUnit, Exponent),
 	RelativeHumidity(Exponent),
 	PhaseUU, // "volt. to volt."
 	PhaseUI, // "volt. to current"
 	Frequency(Exponent),
-	ApparentPower(Exponent),
+	ApparentPower(ApparentPowerUnit, Exponent),
 	ColdWarmTemperatureLimit(Exponent),
 	CumulativeMaxOfActivePower(Exponent),
 	ResultingPowerFactorK,
@@ -473,7 +967,13 @@ impl ValueType {
 	pub fn is_unsigned(&self) -> bool {
 		matches!(
 			self,
-			Self::UniqueMessageIdentification
+			Self::OnTime(_)
+				| Self::OperatingTime(_)
+				| Self::AveragingDuration(_)
+				| Self::ActualityDuration(_)
+				| Self::FabricationNumber
+				| Self::Address
+				| Self::UniqueMessageIdentification
 				| Self::DeviceType
 				| Self::Manufacturer
 				| Self::ParameterSetIdentification
@@ -485,26 +985,32 @@ impl ValueType {
 				| Self::Customer
 				| Self::AccessCodeUser
 				| Self::AccessCodeOperator
+				| Self::AccessCodeSystemOperator
 				| Self::AccessCodeDeveloper
 				| Self::Password
 				| Self::ErrorMask
 				| Self::SecurityKey
 				| Self::BaudRate
 				| Self::ResponseDelayTime
+				| Self::Retry
 				| Self::FirstStorageNumberForCyclicStorage
 				| Self::LastStorageNumberForCyclicStorage
 				| Self::SizeOfStorageBlock
 				| Self::DescriptorForTariffAndSubunit
+				| Self::StorageInterval(_)
 				| Self::TimePointSecond
 				| Self::DurationSinceLastReadout(_)
 				| Self::DurationOfTariff(_)
 				| Self::PeriodOfTarrif(_)
 				| Self::PeriodOfNominalDataTransmissions(_)
+				| Self::ResetCounter
+				| Self::CumulationCounter
 				| Self::DayOfWeek
 				| Self::WeekNumber
 				| Self::StateOfParameterActivation
 				| Self::SpecialSupplierInformation
 				| Self::DurationSinceLastCumulation(_)
+				| Self::OperatingTimeBattery(_)
 				| Self::RemainingBatteryLife(_)
 				| Self::NumberTimesMeterStopped
 				| Self::RelativeHumidity(_)
@@ -516,6 +1022,303 @@ impl ValueType {
 		)
 	}
 
+	/// Returns true if a negative value is something a meter could
+	/// legitimately report, such as a temperature below zero or energy flowing
+	/// in the opposite direction, as opposed to a value that's only signed
+	/// because of how the data was encoded
+	pub fn is_signed_meaningful(&self) -> bool {
+		matches!(
+			self,
+			Self::VolumeFlow(..)
+				| Self::MassFlow(..)
+				| Self::Power(..)
+				| Self::ReactivePower(..)
+				| Self::FlowTemperature(_)
+				| Self::ReturnTemperature(_)
+				| Self::TemperatureDifference(_)
+				| Self::ExternalTemperature(_)
+				| Self::ColdWarmTemperatureLimit(_)
+				| Self::Amperes(_)
+				| Self::PhaseUU
+				| Self::PhaseUI
+				| Self::RFLevel
+				| Self::Credit(_)
+				| Self::Debit(_)
+		)
+	}
+
+	/// Which broad quantity the value is. Volume and mass flows are rates
+	/// rather than amounts, so they're `Quantity::Other` instead of being
+	/// grouped with volume.
+	pub fn quantity(&self) -> Quantity {
+		match self {
+			Self::Energy(..) | Self::ReactiveEnergy(..) | Self::ApparentEnergy(..) => {
+				Quantity::Energy
+			}
+			Self::Volume(..) => Quantity::Volume,
+			Self::Power(..)
+			| Self::ReactivePower(..)
+			| Self::ApparentPower(..)
+			| Self::CumulativeMaxOfActivePower(_) => Quantity::Power,
+			Self::FlowTemperature(_)
+			| Self::ReturnTemperature(_)
+			| Self::TemperatureDifference(_)
+			| Self::ExternalTemperature(_)
+			| Self::ColdWarmTemperatureLimit(_) => Quantity::Temperature,
+			Self::OnTime(_)
+			| Self::OperatingTime(_)
+			| Self::TypeGDate
+			| Self::VariableDateTime
+			| Self::TypeFDateTime
+			| Self::TypeJTime
+			| Self::TypeIDateTime
+			| Self::TypeMDatetime
+			| Self::AveragingDuration(_)
+			| Self::ActualityDuration(_)
+			| Self::StorageInterval(_)
+			| Self::TimePointSecond
+			| Self::DurationSinceLastReadout(_)
+			| Self::StartDateTimeOfTariff
+			| Self::DurationOfTariff(_)
+			| Self::PeriodOfTarrif(_)
+			| Self::PeriodOfNominalDataTransmissions(_)
+			| Self::DayOfWeek
+			| Self::WeekNumber
+			| Self::TimePointOfDayChange
+			| Self::DurationSinceLastCumulation(_)
+			| Self::OperatingTimeBattery(_)
+			| Self::DateAndTimeOfBatteryChange
+			| Self::DSTTypeK
+			| Self::ListeningWindowManagement
+			| Self::RemainingBatteryLife(_) => Quantity::Time,
+			Self::FabricationNumber
+			| Self::EnhancedIdentification
+			| Self::Address
+			| Self::UniqueMessageIdentification
+			| Self::DeviceType
+			| Self::Manufacturer
+			| Self::ParameterSetIdentification
+			| Self::ModelVersion
+			| Self::HardwareVersionNumber
+			| Self::MetrologyFirmwareVersionNumber
+			| Self::OtherSoftwareVersionNumber
+			| Self::CustomerLocation
+			| Self::Customer => Quantity::Identification,
+			Self::ErrorFlags
+			| Self::ErrorMask
+			| Self::ResetCounter
+			| Self::CumulationCounter
+			| Self::NumberTimesMeterStopped
+			| Self::RFLevel => Quantity::Diagnostic,
+			Self::Any
+			| Self::PlainText(_)
+			| Self::ManufacturerSpecific
+			| Self::RetiredCode(..)
+			| Self::ReservedCode(..)
+			| Self::Invalid(_)
+			| Self::Mass(..)
+			| Self::VolumeFlow(..)
+			| Self::MassFlow(..)
+			| Self::Pressure(_)
+			| Self::HCA
+			| Self::Credit(_)
+			| Self::Debit(_)
+			| Self::AccessCodeUser
+			| Self::AccessCodeOperator
+			| Self::AccessCodeSystemOperator
+			| Self::AccessCodeDeveloper
+			| Self::Password
+			| Self::SecurityKey
+			| Self::DigitalOutput
+			| Self::DigitalInput
+			| Self::BaudRate
+			| Self::ResponseDelayTime
+			| Self::Retry
+			| Self::RemoteControl
+			| Self::FirstStorageNumberForCyclicStorage
+			| Self::LastStorageNumberForCyclicStorage
+			| Self::SizeOfStorageBlock
+			| Self::DescriptorForTariffAndSubunit
+			| Self::OperatorSpecific
+			| Self::Dimensionless
+			| Self::WirelessContainer
+			| Self::Volts(_)
+			| Self::Amperes(_)
+			| Self::ControlSignal
+			| Self::StateOfParameterActivation
+			| Self::SpecialSupplierInformation
+			| Self::ManufacturerSpecificContainer
+			| Self::CurrentlySelectedApplication
+			| Self::RelativeHumidity(_)
+			| Self::PhaseUU
+			| Self::PhaseUI
+			| Self::Frequency(_)
+			| Self::ResultingPowerFactorK
+			| Self::ThermalOutputRatingFactorKq
+			| Self::ThermalCouplingRatingFactorOverallKc
+			| Self::ThermalCouplingRatingFactorRoomSideKcr
+			| Self::ThermalCouplingRatingFactorHeaterSideKch
+			| Self::LowTemperatureRatingFactorKt
+			| Self::DisplayOutputScalingFactorKD => Quantity::Other,
+		}
+	}
+
+	/// The power of ten the raw value needs to be multiplied by to get the
+	/// actual value in [`ValueType::unit`]. Anything that isn't scaled (such
+	/// as counters and durations) has an exponent of 0.
+	pub fn exponent(&self) -> Exponent {
+		match self {
+			Self::Energy(_, exp)
+			| Self::Volume(_, exp)
+			| Self::Mass(_, exp)
+			| Self::Power(_, exp)
+			| Self::VolumeFlow(_, exp)
+			| Self::MassFlow(_, exp)
+			| Self::FlowTemperature(exp)
+			| Self::ReturnTemperature(exp)
+			| Self::TemperatureDifference(exp)
+			| Self::ExternalTemperature(exp)
+			| Self::Pressure(exp)
+			| Self::Credit(exp)
+			| Self::Debit(exp)
+			| Self::Volts(exp)
+			| Self::Amperes(exp)
+			| Self::ReactiveEnergy(_, exp)
+			| Self::ApparentEnergy(_, exp)
+			| Self::ReactivePower(_, exp)
+			| Self::RelativeHumidity(exp)
+			| Self::Frequency(exp)
+			| Self::ApparentPower(_, exp)
+			| Self::ColdWarmTemperatureLimit(exp)
+			| Self::CumulativeMaxOfActivePower(exp) => *exp,
+			// Both phases are measured in 0.1°
+			Self::PhaseUU | Self::PhaseUI => -1,
+			_ => 0,
+		}
+	}
+
+	/// The unit of the value once it's been scaled by [`ValueType::exponent`],
+	/// or `None` if it's a plain number (or not a number at all)
+	pub fn unit(&self) -> Option<&str> {
+		Some(match self {
+			Self::Energy(unit, _) => unit.symbol(),
+			Self::Volume(unit, _) => unit.symbol(),
+			Self::Mass(unit, _) => unit.symbol(),
+			Self::Power(unit, _) => unit.symbol(),
+			Self::CumulativeMaxOfActivePower(_) => "W",
+			Self::VolumeFlow(DurationType::Hours, _) => "m³/h",
+			Self::VolumeFlow(DurationType::Minutes, _) => "m³/min",
+			Self::VolumeFlow(DurationType::Seconds, _) => "m³/s",
+			Self::MassFlow(DurationType::Hours, _) => "kg/h",
+			Self::FlowTemperature(_)
+			| Self::ReturnTemperature(_)
+			| Self::ExternalTemperature(_)
+			| Self::ColdWarmTemperatureLimit(_) => "°C",
+			Self::TemperatureDifference(_) => "K",
+			Self::Pressure(_) => "bar",
+			Self::OnTime(duration)
+			| Self::OperatingTime(duration)
+			| Self::AveragingDuration(duration)
+			| Self::ActualityDuration(duration)
+			| Self::StorageInterval(duration)
+			| Self::DurationSinceLastReadout(duration)
+			| Self::DurationOfTariff(duration)
+			| Self::PeriodOfTarrif(duration)
+			| Self::PeriodOfNominalDataTransmissions(duration)
+			| Self::DurationSinceLastCumulation(duration)
+			| Self::OperatingTimeBattery(duration)
+			| Self::RemainingBatteryLife(duration) => duration.symbol(),
+			Self::Volts(_) => "V",
+			Self::Amperes(_) => "A",
+			Self::RFLevel => "dBm",
+			Self::RelativeHumidity(_) => "%",
+			Self::Frequency(_) => "Hz",
+			Self::ReactiveEnergy(unit, _) => unit.symbol(),
+			Self::ApparentEnergy(unit, _) => unit.symbol(),
+			Self::ReactivePower(unit, _) => unit.symbol(),
+			Self::ApparentPower(unit, _) => unit.symbol(),
+			Self::PhaseUU | Self::PhaseUI => "°",
+			// The plain text VIF sends its unit as a string
+			Self::PlainText(unit) => unit,
+			_ => return None,
+		})
+	}
+
+	/// Returns true for value types that hold binary data that shouldn't be
+	/// treated as a number, such as passwords, keys and vendor specific blobs
+	pub fn is_opaque(&self) -> bool {
+		matches!(
+			self,
+			Self::AccessCodeUser
+				| Self::AccessCodeOperator
+				| Self::AccessCodeSystemOperator
+				| Self::AccessCodeDeveloper
+				| Self::Password
+				| Self::SecurityKey
+				| Self::SpecialSupplierInformation
+				| Self::OperatorSpecific
+				| Self::ManufacturerSpecific
+				// Type L isn't decoded, but it's certainly not a number
+				| Self::ListeningWindowManagement
+		)
+	}
+
+	/// Returns true for all of the date and time types, including
+	/// `VariableDateTime` which is resolved into one of the others once the
+	/// data width is known
+	pub fn is_date(&self) -> bool {
+		matches!(
+			self,
+			Self::TypeGDate
+				| Self::VariableDateTime
+				| Self::TypeFDateTime
+				| Self::TypeJTime
+				| Self::TypeIDateTime
+				| Self::TypeMDatetime
+				| Self::DSTTypeK
+				| Self::DateAndTimeOfBatteryChange
+		)
+	}
+
+	/// The widest data field in bytes that makes sense for this value type, if
+	/// it has a natural limit. A temperature in an 8 byte field is almost
+	/// certainly a sign that the record has been misinterpreted.
+	pub fn max_width(&self) -> Option<usize> {
+		Some(match self {
+			Self::FlowTemperature(_)
+			| Self::ReturnTemperature(_)
+			| Self::TemperatureDifference(_)
+			| Self::ExternalTemperature(_)
+			| Self::ColdWarmTemperatureLimit(_)
+			| Self::Pressure(_) => 4,
+			Self::Address | Self::DayOfWeek | Self::WeekNumber => 1,
+			_ => return None,
+		})
+	}
+
+	/// The width in bytes of the data field this value type is always sent
+	/// with, if it has one. The date and time types are fixed by their
+	/// encoding, but most numbers can be sent at any width. Variable dates
+	/// have their type worked out from the width so don't have one either, and
+	/// nor does enhanced identification, which can be sent as just the
+	/// identification number.
+	pub fn expected_width(&self) -> Option<usize> {
+		Some(match self {
+			Self::TypeGDate => 2,
+			Self::TypeJTime => 3,
+			Self::TypeFDateTime | Self::DSTTypeK => 4,
+			Self::TypeIDateTime => 6,
+			Self::Manufacturer => 2,
+			Self::Address | Self::DeviceType | Self::DayOfWeek | Self::WeekNumber => 1,
+			_ => return None,
+		})
+	}
+
+	/// Whether the value is an amount of money, in the meter's local currency
+	pub fn is_monetary(&self) -> bool {
+		matches!(self, Self::Credit(_) | Self::Debit(_))
+	}
+
 	pub fn is_boolean(&self) -> bool {
 		matches!(
 			self,
@@ -523,3 +1326,739 @@ impl ValueType {
 		)
 	}
 }
+
+impl std::fmt::Display for ValueType {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let name = match self {
+			Self::Any => "any value",
+			Self::PlainText(_) => "plain text unit",
+			Self::ManufacturerSpecific => "manufacturer specific",
+			Self::RetiredCode(..) => "retired code",
+			Self::ReservedCode(..) => "reserved code",
+			Self::Invalid(_) => "invalid VIF",
+			Self::Energy(..) => "energy",
+			Self::Volume(..) => "volume",
+			Self::Mass(..) => "mass",
+			Self::OnTime(_) => "on time",
+			Self::OperatingTime(_) => "operating time",
+			Self::Power(..) => "power",
+			Self::VolumeFlow(..) => "volume flow",
+			Self::MassFlow(..) => "mass flow",
+			Self::FlowTemperature(_) => "flow temperature",
+			Self::ReturnTemperature(_) => "return temperature",
+			Self::TemperatureDifference(_) => "temperature difference",
+			Self::ExternalTemperature(_) => "external temperature",
+			Self::Pressure(_) => "pressure",
+			Self::TypeGDate => "date",
+			Self::VariableDateTime
+			| Self::TypeFDateTime
+			| Self::TypeIDateTime
+			| Self::TypeMDatetime => "date and time",
+			Self::TypeJTime => "time",
+			Self::HCA => "heat cost allocation",
+			Self::AveragingDuration(_) => "averaging duration",
+			Self::ActualityDuration(_) => "actuality duration",
+			Self::FabricationNumber => "fabrication number",
+			Self::EnhancedIdentification => "enhanced identification",
+			Self::Address => "bus address",
+			Self::Credit(_) => "credit",
+			Self::Debit(_) => "debit",
+			Self::UniqueMessageIdentification => "unique message identification",
+			Self::DeviceType => "device type",
+			Self::Manufacturer => "manufacturer",
+			Self::ParameterSetIdentification => "parameter set identification",
+			Self::ModelVersion => "model version",
+			Self::HardwareVersionNumber => "hardware version",
+			Self::MetrologyFirmwareVersionNumber => "metrology firmware version",
+			Self::OtherSoftwareVersionNumber => "other software version",
+			Self::CustomerLocation => "customer location",
+			Self::Customer => "customer",
+			Self::AccessCodeUser => "user access code",
+			Self::AccessCodeOperator => "operator access code",
+			Self::AccessCodeSystemOperator => "system operator access code",
+			Self::AccessCodeDeveloper => "developer access code",
+			Self::Password => "password",
+			Self::ErrorFlags => "error flags",
+			Self::ErrorMask => "error mask",
+			Self::SecurityKey => "security key",
+			Self::DigitalOutput => "digital output",
+			Self::DigitalInput => "digital input",
+			Self::BaudRate => "baud rate",
+			Self::ResponseDelayTime => "response delay time",
+			Self::Retry => "retry",
+			Self::RemoteControl => "remote control",
+			Self::FirstStorageNumberForCyclicStorage => "first cyclic storage number",
+			Self::LastStorageNumberForCyclicStorage => "last cyclic storage number",
+			Self::SizeOfStorageBlock => "storage block size",
+			Self::DescriptorForTariffAndSubunit => "tariff and subunit descriptor",
+			Self::StorageInterval(_) => "storage interval",
+			Self::OperatorSpecific => "operator specific",
+			Self::TimePointSecond => "time point second",
+			Self::DurationSinceLastReadout(_) => "duration since last readout",
+			Self::StartDateTimeOfTariff => "start of tariff",
+			Self::DurationOfTariff(_) => "duration of tariff",
+			Self::PeriodOfTarrif(_) => "period of tariff",
+			Self::Dimensionless => "dimensionless",
+			Self::WirelessContainer => "wireless container",
+			Self::PeriodOfNominalDataTransmissions(_) => "nominal transmission period",
+			Self::Volts(_) => "voltage",
+			Self::Amperes(_) => "current",
+			Self::ResetCounter => "reset counter",
+			Self::CumulationCounter => "cumulation counter",
+			Self::ControlSignal => "control signal",
+			Self::DayOfWeek => "day of week",
+			Self::WeekNumber => "week number",
+			Self::TimePointOfDayChange => "time point of day change",
+			Self::StateOfParameterActivation => "state of parameter activation",
+			Self::SpecialSupplierInformation => "special supplier information",
+			Self::DurationSinceLastCumulation(_) => "duration since last cumulation",
+			Self::OperatingTimeBattery(_) => "battery operating time",
+			Self::DateAndTimeOfBatteryChange => "battery change date",
+			Self::RFLevel => "RF level",
+			Self::DSTTypeK => "daylight saving time",
+			Self::ListeningWindowManagement => "listening window management",
+			Self::RemainingBatteryLife(_) => "remaining battery life",
+			Self::NumberTimesMeterStopped => "times the meter stopped",
+			Self::ManufacturerSpecificContainer => "manufacturer specific container",
+			Self::CurrentlySelectedApplication => "currently selected application",
+			Self::ReactiveEnergy(..) => "reactive energy",
+			Self::ApparentEnergy(..) => "apparent energy",
+			Self::ReactivePower(..) => "reactive power",
+			Self::RelativeHumidity(_) => "relative humidity",
+			Self::PhaseUU => "phase voltage to voltage",
+			Self::PhaseUI => "phase voltage to current",
+			Self::Frequency(_) => "frequency",
+			Self::ApparentPower(..) => "apparent power",
+			Self::ColdWarmTemperatureLimit(_) => "cold/warm temperature limit",
+			Self::CumulativeMaxOfActivePower(_) => "cumulative maximum active power",
+			Self::ResultingPowerFactorK => "resulting rating factor K",
+			Self::ThermalOutputRatingFactorKq => "thermal output rating factor Kq",
+			Self::ThermalCouplingRatingFactorOverallKc => "overall thermal coupling factor Kc",
+			Self::ThermalCouplingRatingFactorRoomSideKcr => "room side thermal coupling factor Kcr",
+			Self::ThermalCouplingRatingFactorHeaterSideKch => {
+				"heater side thermal coupling factor Kch"
+			}
+			Self::LowTemperatureRatingFactorKt => "low temperature rating factor Kt",
+			Self::DisplayOutputScalingFactorKD => "display output scaling factor KD",
+		};
+		let mut details = Vec::new();
+		match self {
+			Self::RetiredCode(table, code) | Self::ReservedCode(table, code) => {
+				let table = match table {
+					VIFTable::Table10 => 10,
+					VIFTable::Table12 => 12,
+					VIFTable::Table13 => 13,
+					VIFTable::Table14 => 14,
+				};
+				details.push(format!("table {table}, {code:#04X}"));
+			}
+			Self::Invalid(code) => details.push(format!("{code:#04X}")),
+			_ => {
+				if let Some(unit) = self.unit() {
+					details.push(unit.to_owned());
+				}
+				let exponent = self.exponent();
+				if exponent != 0 {
+					details.push(format!("10^{exponent}"));
+				}
+			}
+		}
+		if details.is_empty() {
+			write!(f, "{name}")
+		} else {
+			write!(f, "{name} ({})", details.join(", "))
+		}
+	}
+}
+
+#[cfg(test)]
+mod test_vif_tables {
+	use rstest::rstest;
+
+	use super::{
+		parse_table_10, parse_table_12, parse_table_13, parse_table_14, DurationType, EnergyUnit,
+		MassUnit, PowerUnit, VIFTable, ValueType, VolumeUnit,
+	};
+
+	#[test]
+	fn test_sweep() {
+		for value in 0x00..=0x7F {
+			parse_table_10(value);
+			parse_table_12(value);
+			parse_table_13(value);
+			parse_table_14(value);
+		}
+	}
+
+	#[test]
+	fn test_table_10_only_reserves_0x6f() {
+		// 0x7B to 0x7F are the extension, plain text, any and manufacturer
+		// specific VIFs, which never make it to the table
+		for value in 0x00..=0x7A {
+			let result = parse_table_10(value);
+			assert_eq!(
+				matches!(result, ValueType::ReservedCode(VIFTable::Table10, _)),
+				value == 0x6F,
+				"{value:#04X} => {result:?}"
+			);
+		}
+	}
+
+	#[rstest]
+	#[case(0x00, ValueType::Energy(EnergyUnit::Wh, -3))]
+	#[case(0x07, ValueType::Energy(EnergyUnit::Wh, 4))]
+	#[case(0x08, ValueType::Energy(EnergyUnit::J, 0))]
+	#[case(0x0F, ValueType::Energy(EnergyUnit::J, 7))]
+	#[case(0x10, ValueType::Volume(VolumeUnit::M3, -6))]
+	#[case(0x17, ValueType::Volume(VolumeUnit::M3, 1))]
+	#[case(0x18, ValueType::Mass(MassUnit::Kg, -3))]
+	#[case(0x1F, ValueType::Mass(MassUnit::Kg, 4))]
+	#[case(0x20, ValueType::OnTime(DurationType::Seconds))]
+	#[case(0x23, ValueType::OnTime(DurationType::Days))]
+	#[case(0x24, ValueType::OperatingTime(DurationType::Seconds))]
+	#[case(0x27, ValueType::OperatingTime(DurationType::Days))]
+	#[case(0x28, ValueType::Power(PowerUnit::W, -3))]
+	#[case(0x2F, ValueType::Power(PowerUnit::W, 4))]
+	#[case(0x30, ValueType::Power(PowerUnit::Jph, 0))]
+	#[case(0x37, ValueType::Power(PowerUnit::Jph, 7))]
+	#[case(0x38, ValueType::VolumeFlow(DurationType::Hours, -6))]
+	#[case(0x3F, ValueType::VolumeFlow(DurationType::Hours, 1))]
+	#[case(0x40, ValueType::VolumeFlow(DurationType::Minutes, -7))]
+	#[case(0x47, ValueType::VolumeFlow(DurationType::Minutes, 0))]
+	#[case(0x48, ValueType::VolumeFlow(DurationType::Seconds, -9))]
+	#[case(0x4F, ValueType::VolumeFlow(DurationType::Seconds, -2))]
+	#[case(0x50, ValueType::MassFlow(DurationType::Hours, -3))]
+	#[case(0x57, ValueType::MassFlow(DurationType::Hours, 4))]
+	#[case(0x58, ValueType::FlowTemperature(-3))]
+	#[case(0x5B, ValueType::FlowTemperature(0))]
+	#[case(0x5C, ValueType::ReturnTemperature(-3))]
+	#[case(0x5F, ValueType::ReturnTemperature(0))]
+	#[case(0x60, ValueType::TemperatureDifference(-3))]
+	#[case(0x63, ValueType::TemperatureDifference(0))]
+	#[case(0x64, ValueType::ExternalTemperature(-3))]
+	#[case(0x67, ValueType::ExternalTemperature(0))]
+	#[case(0x68, ValueType::Pressure(-3))]
+	#[case(0x6B, ValueType::Pressure(0))]
+	#[case(0x6C, ValueType::TypeGDate)]
+	#[case(0x6D, ValueType::VariableDateTime)]
+	#[case(0x6E, ValueType::HCA)]
+	#[case(0x70, ValueType::AveragingDuration(DurationType::Seconds))]
+	#[case(0x73, ValueType::AveragingDuration(DurationType::Days))]
+	#[case(0x74, ValueType::ActualityDuration(DurationType::Seconds))]
+	#[case(0x77, ValueType::ActualityDuration(DurationType::Days))]
+	#[case(0x78, ValueType::FabricationNumber)]
+	#[case(0x79, ValueType::EnhancedIdentification)]
+	#[case(0x7A, ValueType::Address)]
+	fn test_table_10(#[case] value: u8, #[case] expected: ValueType) {
+		assert_eq!(parse_table_10(value), expected);
+	}
+}
+
+#[cfg(test)]
+mod test_display {
+	use rstest::rstest;
+
+	use super::{DurationType, EnergyUnit, VIFTable, ValueType, VolumeUnit};
+
+	#[rstest]
+	#[case::energy(ValueType::Energy(EnergyUnit::Wh, -3), "energy (Wh, 10^-3)")]
+	#[case::volume(ValueType::Volume(VolumeUnit::M3, -6), "volume (m³, 10^-6)")]
+	#[case::no_exponent(ValueType::Energy(EnergyUnit::Wh, 0), "energy (Wh)")]
+	#[case::flow_temperature(ValueType::FlowTemperature(-1), "flow temperature (°C, 10^-1)")]
+	#[case::duration(ValueType::OnTime(DurationType::Hours), "on time (h)")]
+	#[case::plain_text(ValueType::PlainText("RPM".to_owned()), "plain text unit (RPM)")]
+	#[case::structural(ValueType::TypeFDateTime, "date and time")]
+	#[case::fabrication_number(ValueType::FabricationNumber, "fabrication number")]
+	#[case::reserved(
+		ValueType::ReservedCode(VIFTable::Table12, 0x12),
+		"reserved code (table 12, 0x12)"
+	)]
+	#[case::invalid(ValueType::Invalid(0x7F), "invalid VIF (0x7F)")]
+	fn test_display(#[case] value_type: ValueType, #[case] expected: &str) {
+		assert_eq!(value_type.to_string(), expected);
+	}
+}
+
+#[cfg(test)]
+mod test_max_width {
+	use rstest::rstest;
+
+	use super::{EnergyUnit, ValueType};
+
+	#[rstest]
+	#[case::flow_temperature(ValueType::FlowTemperature(-1), Some(4))]
+	#[case::pressure(ValueType::Pressure(-3), Some(4))]
+	#[case::address(ValueType::Address, Some(1))]
+	#[case::day_of_week(ValueType::DayOfWeek, Some(1))]
+	#[case::energy(ValueType::Energy(EnergyUnit::Wh, 3), None)]
+	#[case::fabrication_number(ValueType::FabricationNumber, None)]
+	fn test_max_width(#[case] value_type: ValueType, #[case] expected: Option<usize>) {
+		assert_eq!(value_type.max_width(), expected);
+	}
+}
+
+#[cfg(test)]
+mod test_expected_width {
+	use rstest::rstest;
+
+	use super::{EnergyUnit, ValueType};
+
+	#[rstest]
+	#[case::type_g(ValueType::TypeGDate, Some(2))]
+	#[case::type_j(ValueType::TypeJTime, Some(3))]
+	#[case::type_f(ValueType::TypeFDateTime, Some(4))]
+	#[case::type_k(ValueType::DSTTypeK, Some(4))]
+	#[case::type_i(ValueType::TypeIDateTime, Some(6))]
+	#[case::type_m(ValueType::TypeMDatetime, None)]
+	#[case::variable_date(ValueType::VariableDateTime, None)]
+	#[case::enhanced_identification(ValueType::EnhancedIdentification, None)]
+	#[case::day_of_week(ValueType::DayOfWeek, Some(1))]
+	#[case::address(ValueType::Address, Some(1))]
+	#[case::energy(ValueType::Energy(EnergyUnit::Wh, 3), None)]
+	#[case::fabrication_number(ValueType::FabricationNumber, None)]
+	#[case::flow_temperature(ValueType::FlowTemperature(-1), None)]
+	fn test_expected_width(#[case] value_type: ValueType, #[case] expected: Option<usize>) {
+		assert_eq!(value_type.expected_width(), expected);
+	}
+
+	#[rstest]
+	#[case::flow_temperature(ValueType::FlowTemperature(-1))]
+	#[case::return_temperature(ValueType::ReturnTemperature(-1))]
+	#[case::temperature_difference(ValueType::TemperatureDifference(-1))]
+	#[case::external_temperature(ValueType::ExternalTemperature(-1))]
+	#[case::temperature_limit(ValueType::ColdWarmTemperatureLimit(-1))]
+	#[case::pressure(ValueType::Pressure(-1))]
+	#[case::address(ValueType::Address)]
+	#[case::day_of_week(ValueType::DayOfWeek)]
+	#[case::week_number(ValueType::WeekNumber)]
+	fn test_within_max_width(#[case] value_type: ValueType) {
+		let max_width = value_type
+			.max_width()
+			.expect("value type must have a maximum width");
+
+		if let Some(width) = value_type.expected_width() {
+			assert!(
+				width <= max_width,
+				"expected width is wider than the maximum"
+			);
+		}
+	}
+}
+
+#[cfg(test)]
+mod test_signedness {
+	use rstest::rstest;
+
+	use super::{DurationType, EnergyUnit, PowerUnit, ValueType, VolumeUnit};
+
+	#[rstest]
+	#[case::energy(ValueType::Energy(EnergyUnit::Wh, 0), false, false)]
+	#[case::volume(ValueType::Volume(VolumeUnit::M3, -6), false, false)]
+	#[case::power(ValueType::Power(PowerUnit::W, 0), false, true)]
+	#[case::temperature_difference(ValueType::TemperatureDifference(-3), false, true)]
+	#[case::external_temperature(ValueType::ExternalTemperature(-3), false, true)]
+	#[case::rf_level(ValueType::RFLevel, false, true)]
+	#[case::on_time(ValueType::OnTime(DurationType::Hours), true, false)]
+	#[case::retry(ValueType::Retry, true, false)]
+	#[case::reset_counter(ValueType::ResetCounter, true, false)]
+	#[case::cumulation_counter(ValueType::CumulationCounter, true, false)]
+	#[case::meter_stopped(ValueType::NumberTimesMeterStopped, true, false)]
+	// Device specific, so there's no telling what the sign means
+	#[case::control_signal(ValueType::ControlSignal, false, false)]
+	#[case::access_code(ValueType::AccessCodeSystemOperator, true, false)]
+	#[case::storage_interval(ValueType::StorageInterval(DurationType::Days), true, false)]
+	#[case::fabrication_number(ValueType::FabricationNumber, true, false)]
+	#[case::address(ValueType::Address, true, false)]
+	fn test_classification(
+		#[case] value_type: ValueType,
+		#[case] unsigned: bool,
+		#[case] signed_meaningful: bool,
+	) {
+		assert_eq!(value_type.is_unsigned(), unsigned, "is_unsigned");
+		assert_eq!(
+			value_type.is_signed_meaningful(),
+			signed_meaningful,
+			"is_signed_meaningful"
+		);
+		assert!(
+			!(value_type.is_unsigned() && value_type.is_signed_meaningful()),
+			"a value can't be both"
+		);
+	}
+}
+
+#[cfg(test)]
+mod test_quantity {
+	use rstest::rstest;
+
+	use super::{
+		DurationType, EnergyUnit, PowerUnit, Quantity, ReactiveEnergyUnit, ValueType, VolumeUnit,
+	};
+
+	#[rstest]
+	#[case::energy(ValueType::Energy(EnergyUnit::Wh, 0), Quantity::Energy)]
+	#[case::reactive_energy(
+		ValueType::ReactiveEnergy(ReactiveEnergyUnit::KVARh, 0),
+		Quantity::Energy
+	)]
+	#[case::volume(ValueType::Volume(VolumeUnit::Feet3, 0), Quantity::Volume)]
+	#[case::volume_flow(ValueType::VolumeFlow(DurationType::Hours, -6), Quantity::Other)]
+	#[case::mass_flow(ValueType::MassFlow(DurationType::Hours, -3), Quantity::Other)]
+	#[case::power(ValueType::Power(PowerUnit::MW, -1), Quantity::Power)]
+	#[case::flow_temperature(ValueType::FlowTemperature(-3), Quantity::Temperature)]
+	#[case::date(ValueType::TypeGDate, Quantity::Time)]
+	#[case::on_time(ValueType::OnTime(DurationType::Days), Quantity::Time)]
+	#[case::fabrication_number(ValueType::FabricationNumber, Quantity::Identification)]
+	#[case::error_flags(ValueType::ErrorFlags, Quantity::Diagnostic)]
+	#[case::volts(ValueType::Volts(-9), Quantity::Other)]
+	#[case::manufacturer_specific(ValueType::ManufacturerSpecific, Quantity::Other)]
+	fn test_quantity(#[case] value_type: ValueType, #[case] expected: Quantity) {
+		assert_eq!(value_type.quantity(), expected);
+	}
+}
+
+#[cfg(test)]
+mod test_source_table {
+	use rstest::rstest;
+	use winnow::binary::bits;
+	use winnow::prelude::*;
+	use winnow::Bytes;
+
+	use super::{VIFTable, ValueInfoBlock};
+
+	#[rstest]
+	#[case::primary(&[0x13], VIFTable::Table10)]
+	#[case::primary_with_vife(&[0x93, 0x3C], VIFTable::Table10)]
+	#[case::manufacturer(&[0x7F], VIFTable::Table10)]
+	#[case::fd_extension(&[0xFD, 0x3A], VIFTable::Table12)]
+	#[case::fb_extension(&[0xFB, 0x1A], VIFTable::Table14)]
+	#[case::second_level(&[0xFD, 0xFD, 0x00], VIFTable::Table13)]
+	fn test_source_table(#[case] input: &[u8], #[case] expected: VIFTable) {
+		let result = bits::bits(ValueInfoBlock::parse)
+			.parse(Bytes::new(input))
+			.unwrap();
+
+		assert_eq!(result.source_table(), expected);
+	}
+}
+
+#[cfg(test)]
+mod test_encode {
+	use rstest::rstest;
+	use winnow::binary::bits;
+	use winnow::prelude::*;
+	use winnow::Bytes;
+
+	use super::{ValueInfoBlock, ValueType, VolumeUnit};
+
+	#[rstest]
+	#[case::primary(&[0x13])]
+	#[case::vifes(&[0x93, 0xFF, 0x00])]
+	#[case::table_12(&[0xFD, 0x11])]
+	#[case::table_13(&[0xFD, 0xFD, 0x02])]
+	#[case::table_14(&[0xFB, 0x1A])]
+	#[case::plain_text(&[0x7C, 0x03, 0x48, 0x43, 0x41])]
+	#[case::plain_text_vifes(&[0xFC, 0x02, 0x42, 0x41, 0x74])]
+	#[case::any(&[0x7E])]
+	#[case::manufacturer_specific(&[0xFF, 0x00])]
+	#[case::reserved(&[0xFD, 0x77])]
+	#[case::retired(&[0xFB, 0x22])]
+	#[case::invalid(&[0x7B])]
+	fn test_round_trip(#[case] input: &[u8]) {
+		let result = bits::bits(ValueInfoBlock::parse)
+			.parse(Bytes::new(input))
+			.unwrap();
+
+		assert_eq!(result.encode().as_deref(), Some(input));
+	}
+
+	#[rstest]
+	#[case::variable_date(ValueType::TypeFDateTime, Some(vec![0x6D]))]
+	#[case::exponent_out_of_range(ValueType::Volume(VolumeUnit::M3, 5), None)]
+	fn test_value_types(#[case] value_type: ValueType, #[case] expected: Option<Vec<u8>>) {
+		let vib = ValueInfoBlock {
+			value_type,
+			extra_vifes: None,
+			raw: Vec::new(),
+		};
+
+		assert_eq!(vib.encode(), expected);
+	}
+}
+
+#[cfg(test)]
+mod test_date_vifs {
+	use rstest::rstest;
+	use winnow::binary::bits;
+	use winnow::prelude::*;
+	use winnow::Bytes;
+
+	use super::{ValueInfoBlock, ValueType};
+
+	#[rstest]
+	#[case::type_g(&[0x6C], ValueType::TypeGDate)]
+	#[case::type_g_with_vife(&[0xEC, 0x22], ValueType::TypeGDate)]
+	#[case::variable(&[0x6D], ValueType::VariableDateTime)]
+	#[case::variable_with_vife(&[0xED, 0x22], ValueType::VariableDateTime)]
+	fn test_date_vifs(#[case] input: &[u8], #[case] expected: ValueType) {
+		let result = bits::bits(ValueInfoBlock::parse)
+			.parse(Bytes::new(input))
+			.unwrap();
+
+		assert_eq!(result.value_type, expected);
+	}
+}
+
+#[cfg(test)]
+mod test_is_date {
+	use rstest::rstest;
+
+	use super::{EnergyUnit, ValueType};
+
+	#[rstest]
+	#[case::type_g(ValueType::TypeGDate, true)]
+	#[case::variable(ValueType::VariableDateTime, true)]
+	#[case::type_f(ValueType::TypeFDateTime, true)]
+	#[case::type_j(ValueType::TypeJTime, true)]
+	#[case::type_i(ValueType::TypeIDateTime, true)]
+	#[case::type_m(ValueType::TypeMDatetime, true)]
+	#[case::type_k(ValueType::DSTTypeK, true)]
+	#[case::battery_change(ValueType::DateAndTimeOfBatteryChange, true)]
+	#[case::energy(ValueType::Energy(EnergyUnit::Wh, 0), false)]
+	#[case::dimensionless(ValueType::Dimensionless, false)]
+	fn test_is_date(#[case] value_type: ValueType, #[case] expected: bool) {
+		assert_eq!(value_type.is_date(), expected);
+	}
+}
+
+#[cfg(test)]
+mod test_unit {
+	use rstest::rstest;
+
+	use super::{
+		ApparentEnergyUnit, ApparentPowerUnit, DurationType, EnergyUnit, MassUnit,
+		ReactiveEnergyUnit, ReactivePowerUnit, ValueType, VolumeUnit,
+	};
+
+	#[rstest]
+	#[case::energy(ValueType::Energy(EnergyUnit::Wh, 3), Some("Wh"), 3)]
+	#[case::volume(ValueType::Volume(VolumeUnit::M3, -3), Some("m³"), -3)]
+	#[case::mass(ValueType::Mass(MassUnit::T, 2), Some("t"), 2)]
+	#[case::volume_flow(ValueType::VolumeFlow(DurationType::Minutes, -7), Some("m³/min"), -7)]
+	#[case::flow_temperature(ValueType::FlowTemperature(-1), Some("°C"), -1)]
+	#[case::temperature_difference(ValueType::TemperatureDifference(-2), Some("K"), -2)]
+	#[case::on_time(ValueType::OnTime(DurationType::Days), Some("d"), 0)]
+	#[case::volts(ValueType::Volts(-9), Some("V"), -9)]
+	#[case::dimensionless(ValueType::Dimensionless, None, 0)]
+	#[case::fabrication_number(ValueType::FabricationNumber, None, 0)]
+	#[case::hca(ValueType::HCA, None, 0)]
+	#[case::pressure(ValueType::Pressure(-3), Some("bar"), -3)]
+	#[case::frequency(ValueType::Frequency(-3), Some("Hz"), -3)]
+	#[case::relative_humidity(ValueType::RelativeHumidity(-1), Some("%"), -1)]
+	#[case::reactive_energy(
+		ValueType::ReactiveEnergy(ReactiveEnergyUnit::KVARh, 1),
+		Some("kVARh"),
+		1
+	)]
+	#[case::apparent_energy(
+		ValueType::ApparentEnergy(ApparentEnergyUnit::KVAh, 0),
+		Some("kVAh"),
+		0
+	)]
+	#[case::reactive_power(ValueType::ReactivePower(ReactivePowerUnit::KVAR, -3), Some("kVAR"), -3)]
+	#[case::apparent_power(ValueType::ApparentPower(ApparentPowerUnit::KVA, -1), Some("kVA"), -1)]
+	#[case::phase(ValueType::PhaseUI, Some("°"), -1)]
+	#[case::temperature_limit(ValueType::ColdWarmTemperatureLimit(-2), Some("°C"), -2)]
+	#[case::cumulative_max_power(ValueType::CumulativeMaxOfActivePower(1), Some("W"), 1)]
+	fn test_unit(#[case] value_type: ValueType, #[case] unit: Option<&str>, #[case] exponent: i8) {
+		assert_eq!(value_type.unit(), unit);
+		assert_eq!(value_type.exponent(), exponent);
+	}
+}
+
+#[cfg(test)]
+mod test_combinable_vifes {
+	use rstest::rstest;
+	use winnow::binary::bits;
+	use winnow::prelude::*;
+	use winnow::Bytes;
+
+	use super::{CombinableVife, ValueInfoBlock};
+
+	#[rstest]
+	#[case::no_vifes(&[0x13], Some("m³"), -3)]
+	#[case::per_hour(&[0x93, 0x22], Some("m³/h"), -3)]
+	#[case::per_day(&[0x93, 0x23], Some("m³/day"), -3)]
+	#[case::multiplier(&[0x93, 0x73], Some("m³"), -6)]
+	#[case::multiplier_1000(&[0x93, 0x7D], Some("m³"), 0)]
+	#[case::per_hour_and_multiplier(&[0x93, 0xA2, 0x75], Some("m³/h"), -4)]
+	#[case::dimensionless_per_hour(&[0xFD, 0xBA, 0x22], Some("1/h"), 0)]
+	#[case::after_manufacturer(&[0x93, 0xFF, 0x22], Some("m³"), -3)]
+	#[case::manufacturer_specific(&[0xFF, 0x22], None, 0)]
+	fn test_unit(#[case] input: &[u8], #[case] unit: Option<&str>, #[case] exponent: i8) {
+		let result = bits::bits(ValueInfoBlock::parse)
+			.parse(Bytes::new(input))
+			.unwrap();
+
+		assert_eq!(result.unit().as_deref(), unit);
+		assert_eq!(result.exponent(), exponent);
+	}
+
+	#[test]
+	fn test_decoding() {
+		let input = Bytes::new(&[0x93, 0xA2, 0xF5, 0x3A]);
+
+		let result = bits::bits(ValueInfoBlock::parse).parse(input).unwrap();
+
+		assert_eq!(
+			result.combinable_vifes(),
+			[
+				CombinableVife::Per("h"),
+				CombinableVife::Multiplier(-1),
+				CombinableVife::Other(0x3A),
+			]
+		);
+	}
+}
+
+#[cfg(test)]
+mod test_vife_limit {
+	use winnow::binary::bits;
+	use winnow::error::{ErrorKind, StrContext};
+	use winnow::prelude::*;
+	use winnow::Bytes;
+
+	use super::{dump_remaining_vifes_with_limit, ValueInfoBlock, MAX_VIFES};
+	use crate::parse::application_layer::record::Record;
+
+	#[test]
+	fn test_at_limit() {
+		let mut data = vec![0x93];
+		data.extend([0xA2; MAX_VIFES - 1]);
+		data.push(0x22);
+
+		let result = bits::bits(ValueInfoBlock::parse)
+			.parse(Bytes::new(&data))
+			.unwrap();
+
+		assert_eq!(result.extra_vifes.unwrap().len(), MAX_VIFES);
+	}
+
+	#[test]
+	fn test_pathological() {
+		let mut data = vec![0x93];
+		data.extend([0xFF; 100]);
+		data.push(0x00);
+
+		let result = bits::bits(ValueInfoBlock::parse)
+			.parse(Bytes::new(&data))
+			.unwrap_err();
+
+		let err = result.inner();
+		assert_eq!(err.kind(), ErrorKind::Verify);
+		assert_eq!(
+			err.context().next(),
+			Some(&StrContext::Label("too many VIFEs"))
+		);
+	}
+
+	#[test]
+	fn test_stuck_extension_bit() {
+		// 20 VIFEs with the extension bit set, then a byte of data
+		let mut data = vec![0x01, 0x93];
+		data.extend([0xA2; 20]);
+		data.push(0x2A);
+
+		let result = Record::parse.parse(Bytes::new(&data)).unwrap_err();
+
+		assert_eq!(
+			result.inner().context().next(),
+			Some(&StrContext::Label("too many VIFEs"))
+		);
+	}
+
+	#[test]
+	fn test_custom_limit() {
+		let input = Bytes::new(&[0xA2, 0xA2, 0x22]);
+
+		bits::bits(dump_remaining_vifes_with_limit(2))
+			.parse(input)
+			.unwrap_err();
+		bits::bits(dump_remaining_vifes_with_limit(3))
+			.parse(input)
+			.unwrap();
+	}
+}
+
+#[cfg(test)]
+mod test_manufacturer_vife {
+	use winnow::binary::bits;
+	use winnow::prelude::*;
+	use winnow::Bytes;
+
+	use super::{register_manufacturer_vife, ValueInfoBlock, ValueType, VolumeUnit};
+	use crate::parse::options::ParseOptions;
+	use crate::parse::transport_layer::manufacturer::InvalidManufacturerCode;
+
+	fn from(manufacturer: &str) -> ParseOptions {
+		ParseOptions {
+			manufacturer: Some(manufacturer.parse().unwrap()),
+			..ParseOptions::default()
+		}
+	}
+
+	fn dummy_handler(vifes: &[u8]) -> Option<ValueType> {
+		match vifes {
+			[0x01] => Some(ValueType::Volume(VolumeUnit::M3, -3)),
+			_ => None,
+		}
+	}
+
+	#[test]
+	fn test_registered_handler() {
+		register_manufacturer_vife("ZZA", dummy_handler).unwrap();
+		let input = Bytes::new(&[0xFF, 0x01]);
+
+		let result = bits::bits(ValueInfoBlock::parse_with(from("ZZA")))
+			.parse(input)
+			.unwrap();
+
+		assert!(matches!(
+			result.value_type,
+			ValueType::Volume(VolumeUnit::M3, -3)
+		));
+		assert_eq!(result.extra_vifes, Some(vec![0x01]));
+	}
+
+	#[test]
+	fn test_handler_declines() {
+		register_manufacturer_vife("ZZB", dummy_handler).unwrap();
+		let input = Bytes::new(&[0xFF, 0x02]);
+
+		let result = bits::bits(ValueInfoBlock::parse_with(from("ZZB")))
+			.parse(input)
+			.unwrap();
+
+		assert!(matches!(result.value_type, ValueType::ManufacturerSpecific));
+	}
+
+	#[test]
+	fn test_other_manufacturer() {
+		register_manufacturer_vife("ZZC", dummy_handler).unwrap();
+		let input = Bytes::new(&[0xFF, 0x01]);
+
+		let result = bits::bits(ValueInfoBlock::parse_with(from("ABB")))
+			.parse(input)
+			.unwrap();
+
+		assert!(matches!(result.value_type, ValueType::ManufacturerSpecific));
+	}
+
+	#[test]
+	fn test_invalid_code() {
+		assert_eq!(
+			register_manufacturer_vife("zzd", dummy_handler),
+			Err(InvalidManufacturerCode)
+		);
+	}
+}
@@ -1,23 +1,95 @@
 // Copyright 2023 Lexi Robinson
 // Licensed under the EUPL-1.2
 use winnow::error::{
-	AddContext, ContextError, ErrorConvert, ErrorKind, FromExternalError, InputError, ParserError,
-	StrContext,
+	AddContext, ContextError, ErrMode, ErrorConvert, ErrorKind, FromExternalError, InputError,
+	ParserError, StrContext,
 };
 use winnow::stream::Stream;
-use winnow::PResult;
+use winnow::{Bytes, PResult, Parser};
 
 /// Because the version of Winnow we're using doesn't let you use `ContextError`
 /// with the bit-level parsers I've had to wrap it in a struct I control so I
 /// can implement `ErrorConvert` and get it working again
+///
+/// While I'm here it also keeps track of how much input was left when the
+/// error happened, so that it can point out where in a packet things went wrong
 #[derive(Debug, Clone, PartialEq)]
-pub struct MBusError(ContextError<StrContext>, ErrorKind);
+pub struct MBusError(ContextError<StrContext>, ErrorKind, Option<usize>);
+
+/// Input types that can say how many bytes are left in them, which is used to
+/// work out where an error happened. Bit level inputs count the partially read
+/// byte as remaining.
+pub trait RemainingBytes {
+	fn remaining_bytes(&self) -> usize;
+}
+
+impl RemainingBytes for &Bytes {
+	fn remaining_bytes(&self) -> usize {
+		self.len()
+	}
+}
+
+impl RemainingBytes for &[u8] {
+	fn remaining_bytes(&self) -> usize {
+		self.len()
+	}
+}
+
+impl<I: RemainingBytes> RemainingBytes for (I, usize) {
+	fn remaining_bytes(&self) -> usize {
+		self.0.remaining_bytes()
+	}
+}
+
+const HEX_DUMP_WIDTH: usize = 16;
 
 pub type MBResult<O> = PResult<O, MBusError>;
 
+/// The packet is valid but uses a feature this library doesn't implement yet,
+/// such as encryption or compact frames
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnsupportedFeature(pub &'static str);
+
+impl std::fmt::Display for UnsupportedFeature {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{} are not supported yet", self.0)
+	}
+}
+
+impl std::error::Error for UnsupportedFeature {}
+
+/// Creates an error for input that can't be parsed because the feature it
+/// uses isn't implemented. These are cut errors so that they aren't masked by
+/// any alternatives that might otherwise have been tried.
+pub(crate) fn unsupported<I: Stream + RemainingBytes>(
+	input: &I,
+	token_start: &<I as Stream>::Checkpoint,
+	feature: &'static str,
+) -> ErrMode<MBusError> {
+	ErrMode::Cut(
+		MBusError::from_external_error(input, ErrorKind::Verify, UnsupportedFeature(feature))
+			.add_context(input, token_start, StrContext::Label(feature)),
+	)
+}
+
+/// Parses `data`, which has been cut out of a larger input and had `trailing`
+/// bytes of that input after it. Anything that sits inside something else,
+/// like the user data of a long frame or the records in a container, has to
+/// be parsed this way so that error offsets still point at the right byte of
+/// the whole input rather than being short by however much came after it.
+pub(crate) fn parse_within<'a, O>(
+	mut parser: impl Parser<&'a Bytes, O, MBusError>,
+	data: &'a [u8],
+	trailing: usize,
+) -> MBResult<O> {
+	parser
+		.parse_next(&mut Bytes::new(data))
+		.map_err(|err| err.map(|err| err.with_trailing(trailing)))
+}
+
 impl MBusError {
 	pub fn new() -> Self {
-		Self(ContextError::new(), ErrorKind::Fail)
+		Self(ContextError::new(), ErrorKind::Fail, None)
 	}
 
 	pub fn context(&self) -> impl Iterator<Item = &StrContext> {
@@ -31,6 +103,57 @@ impl MBusError {
 	pub fn kind(&self) -> ErrorKind {
 		self.1
 	}
+
+	/// Counts `trailing` more bytes as being left after the error, see
+	/// [`parse_within`]
+	fn with_trailing(self, trailing: usize) -> Self {
+		Self(self.0, self.1, self.2.map(|remaining| remaining + trailing))
+	}
+
+	/// Where in `input` the error happened, if known. `input` must be the
+	/// entire input that was originally passed to the parser.
+	pub fn offset(&self, input: &[u8]) -> Option<usize> {
+		input.len().checked_sub(self.2?)
+	}
+
+	/// Formats `input` as a hex dump with a caret under the byte where the
+	/// error happened. `input` must be the entire input that was originally
+	/// passed to the parser.
+	pub fn hex_dump(&self, input: &[u8]) -> String {
+		let offset = self.offset(input);
+		let mut lines = Vec::new();
+		let mut caret_line = None;
+		for (n, chunk) in input.chunks(HEX_DUMP_WIDTH).enumerate() {
+			let start = n * HEX_DUMP_WIDTH;
+			let hex: Vec<_> = chunk.iter().map(|byte| format!("{byte:02X}")).collect();
+			lines.push(format!("{start:04X}: {}", hex.join(" ")));
+			if let Some(offset) = offset {
+				if (start..start + HEX_DUMP_WIDTH).contains(&offset) {
+					caret_line = Some((lines.len(), offset - start));
+				}
+			}
+		}
+		match (offset, caret_line) {
+			// The error was at the very end of the input and it finished on a
+			// line boundary, so the caret needs a line of its own
+			(Some(offset), None) => {
+				lines.push(format!("{offset:04X}:"));
+				caret_line = Some((lines.len(), 0));
+			}
+			(None, _) => return lines.join("\n"),
+			_ => (),
+		}
+		if let Some((line, column)) = caret_line {
+			lines.insert(line, format!("      {}^^", "   ".repeat(column)));
+		}
+		lines.join("\n")
+	}
+
+	/// If parsing failed because of a missing feature rather than a broken
+	/// packet, which feature it was
+	pub fn unsupported_feature(&self) -> Option<&UnsupportedFeature> {
+		self.cause()?.downcast_ref()
+	}
 }
 
 impl Default for MBusError {
@@ -39,13 +162,17 @@ impl Default for MBusError {
 	}
 }
 
-impl<I: Stream> ParserError<I> for MBusError {
+impl<I: Stream + RemainingBytes> ParserError<I> for MBusError {
 	fn append(self, input: &I, token_start: &<I as Stream>::Checkpoint, kind: ErrorKind) -> Self {
-		Self(self.0.append(input, token_start, kind), kind)
+		Self(self.0.append(input, token_start, kind), kind, self.2)
 	}
 
 	fn from_error_kind(input: &I, kind: ErrorKind) -> Self {
-		Self(ContextError::from_error_kind(input, kind), kind)
+		Self(
+			ContextError::from_error_kind(input, kind),
+			kind,
+			Some(input.remaining_bytes()),
+		)
 	}
 }
 
@@ -62,13 +189,23 @@ impl<I: Stream> AddContext<I, StrContext> for MBusError {
 		token_start: &<I as Stream>::Checkpoint,
 		context: StrContext,
 	) -> Self {
-		Self(self.0.add_context(input, token_start, context), self.1)
+		Self(
+			self.0.add_context(input, token_start, context),
+			self.1,
+			self.2,
+		)
 	}
 }
 
-impl<I, E: std::error::Error + Send + Sync + 'static> FromExternalError<I, E> for MBusError {
+impl<I: RemainingBytes, E: std::error::Error + Send + Sync + 'static> FromExternalError<I, E>
+	for MBusError
+{
 	fn from_external_error(input: &I, kind: ErrorKind, e: E) -> Self {
-		Self(ContextError::from_external_error(input, kind, e), kind)
+		Self(
+			ContextError::from_external_error(input, kind, e),
+			kind,
+			Some(input.remaining_bytes()),
+		)
 	}
 }
 
@@ -79,7 +216,7 @@ impl ErrorConvert<MBusError> for MBusError {
 }
 
 // impl<I: Stream> ErrorConvert<InputError<I>> for MBusError {
-impl<I: Stream + Clone> ErrorConvert<MBusError> for InputError<I> {
+impl<I: Stream + Clone + RemainingBytes> ErrorConvert<MBusError> for InputError<I> {
 	fn convert(self) -> MBusError {
 		MBusError::from_error_kind(&self.input, self.kind)
 	}
@@ -87,6 +224,97 @@ impl<I: Stream + Clone> ErrorConvert<MBusError> for InputError<I> {
 
 impl ErrorConvert<MBusError> for ContextError<StrContext> {
 	fn convert(self) -> MBusError {
-		MBusError(self, ErrorKind::Fail)
+		MBusError(self, ErrorKind::Fail, None)
+	}
+}
+
+#[cfg(test)]
+mod test_hex_dump {
+	use winnow::prelude::*;
+	use winnow::Bytes;
+
+	use crate::parse::application_layer::frame::Frame;
+	use crate::parse::link_layer::{user_data_frame, Packet};
+	use crate::utils::read_test_file;
+
+	#[test]
+	fn test_truncated_packet() {
+		let data = read_test_file("./libmbus_test_data/test-frames/oms_frame1.hex").unwrap();
+		let data = &data[..21];
+
+		let error = Packet::parse.parse(Bytes::new(data)).unwrap_err();
+
+		// The packet says it's 0x20 bytes long so the data can't be read at all
+		assert_eq!(error.inner().offset(data), Some(6));
+		assert_eq!(
+			error.inner().hex_dump(data),
+			"0000: 68 20 20 68 08 FD 72 78 56 34 12 93 15 33 03 2A\n".to_owned()
+				+ "                        ^^\n"
+				+ "0010: 00 00 00 0C 14"
+		);
+	}
+
+	#[test]
+	fn test_truncated_record() {
+		let data = [
+			0x04, 0x13, 0x01, 0x00, 0x00, 0x00, // Good record
+			0x04, 0x13, 0x02, 0x00, // Truncated record
+		];
+
+		let error = Frame::parse.parse(Bytes::new(&data)).unwrap_err();
+
+		assert_eq!(error.inner().offset(&data), Some(8));
+		assert_eq!(
+			error.inner().hex_dump(&data),
+			"0000: 04 13 01 00 00 00 04 13 02 00\n".to_owned() + "                              ^^"
+		);
+	}
+
+	#[test]
+	fn test_bad_record_in_packet() {
+		let data = user_data_frame(
+			0x01,
+			&[
+				0x78, // No header
+				0x04, 0x13, 0x01, 0x00, 0x00, 0x00, // Good record
+				0x0D, 0x13, 0xFB, // Reserved LVAR value
+			],
+		);
+
+		let error = Packet::parse.parse(Bytes::new(&data)).unwrap_err();
+
+		assert_eq!(error.inner().offset(&data), Some(15));
+	}
+
+	#[test]
+	fn test_bad_record_in_container() {
+		let data = user_data_frame(
+			0x01,
+			&[
+				0x78, // No header
+				0x0D, 0xFD, 0x3B, 0x03, // Wireless container with 3 bytes of records
+				0x0D, 0x13, 0xFB, // Reserved LVAR value
+				0x01, 0xFD, 0x17, 0x00, // Error flags
+			],
+		);
+
+		let error = Packet::parse.parse(Bytes::new(&data)).unwrap_err();
+
+		// The record inside the container can't be parsed, so the container
+		// stops short at the start of it
+		assert_eq!(error.inner().offset(&data), Some(11));
+	}
+
+	#[test]
+	fn test_end_of_input() {
+		let data = [0x10, 0x5B, 0x01];
+
+		let error = Packet::parse.parse(Bytes::new(&data)).unwrap_err();
+
+		assert_eq!(error.inner().offset(&data), Some(3));
+		assert_eq!(
+			error.inner().hex_dump(&data),
+			"0000: 10 5B 01\n".to_owned() + "               ^^"
+		);
 	}
 }
@@ -3,21 +3,161 @@
 
 use winnow::binary;
 use winnow::binary::bits;
-use winnow::combinator::{alt, cut_err, preceded};
+use winnow::combinator::{alt, cut_err, eof, preceded, repeat, terminated};
 use winnow::error::{AddContext, ErrMode, ErrorKind, ParserError, StrContext};
 use winnow::prelude::*;
 use winnow::stream::Stream;
 use winnow::Bytes;
 
-use super::error::{MBResult, MBusError};
+use super::application_layer::frame::DeviceResponse;
+use super::application_layer::record::Record;
+use super::error::{parse_within, MBResult, MBusError};
+use super::options::ParseOptions;
+use super::spans::Spans;
+use super::transport_layer::control_info::parse_header;
+use super::transport_layer::header::{LongHeader, TPLHeader};
 use super::transport_layer::MBusMessage;
+use super::warnings::ParseWarning;
 
-const LONG_FRAME_HEADER: u8 = 0x68;
-const SHORT_FRAME_HEADER: u8 = 0x10;
-const FRAME_TAIL: u8 = 0x16;
-const ACK_FRAME: u8 = 0xE5;
+pub(crate) const LONG_FRAME_HEADER: u8 = 0x68;
+pub(crate) const SHORT_FRAME_HEADER: u8 = 0x10;
+pub(crate) const FRAME_TAIL: u8 = 0x16;
+pub(crate) const ACK_FRAME: u8 = 0xE5;
 
-#[derive(Debug)]
+pub(crate) fn calculate_checksum(data: &[u8]) -> u8 {
+	data.iter().copied().fold(0, u8::wrapping_add)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumError {
+	/// The first byte isn't the start of any known frame type
+	UnknownFrame,
+	/// There aren't enough bytes for the frame's declared length
+	Truncated,
+	/// The two length bytes of a long frame don't agree
+	LengthMismatch,
+	/// The frame doesn't have a stop byte where it should
+	MissingTail,
+	/// The frame's checksum byte doesn't match the calculated checksum
+	Mismatch { expected: u8, actual: u8 },
+}
+
+impl std::fmt::Display for ChecksumError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::UnknownFrame => write!(f, "unknown frame type"),
+			Self::Truncated => write!(f, "frame is truncated"),
+			Self::LengthMismatch => write!(f, "frame length fields do not match"),
+			Self::MissingTail => write!(f, "frame has no stop byte"),
+			Self::Mismatch { expected, actual } => write!(
+				f,
+				"checksum mismatch: expected {expected:#04X} but frame has {actual:#04X}"
+			),
+		}
+	}
+}
+
+impl std::error::Error for ChecksumError {}
+
+/// Works out how many more bytes need to be read before `buffer` holds a
+/// complete frame, so a serial read loop can size its next read. Until a long
+/// frame's length byte has arrived this is just enough to read it.
+///
+/// Returns `Some(0)` once the frame is complete, and `None` if the buffer
+/// doesn't start like a frame and needs resynchronising.
+pub fn bytes_needed(buffer: &[u8]) -> Option<usize> {
+	let total = match buffer {
+		[] => 1,
+		[ACK_FRAME, ..] => 1,
+		[SHORT_FRAME_HEADER, ..] => 5,
+		[LONG_FRAME_HEADER] => 2,
+		[LONG_FRAME_HEADER, length, rest @ ..] => {
+			match rest {
+				[] => {}
+				[length_confirmation] | [length_confirmation, LONG_FRAME_HEADER, ..]
+					if length_confirmation == length => {}
+				_ => return None,
+			}
+			// Both starts, both lengths, the checksum and the tail
+			usize::from(*length) + 6
+		}
+		_ => return None,
+	};
+	Some(total.saturating_sub(buffer.len()))
+}
+
+/// Checks a complete frame's checksum without parsing any of its contents.
+/// Single character acknowledgements have no checksum and always pass.
+pub fn verify_checksum(frame: &[u8]) -> Result<(), ChecksumError> {
+	let (body, rest) = match frame {
+		[] => return Err(ChecksumError::Truncated),
+		[ACK_FRAME, ..] => return Ok(()),
+		[SHORT_FRAME_HEADER, rest @ ..] => {
+			if rest.len() < 4 {
+				return Err(ChecksumError::Truncated);
+			}
+			rest.split_at(2)
+		}
+		[LONG_FRAME_HEADER, rest @ ..] => {
+			let [length, length_confirmation, LONG_FRAME_HEADER, rest @ ..] = rest else {
+				return Err(if rest.len() < 3 {
+					ChecksumError::Truncated
+				} else {
+					ChecksumError::LengthMismatch
+				});
+			};
+			if length != length_confirmation {
+				return Err(ChecksumError::LengthMismatch);
+			}
+			let length = usize::from(*length);
+			if rest.len() < length + 2 {
+				return Err(ChecksumError::Truncated);
+			}
+			rest.split_at(length)
+		}
+		_ => return Err(ChecksumError::UnknownFrame),
+	};
+	let [actual, FRAME_TAIL, ..] = *rest else {
+		return Err(ChecksumError::MissingTail);
+	};
+	let expected = calculate_checksum(body);
+	if expected != actual {
+		return Err(ChecksumError::Mismatch { expected, actual });
+	}
+	Ok(())
+}
+
+/// Reads the long transport layer header out of a frame without parsing any
+/// of the data after it, so a device can be identified even if its records
+/// use something this library doesn't support. The frame's checksum is
+/// still verified.
+///
+/// Returns `None` if the frame is valid but doesn't have a long header, such
+/// as an ACK or a response with a short header.
+pub fn peek_identity(data: &[u8]) -> MBResult<Option<LongHeader>> {
+	let input = &mut Bytes::new(data);
+	if data.first() != Some(&LONG_FRAME_HEADER) {
+		Packet::parse.parse_next(input)?;
+		return Ok(None);
+	}
+	let (_, _, data) = preceded(LONG_FRAME_HEADER.void(), parse_long_frame)
+		.context(StrContext::Label("long frame header"))
+		.parse_next(input)?;
+
+	let header = |data: &mut &Bytes| {
+		let ci = binary::u8
+			.context(StrContext::Label("CI field"))
+			.parse_next(data)?;
+		parse_header(ParseOptions::default(), ci, data)
+	};
+	match parse_within(header, data, input.len() + 2)? {
+		Some(TPLHeader::Long(header)) => Ok(Some(header)),
+		_ => Ok(None),
+	}
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum PrimaryControlMessage {
 	ResetRemoteLink,
 	ResetUserProcess,
@@ -29,7 +169,41 @@ pub enum PrimaryControlMessage {
 	RequestUserData2, // REQ UD2
 }
 
-#[derive(Debug)]
+impl PrimaryControlMessage {
+	/// Whether this message has the FCV bit set, meaning the frame count bit
+	/// is used to detect lost messages
+	pub fn frame_count_valid(&self) -> bool {
+		matches!(
+			self,
+			Self::SendUserDataConfirmed | Self::RequestUserData1 | Self::RequestUserData2
+		)
+	}
+
+	/// Whether the secondary station has to confirm this message with an ACK
+	/// (or a NACK) rather than responding with data
+	pub fn expects_confirmation(&self) -> bool {
+		matches!(
+			self,
+			Self::ResetRemoteLink | Self::ResetUserProcess | Self::SendUserDataConfirmed
+		)
+	}
+
+	fn function(&self) -> u8 {
+		match self {
+			Self::ResetRemoteLink => 0,
+			Self::ResetUserProcess => 1,
+			Self::SendUserDataConfirmed => 3,
+			Self::SendUserDataUnconfirmed => 4,
+			Self::RequestAccessDemand => 8,
+			Self::RequestLinkStatus => 9,
+			Self::RequestUserData1 => 10,
+			Self::RequestUserData2 => 11,
+		}
+	}
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum SecondaryControlMessage {
 	ACK,
 	NACK,
@@ -40,13 +214,15 @@ pub enum SecondaryControlMessage {
 	LinkNotImplemented,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum DataFlowControl {
 	Continue, // "further messages are acceptable"
 	Pause,    // "further messages may cause data overflow"
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Control {
 	Primary {
 		frame_count_bit: bool,
@@ -60,6 +236,17 @@ pub enum Control {
 }
 
 impl Control {
+	/// Builds the control field for a message from the primary station.
+	///
+	/// The frame count bit only means anything for messages that have the FCV
+	/// bit set, so it's cleared for any other message.
+	pub fn primary(message: PrimaryControlMessage, frame_count_bit: bool) -> Self {
+		Self::Primary {
+			frame_count_bit: frame_count_bit && message.frame_count_valid(),
+			message,
+		}
+	}
+
 	fn parse(input: &mut &Bytes) -> MBResult<Self> {
 		bits::bits((
 			bits::bool
@@ -103,7 +290,7 @@ impl Control {
 						9 => SecondaryControlMessage::UserDataUnavailable,
 						11 => SecondaryControlMessage::Status,
 						14 => SecondaryControlMessage::LinkNotFunctioning,
-						15 => SecondaryControlMessage::LinkNotFunctioning,
+						15 => SecondaryControlMessage::LinkNotImplemented,
 						_ => return None,
 					},
 				}
@@ -111,9 +298,141 @@ impl Control {
 		})
 		.parse_next(input)
 	}
+
+	/// Encodes this control field back into the byte that'd be sent on the wire
+	pub fn to_byte(&self) -> u8 {
+		let (prm, fcb_acd, fcv_dfc, function) = match self {
+			Self::Primary {
+				frame_count_bit,
+				message,
+			} => (
+				true,
+				*frame_count_bit,
+				message.frame_count_valid(),
+				message.function(),
+			),
+			Self::Secondary {
+				access_demand,
+				data_flow_control,
+				message,
+			} => (
+				false,
+				*access_demand,
+				matches!(data_flow_control, DataFlowControl::Pause),
+				match message {
+					SecondaryControlMessage::ACK => 0,
+					SecondaryControlMessage::NACK => 1,
+					SecondaryControlMessage::UserData => 8,
+					SecondaryControlMessage::UserDataUnavailable => 9,
+					SecondaryControlMessage::Status => 11,
+					SecondaryControlMessage::LinkNotFunctioning => 14,
+					SecondaryControlMessage::LinkNotImplemented => 15,
+				},
+			),
+		};
+		(u8::from(prm) << 6) | (u8::from(fcb_acd) << 5) | (u8::from(fcv_dfc) << 4) | function
+	}
+
+	/// Works out whether `response` confirms this message having been sent
+	pub fn confirmation(&self, response: &Packet) -> Confirmation {
+		let Self::Primary { message, .. } = self else {
+			return Confirmation::Unexpected;
+		};
+		if !message.expects_confirmation() {
+			return Confirmation::Unexpected;
+		}
+		match response {
+			Packet::Ack => Confirmation::Ack,
+			Packet::Short {
+				control: Self::Secondary { message, .. },
+				..
+			} => match message {
+				SecondaryControlMessage::ACK => Confirmation::Ack,
+				SecondaryControlMessage::NACK => Confirmation::Nack,
+				_ => Confirmation::Unexpected,
+			},
+			_ => Confirmation::Unexpected,
+		}
+	}
 }
 
-#[derive(Debug)]
+/// How a secondary station answered a message that needs confirming
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Confirmation {
+	/// Either the single 0xE5 byte or a short frame with an ACK control field
+	Ack,
+	Nack,
+	/// The message didn't need confirming, or the response wasn't an ACK or
+	/// a NACK
+	Unexpected,
+}
+
+/// Tracks the frame count bit for one secondary station. A master should keep
+/// one of these per address and build its requests from it.
+///
+/// EN 13757-2: The bit toggles for each successful exchange of a message that
+/// has the FCV bit set, and a message that wasn't answered is retried with the
+/// same bit so the secondary can tell it's a repeat. After a SND_NKE the
+/// secondary expects the next bit to be set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FcbState {
+	frame_count_bit: bool,
+}
+
+impl Default for FcbState {
+	fn default() -> Self {
+		Self {
+			frame_count_bit: true,
+		}
+	}
+}
+
+impl FcbState {
+	/// The state of a station that has just been reset with SND_NKE
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// The frame count bit the next message should be sent with
+	pub fn frame_count_bit(&self) -> bool {
+		self.frame_count_bit
+	}
+
+	/// Builds the control field for sending `message`, without changing the
+	/// state. Use the same control again if the message needs retrying.
+	pub fn control(&self, message: PrimaryControlMessage) -> Control {
+		Control::primary(message, self.frame_count_bit)
+	}
+
+	/// The control field for the next REQ_UD2
+	pub fn request_user_data_2(&self) -> Control {
+		self.control(PrimaryControlMessage::RequestUserData2)
+	}
+
+	/// Records that the secondary station answered `sent`. This should only
+	/// be called once a valid response has arrived, never for a timeout or a
+	/// corrupt reply.
+	pub fn confirm(&mut self, sent: &Control) {
+		match sent {
+			Control::Primary {
+				message: PrimaryControlMessage::ResetRemoteLink,
+				..
+			} => *self = Self::new(),
+			Control::Primary { message, .. } if message.frame_count_valid() => {
+				self.frame_count_bit = !self.frame_count_bit;
+			}
+			_ => (),
+		}
+	}
+}
+
+/// A parsed packet owns all of its data, so it's `Send + Sync + 'static` and
+/// can outlive the buffer it was parsed from
+// Long frames are what everyone actually wants to parse, so there's no point
+// boxing the message just to make the other variants smaller
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Packet {
 	Ack,
 	Short {
@@ -127,7 +446,22 @@ pub enum Packet {
 	},
 }
 
-fn parse_variable(input: &mut &Bytes) -> MBResult<Packet> {
+fn parse_variable(options: ParseOptions, input: &mut &Bytes) -> MBResult<Packet> {
+	let (control, address, data) = parse_long_frame(input)?;
+
+	// The data was followed by the checksum and frame tail
+	let message = parse_within(MBusMessage::parse_with(options), data, input.len() + 2)?;
+
+	Ok(Packet::Long {
+		control,
+		address,
+		message,
+	})
+}
+
+/// Checks the framing of a long frame and pulls out the link layer fields,
+/// leaving the user data for the caller to parse
+fn parse_long_frame<'a>(input: &mut &'a Bytes) -> MBResult<(Control, u8, &'a [u8])> {
 	let length = binary::u8
 		.context(StrContext::Label("length"))
 		.parse_next(input)?;
@@ -166,11 +500,7 @@ fn parse_variable(input: &mut &Bytes) -> MBResult<Packet> {
 	)
 		.parse_next(input)?;
 
-	let sum = data
-		.iter()
-		.copied()
-		.reduce(u8::wrapping_add)
-		.unwrap_or_default()
+	let sum = calculate_checksum(data)
 		.wrapping_add(raw_control)
 		.wrapping_add(address);
 
@@ -184,15 +514,7 @@ fn parse_variable(input: &mut &Bytes) -> MBResult<Packet> {
 		);
 	}
 
-	let mut data = Bytes::new(data);
-
-	let message = MBusMessage::parse.parse_next(&mut data)?;
-
-	Ok(Packet::Long {
-		control,
-		address,
-		message,
-	})
+	Ok((control, address, data))
 }
 
 fn parse_fixed(input: &mut &Bytes) -> MBResult<Packet> {
@@ -208,7 +530,7 @@ fn parse_fixed(input: &mut &Bytes) -> MBResult<Packet> {
 	)
 		.parse_next(input)?;
 
-	let sum = raw_control.wrapping_add(address);
+	let sum = calculate_checksum(&[raw_control, address]);
 	if sum != checksum {
 		return Err(
 			ErrMode::from_error_kind(input, ErrorKind::Verify).add_context(
@@ -227,11 +549,72 @@ fn parse_ack(_input: &mut &Bytes) -> MBResult<Packet> {
 }
 
 impl Packet {
+	/// Whether this is a response from a device that has more data waiting for
+	/// it, meaning it expects another REQ_UD2 with the frame count bit toggled
+	pub fn expects_follow_up(&self) -> bool {
+		matches!(
+			self,
+			Self::Long {
+				message: MBusMessage::ResponseFromDevice(_, DeviceResponse::Full(frame)),
+				..
+			} if frame.more_data_follows
+		)
+	}
+
+	/// The access number of the transport layer header, which can be used to
+	/// tell if a device has repeated a telegram rather than sending the next
+	/// one in a sequence
+	pub fn access_number(&self) -> Option<u8> {
+		match self {
+			Self::Long {
+				message: MBusMessage::ResponseFromDevice(header, _),
+				..
+			} => header.access_number(),
+			_ => None,
+		}
+	}
+
+	pub fn is_ack(&self) -> bool {
+		matches!(self, Self::Ack)
+	}
+
+	/// Whether the device is reporting an application error. See
+	/// [`MBusMessage::is_error`].
+	pub fn is_error(&self) -> bool {
+		self.message().is_some_and(MBusMessage::is_error)
+	}
+
+	/// Whether the device is reporting an alarm. See
+	/// [`MBusMessage::is_alarm`].
+	pub fn is_alarm(&self) -> bool {
+		self.message().is_some_and(MBusMessage::is_alarm)
+	}
+
+	/// Whether the device is responding with its data. See
+	/// [`MBusMessage::is_data_response`].
+	pub fn is_data_response(&self) -> bool {
+		self.message().is_some_and(MBusMessage::is_data_response)
+	}
+
+	fn message(&self) -> Option<&MBusMessage> {
+		match self {
+			Self::Long { message, .. } => Some(message),
+			_ => None,
+		}
+	}
+
 	pub fn parse(input: &mut &Bytes) -> MBResult<Packet> {
+		Self::parse_with(ParseOptions::default()).parse_next(input)
+	}
+
+	pub fn parse_with<'a>(options: ParseOptions) -> impl Parser<&'a Bytes, Packet, MBusError> {
 		alt((
 			preceded(
 				LONG_FRAME_HEADER.void(),
-				cut_err(parse_variable.context(StrContext::Label("long frame header"))),
+				cut_err(
+					(move |input: &mut &'a Bytes| parse_variable(options, input))
+						.context(StrContext::Label("long frame header")),
+				),
 			),
 			preceded(
 				SHORT_FRAME_HEADER.void(),
@@ -239,6 +622,733 @@ impl Packet {
 			),
 			preceded(ACK_FRAME.void(), cut_err(parse_ack)),
 		))
-		.parse_next(input)
+	}
+
+	/// Parses a packet and also works out where each part of it was in the
+	/// input, such as for highlighting the raw bytes of a record. This is
+	/// slower than [`Packet::parse_with`] as the records have to be looked at
+	/// twice, so it should only be used when the spans are wanted. Fails if
+	/// a record that was parsed can't be found again.
+	pub fn parse_with_spans<'a>(
+		options: ParseOptions,
+	) -> impl Parser<&'a Bytes, (Packet, Spans), MBusError> {
+		Self::parse_with(options)
+			.with_recognized()
+			.verify_map(move |(packet, raw): (Packet, &[u8])| {
+				let spans = Spans::for_packet(options, raw, &packet)?;
+				Some((packet, spans))
+			})
+			.context(StrContext::Label("spans"))
+	}
+
+	/// Parses a packet and also lists anything that was only accepted because
+	/// the options are lenient, such as a date with the month 15. Turning on
+	/// the matching strict option would reject the packet instead.
+	pub fn parse_with_warnings<'a>(
+		options: ParseOptions,
+	) -> impl Parser<&'a Bytes, (Packet, Vec<ParseWarning>), MBusError> {
+		Self::parse_with_spans(options).map(|(packet, spans)| {
+			let warnings = ParseWarning::for_packet(&packet, &spans);
+			(packet, warnings)
+		})
+	}
+
+	/// Parses every packet in a buffer that has several back to back, such as
+	/// an ACK immediately followed by a response. The whole buffer must be
+	/// made up of complete packets.
+	pub fn parse_many(input: &mut &Bytes) -> MBResult<Vec<Packet>> {
+		Self::parse_many_with(ParseOptions::default()).parse_next(input)
+	}
+
+	pub fn parse_many_with<'a>(
+		options: ParseOptions,
+	) -> impl Parser<&'a Bytes, Vec<Packet>, MBusError> {
+		terminated(repeat(0.., Self::parse_with(options)), eof)
+			.context(StrContext::Label("packets"))
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MultiTelegramError {
+	/// The packet wasn't a full data response from a device
+	NotAResponse,
+	/// The packet came from a different device to the first telegram
+	WrongAddress { expected: u8, actual: u8 },
+	/// The last telegram has already been received
+	Complete,
+}
+
+impl std::fmt::Display for MultiTelegramError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::NotAResponse => write!(f, "packet is not a data response"),
+			Self::WrongAddress { expected, actual } => write!(
+				f,
+				"expected a telegram from {expected:#04X} but got one from {actual:#04X}"
+			),
+			Self::Complete => write!(f, "all telegrams have already been received"),
+		}
+	}
+}
+
+impl std::error::Error for MultiTelegramError {}
+
+/// Collects the records from a device that needs more than one telegram to
+/// send all of its data. Each response with more data following should be
+/// answered with another REQ_UD2 with the frame count bit toggled, and its
+/// packet passed to [`MultiTelegram::push`] until that returns `true`.
+#[derive(Debug, Clone, Default)]
+pub struct MultiTelegram {
+	address: Option<u8>,
+	records: Vec<Record>,
+	complete: bool,
+}
+
+impl MultiTelegram {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Adds the records from the next telegram, returning whether it was the
+	/// last one. The packet is rejected without changing anything if it
+	/// didn't come from the same address as the first telegram.
+	pub fn push(&mut self, packet: Packet) -> Result<bool, MultiTelegramError> {
+		if self.complete {
+			return Err(MultiTelegramError::Complete);
+		}
+		let Packet::Long {
+			address,
+			message: MBusMessage::ResponseFromDevice(_, DeviceResponse::Full(frame)),
+			..
+		} = packet
+		else {
+			return Err(MultiTelegramError::NotAResponse);
+		};
+		match self.address {
+			Some(expected) if expected != address => {
+				return Err(MultiTelegramError::WrongAddress {
+					expected,
+					actual: address,
+				})
+			}
+			_ => self.address = Some(address),
+		}
+		self.records.extend(frame.records);
+		self.complete = !frame.more_data_follows;
+		Ok(self.complete)
+	}
+
+	/// Whether the device has sent its last telegram
+	pub fn is_complete(&self) -> bool {
+		self.complete
+	}
+
+	/// The address of the device sending the telegrams, once the first one has
+	/// arrived
+	pub fn address(&self) -> Option<u8> {
+		self.address
+	}
+
+	/// Every record received so far, in the order they were sent
+	pub fn records(&self) -> &[Record] {
+		&self.records
+	}
+
+	pub fn into_records(self) -> Vec<Record> {
+		self.records
+	}
+}
+
+/// Builds a RSP_UD long frame from the secondary station at `address`, where
+/// `data` is everything from the CI field onwards
+#[cfg(test)]
+pub(crate) fn user_data_frame(address: u8, data: &[u8]) -> Vec<u8> {
+	let control = Control::Secondary {
+		access_demand: false,
+		data_flow_control: DataFlowControl::Continue,
+		message: SecondaryControlMessage::UserData,
+	};
+	crate::encode::encode_long_frame(&control, address, data)
+}
+
+#[cfg(test)]
+mod test_verify_checksum {
+	use rstest::rstest;
+
+	use super::{verify_checksum, ChecksumError};
+	use crate::utils::read_test_file;
+
+	#[rstest]
+	#[case::ack(&[0xE5])]
+	#[case::short(&[0x10, 0x5B, 0x01, 0x5C, 0x16])]
+	#[case::long(&[0x68, 0x03, 0x03, 0x68, 0x53, 0x01, 0xBD, 0x11, 0x16])]
+	fn test_good(#[case] frame: &[u8]) {
+		assert_eq!(verify_checksum(frame), Ok(()));
+	}
+
+	#[test]
+	fn test_good_file() {
+		let data = read_test_file("./libmbus_test_data/test-frames/wmbus-converted.hex").unwrap();
+
+		assert_eq!(verify_checksum(&data), Ok(()));
+	}
+
+	#[rstest]
+	#[case::empty(&[], ChecksumError::Truncated)]
+	#[case::unknown(&[0x42], ChecksumError::UnknownFrame)]
+	#[case::short_truncated(&[0x10, 0x5B, 0x01], ChecksumError::Truncated)]
+	#[case::short_bad_sum(&[0x10, 0x5B, 0x01, 0x5D, 0x16], ChecksumError::Mismatch { expected: 0x5C, actual: 0x5D })]
+	#[case::short_bad_tail(&[0x10, 0x5B, 0x01, 0x5C, 0x17], ChecksumError::MissingTail)]
+	#[case::long_bad_sum(
+		&[0x68, 0x03, 0x03, 0x68, 0x53, 0x01, 0xBD, 0x12, 0x16],
+		ChecksumError::Mismatch { expected: 0x11, actual: 0x12 },
+	)]
+	#[case::long_bad_length(&[0x68, 0x03, 0x04, 0x68, 0x53, 0x01, 0xBD, 0x11, 0x16], ChecksumError::LengthMismatch)]
+	#[case::long_truncated(&[0x68, 0x03, 0x03, 0x68, 0x53, 0x01, 0xBD, 0x11], ChecksumError::Truncated)]
+	fn test_bad(#[case] frame: &[u8], #[case] expected: ChecksumError) {
+		assert_eq!(verify_checksum(frame), Err(expected));
+	}
+}
+
+#[cfg(test)]
+mod test_packet_bounds {
+	use super::Packet;
+	use crate::parse::error::MBusError;
+
+	fn assert_owned<T: Send + Sync + 'static>() {}
+
+	#[test]
+	fn test_packet_is_owned() {
+		assert_owned::<Packet>();
+	}
+
+	#[test]
+	fn test_error_is_owned() {
+		assert_owned::<MBusError>();
+	}
+
+	#[test]
+	fn test_packet_is_clone() {
+		fn assert_clone<T: Clone>() {}
+
+		assert_clone::<Packet>();
+	}
+}
+
+/// Parses a complete packet, which must take up the whole of the slice
+///
+/// ```
+/// use libmbus::parse::link_layer::Packet;
+///
+/// let packet: Packet = [0x10, 0x5B, 0x01, 0x5C, 0x16].as_slice().try_into().unwrap();
+/// assert!(matches!(packet, Packet::Short { address: 1, .. }));
+/// ```
+impl TryFrom<&[u8]> for Packet {
+	type Error = MBusError;
+
+	fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+		Packet::parse
+			.parse(Bytes::new(data))
+			.map_err(|e| e.into_inner())
+	}
+}
+
+#[cfg(test)]
+mod test_bytes_needed {
+	use rstest::rstest;
+
+	use super::bytes_needed;
+	use crate::utils::read_test_file;
+
+	#[rstest]
+	#[case::empty(&[], Some(1))]
+	#[case::ack(&[0xE5], Some(0))]
+	#[case::partial_short(&[0x10, 0x5B], Some(3))]
+	#[case::short(&[0x10, 0x5B, 0x01, 0x5C, 0x16], Some(0))]
+	#[case::long_start(&[0x68], Some(1))]
+	#[case::long_length(&[0x68, 0x03], Some(7))]
+	#[case::long_header(&[0x68, 0x03, 0x03, 0x68], Some(5))]
+	#[case::length_mismatch(&[0x68, 0x03, 0x04], None)]
+	#[case::bad_second_start(&[0x68, 0x03, 0x03, 0x10], None)]
+	#[case::garbage(&[0x00], None)]
+	fn test_bytes_needed(#[case] buffer: &[u8], #[case] expected: Option<usize>) {
+		assert_eq!(bytes_needed(buffer), expected);
+	}
+
+	#[test]
+	fn test_partial_long_frame() {
+		let data =
+			read_test_file("./libmbus_test_data/test-frames/kamstrup_multical_601.hex").unwrap();
+
+		for read in 2..data.len() {
+			assert_eq!(bytes_needed(&data[..read]), Some(data.len() - read));
+		}
+		assert_eq!(bytes_needed(&data), Some(0));
+	}
+}
+
+#[cfg(test)]
+mod test_parse_many {
+	use winnow::prelude::*;
+	use winnow::Bytes;
+
+	use super::Packet;
+	use crate::utils::read_test_file;
+
+	#[test]
+	fn test_ack_then_long_frame() {
+		let mut data = vec![0xE5];
+		data.extend(
+			read_test_file("./libmbus_test_data/test-frames/kamstrup_multical_601.hex").unwrap(),
+		);
+
+		let packets = Packet::parse_many.parse(Bytes::new(&data)).unwrap();
+
+		assert_eq!(packets.len(), 2);
+		assert!(packets[0].is_ack());
+		assert!(packets[1].is_data_response());
+	}
+
+	#[test]
+	fn test_acks() {
+		let packets = Packet::parse_many
+			.parse(Bytes::new(&[
+				0xE5, 0xE5, 0x10, 0x5B, 0x01, 0x5C, 0x16, 0xE5,
+			]))
+			.unwrap();
+
+		assert!(matches!(
+			packets.as_slice(),
+			[
+				Packet::Ack,
+				Packet::Ack,
+				Packet::Short { address: 0x01, .. },
+				Packet::Ack
+			]
+		));
+	}
+
+	#[test]
+	fn test_trailing_garbage() {
+		Packet::parse_many
+			.parse(Bytes::new(&[0xE5, 0x00]))
+			.unwrap_err();
+	}
+}
+
+#[cfg(test)]
+mod test_try_from {
+	use super::Packet;
+	use crate::utils::read_test_file;
+
+	#[test]
+	fn test_try_from() {
+		let data =
+			read_test_file("./libmbus_test_data/test-frames/kamstrup_multical_601.hex").unwrap();
+
+		let packet = Packet::try_from(data.as_slice()).unwrap();
+
+		assert!(matches!(packet, Packet::Long { address: 0x11, .. }));
+	}
+
+	#[test]
+	fn test_try_from_truncated() {
+		let data =
+			read_test_file("./libmbus_test_data/test-frames/kamstrup_multical_601.hex").unwrap();
+
+		let truncated = &data[..data.len() - 1];
+
+		let err = Packet::try_from(truncated).unwrap_err();
+
+		// The user data after the C and A fields is one byte too short
+		assert_eq!(err.offset(truncated), Some(6));
+	}
+}
+
+#[cfg(test)]
+mod test_control {
+	use rstest::rstest;
+	use winnow::prelude::*;
+	use winnow::Bytes;
+
+	use super::{Control, PrimaryControlMessage};
+
+	#[rstest]
+	#[case::req_ud2(PrimaryControlMessage::RequestUserData2, true, 0x7B)]
+	#[case::req_ud2_no_fcb(PrimaryControlMessage::RequestUserData2, false, 0x5B)]
+	#[case::snd_ud(PrimaryControlMessage::SendUserDataConfirmed, true, 0x73)]
+	#[case::snd_nke(PrimaryControlMessage::ResetRemoteLink, false, 0x40)]
+	#[case::snd_nke_ignores_fcb(PrimaryControlMessage::ResetRemoteLink, true, 0x40)]
+	fn test_primary(
+		#[case] message: PrimaryControlMessage,
+		#[case] frame_count_bit: bool,
+		#[case] expected: u8,
+	) {
+		let control = Control::primary(message, frame_count_bit);
+
+		assert_eq!(control.to_byte(), expected);
+		let reparsed = Control::parse.parse(Bytes::new(&[expected])).unwrap();
+		assert_eq!(reparsed.to_byte(), expected);
+	}
+}
+
+#[cfg(test)]
+mod test_fcb_state {
+	use super::{Control, FcbState, PrimaryControlMessage};
+
+	#[test]
+	fn test_toggle_sequence() {
+		let mut state = FcbState::new();
+
+		let first = state.request_user_data_2();
+		assert_eq!(first.to_byte(), 0x7B);
+		state.confirm(&first);
+
+		let second = state.request_user_data_2();
+		assert_eq!(second.to_byte(), 0x5B);
+		state.confirm(&second);
+
+		assert_eq!(state.request_user_data_2().to_byte(), 0x7B);
+	}
+
+	#[test]
+	fn test_retry_keeps_bit() {
+		let mut state = FcbState::new();
+		state.confirm(&state.request_user_data_2());
+
+		// The first attempt timed out, so nothing is confirmed
+		let attempt = state.request_user_data_2();
+		let retry = state.request_user_data_2();
+		assert_eq!(attempt.to_byte(), 0x5B);
+		assert_eq!(retry.to_byte(), 0x5B);
+
+		state.confirm(&retry);
+		assert_eq!(state.request_user_data_2().to_byte(), 0x7B);
+	}
+
+	#[test]
+	fn test_messages_without_fcv() {
+		let mut state = FcbState::new();
+
+		let status = state.control(PrimaryControlMessage::RequestLinkStatus);
+		assert_eq!(status.to_byte(), 0x49);
+		state.confirm(&status);
+
+		assert!(state.frame_count_bit());
+	}
+
+	#[test]
+	fn test_reset() {
+		let mut state = FcbState::new();
+		state.confirm(&state.request_user_data_2());
+		assert!(!state.frame_count_bit());
+
+		state.confirm(&Control::primary(
+			PrimaryControlMessage::ResetRemoteLink,
+			false,
+		));
+
+		assert_eq!(state, FcbState::new());
+		assert_eq!(state.request_user_data_2().to_byte(), 0x7B);
+	}
+}
+
+#[cfg(test)]
+mod test_confirmation {
+	use rstest::rstest;
+	use winnow::prelude::*;
+	use winnow::Bytes;
+
+	use super::{Confirmation, Control, Packet, PrimaryControlMessage};
+
+	#[rstest]
+	#[case::single_ack(PrimaryControlMessage::SendUserDataConfirmed, &[0xE5], Confirmation::Ack)]
+	#[case::short_ack(PrimaryControlMessage::SendUserDataConfirmed, &[0x10, 0x00, 0x01, 0x01, 0x16], Confirmation::Ack)]
+	#[case::short_nack(PrimaryControlMessage::SendUserDataConfirmed, &[0x10, 0x01, 0x01, 0x02, 0x16], Confirmation::Nack)]
+	#[case::reset(PrimaryControlMessage::ResetRemoteLink, &[0xE5], Confirmation::Ack)]
+	#[case::status(PrimaryControlMessage::SendUserDataConfirmed, &[0x10, 0x0B, 0x01, 0x0C, 0x16], Confirmation::Unexpected)]
+	#[case::response(PrimaryControlMessage::SendUserDataConfirmed, &[0x68, 0x03, 0x03, 0x68, 0x08, 0x01, 0x78, 0x81, 0x16], Confirmation::Unexpected)]
+	#[case::not_confirmed(PrimaryControlMessage::RequestUserData2, &[0xE5], Confirmation::Unexpected)]
+	fn test_confirmation(
+		#[case] message: PrimaryControlMessage,
+		#[case] response: &[u8],
+		#[case] expected: Confirmation,
+	) {
+		let control = Control::primary(message, true);
+		let response = Packet::parse.parse(Bytes::new(response)).unwrap();
+
+		assert_eq!(control.confirmation(&response), expected);
+	}
+}
+
+#[cfg(test)]
+mod test_follow_up {
+	use winnow::prelude::*;
+	use winnow::Bytes;
+
+	use super::{user_data_frame, Packet};
+
+	fn response(marker: &[u8]) -> Packet {
+		let mut data = vec![
+			0x7A, 0x05, 0x00, 0x00, 0x00, // Short header with access number 5
+			0x02, 0x13, 0x05, 0x00, // Record
+		];
+		data.extend(marker);
+		let frame = user_data_frame(0x01, &data);
+
+		Packet::parse.parse(Bytes::new(&frame)).unwrap()
+	}
+
+	#[test]
+	fn test_more_data_follows() {
+		let packet = response(&[0x1F]);
+
+		assert!(packet.expects_follow_up());
+		assert_eq!(packet.access_number(), Some(5));
+	}
+
+	#[test]
+	fn test_no_more_data() {
+		let packet = response(&[]);
+
+		assert!(!packet.expects_follow_up());
+		assert_eq!(packet.access_number(), Some(5));
+	}
+
+	#[test]
+	fn test_short_frame() {
+		let packet = Packet::parse
+			.parse(Bytes::new(&[0x10, 0x5B, 0x01, 0x5C, 0x16]))
+			.unwrap();
+
+		assert!(!packet.expects_follow_up());
+		assert_eq!(packet.access_number(), None);
+	}
+}
+
+#[cfg(test)]
+mod test_classification {
+	use winnow::prelude::*;
+	use winnow::Bytes;
+
+	use super::{user_data_frame, Packet};
+
+	fn response(data: &[u8]) -> Packet {
+		let frame = user_data_frame(0x01, data);
+
+		Packet::parse.parse(Bytes::new(&frame)).unwrap()
+	}
+
+	#[test]
+	fn test_ack() {
+		let packet = Packet::parse.parse(Bytes::new(&[0xE5])).unwrap();
+
+		assert!(packet.is_ack());
+		assert!(!packet.is_error());
+		assert!(!packet.is_alarm());
+		assert!(!packet.is_data_response());
+	}
+
+	#[test]
+	fn test_application_error() {
+		// CI 0x70 with no header and the "record overflow" error code
+		let packet = response(&[0x70, 0x03]);
+
+		assert!(!packet.is_ack());
+		assert!(packet.is_error());
+		assert!(!packet.is_alarm());
+		assert!(!packet.is_data_response());
+	}
+
+	#[test]
+	fn test_alarm() {
+		// CI 0x71 with no header and a one byte alarm
+		let packet = response(&[0x71, 0x01]);
+
+		assert!(!packet.is_error());
+		assert!(packet.is_alarm());
+		assert!(!packet.is_data_response());
+	}
+
+	#[test]
+	fn test_data_response() {
+		let packet = response(&[0x7A, 0x05, 0x00, 0x00, 0x00, 0x02, 0x13, 0x05, 0x00]);
+
+		assert!(!packet.is_ack());
+		assert!(!packet.is_error());
+		assert!(!packet.is_alarm());
+		assert!(packet.is_data_response());
+	}
+}
+
+#[cfg(test)]
+mod test_multi_telegram {
+	use winnow::prelude::*;
+	use winnow::Bytes;
+
+	use super::{user_data_frame, MultiTelegram, MultiTelegramError, Packet};
+	use crate::parse::application_layer::data::DataType;
+
+	fn response(address: u8, value: u8, more_data_follows: bool) -> Packet {
+		let mut data = vec![
+			0x7A, 0x05, 0x00, 0x00, 0x00, // Short header with access number 5
+			0x02, 0x13, value, 0x00, // Record
+		];
+		if more_data_follows {
+			data.push(0x1F);
+		}
+		let frame = user_data_frame(address, &data);
+
+		Packet::parse.parse(Bytes::new(&frame)).unwrap()
+	}
+
+	#[test]
+	fn test_two_telegrams() {
+		let mut telegrams = MultiTelegram::new();
+
+		assert_eq!(telegrams.push(response(0x01, 1, true)), Ok(false));
+		assert!(!telegrams.is_complete());
+		assert_eq!(telegrams.push(response(0x01, 2, false)), Ok(true));
+		assert!(telegrams.is_complete());
+		assert_eq!(telegrams.address(), Some(0x01));
+
+		let values: Vec<_> = telegrams
+			.into_records()
+			.into_iter()
+			.map(|record| record.data)
+			.collect();
+		assert_eq!(values, [DataType::Signed(1), DataType::Signed(2)]);
+	}
+
+	#[test]
+	fn test_wrong_address() {
+		let mut telegrams = MultiTelegram::new();
+		telegrams.push(response(0x01, 1, true)).unwrap();
+
+		assert_eq!(
+			telegrams.push(response(0x02, 2, false)),
+			Err(MultiTelegramError::WrongAddress {
+				expected: 0x01,
+				actual: 0x02
+			})
+		);
+		assert!(!telegrams.is_complete());
+		assert_eq!(telegrams.records().len(), 1);
+	}
+
+	#[test]
+	fn test_already_complete() {
+		let mut telegrams = MultiTelegram::new();
+		telegrams.push(response(0x01, 1, false)).unwrap();
+
+		assert_eq!(
+			telegrams.push(response(0x01, 2, false)),
+			Err(MultiTelegramError::Complete)
+		);
+	}
+
+	#[test]
+	fn test_not_a_response() {
+		let mut telegrams = MultiTelegram::new();
+
+		assert_eq!(
+			telegrams.push(Packet::Ack),
+			Err(MultiTelegramError::NotAResponse)
+		);
+		assert_eq!(telegrams.address(), None);
+	}
+}
+
+#[cfg(test)]
+mod test_parse_options {
+	use winnow::prelude::*;
+	use winnow::Bytes;
+
+	use super::{user_data_frame, Packet};
+	use crate::parse::options::ParseOptions;
+
+	fn month_15_frame() -> Vec<u8> {
+		user_data_frame(
+			0x01,
+			&[
+				0x7A, 0x05, 0x00, 0x00, 0x00, // Short header
+				0x02, 0x6C, 0x01, 0x0F, // Type G date with a month of 15
+			],
+		)
+	}
+
+	#[test]
+	fn test_default_options() {
+		let frame = month_15_frame();
+
+		Packet::parse.parse(Bytes::new(&frame)).unwrap();
+		Packet::parse_with(ParseOptions::default())
+			.parse(Bytes::new(&frame))
+			.unwrap();
+	}
+
+	#[test]
+	fn test_strict_dates() {
+		let frame = month_15_frame();
+		let options = ParseOptions {
+			strict_dates: true,
+			..Default::default()
+		};
+
+		Packet::parse_with(options)
+			.parse(Bytes::new(&frame))
+			.unwrap_err();
+	}
+}
+
+#[cfg(test)]
+mod test_peek_identity {
+	use winnow::prelude::*;
+	use winnow::Bytes;
+
+	use super::{peek_identity, user_data_frame, Packet};
+	use crate::parse::transport_layer::header::{DeviceType, ThermalMeterType};
+
+	const KAMSTRUP_HEADER: [u8; 12] = [
+		0x17, 0x58, 0x85, 0x06, 0x2D, 0x2C, 0x08, 0x04, 0x04, 0x00, 0x00, 0x00,
+	];
+
+	#[test]
+	fn test_unsupported_records() {
+		// Format frames aren't supported, but the long header is fine
+		let frame = user_data_frame(
+			0x01,
+			&[&[0x6B], &KAMSTRUP_HEADER[..], &[0x04, 0x13]].concat(),
+		);
+		Packet::parse.parse(Bytes::new(&frame)).unwrap_err();
+
+		let header = peek_identity(&frame).unwrap().unwrap();
+
+		assert_eq!(header.identifier, 6_855_817);
+		assert_eq!(header.manufacturer.to_string(), "KAM");
+		assert_eq!(header.version, 0x08);
+		assert_eq!(
+			header.device_type,
+			DeviceType::ThermalEnergyMeter(ThermalMeterType::OutletHeat)
+		);
+	}
+
+	#[test]
+	fn test_no_long_header() {
+		assert!(peek_identity(&[0xE5]).unwrap().is_none());
+		assert!(peek_identity(&[0x10, 0x5B, 0x01, 0x5C, 0x16])
+			.unwrap()
+			.is_none());
+		let short_header = user_data_frame(0x01, &[0x7A, 0x04, 0x00, 0x00, 0x00]);
+		assert!(peek_identity(&short_header).unwrap().is_none());
+	}
+
+	#[test]
+	fn test_bad_checksum() {
+		let mut frame = user_data_frame(0x01, &[&[0x72], &KAMSTRUP_HEADER[..]].concat());
+		let checksum = frame.len() - 2;
+		frame[checksum] ^= 0xFF;
+
+		peek_identity(&frame).unwrap_err();
 	}
 }
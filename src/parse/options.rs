@@ -0,0 +1,51 @@
+// Copyright 2024 Lexi Robinson
+// Licensed under the EUPL-1.2
+
+use crate::parse::application_layer::vib::MAX_VIFES;
+use crate::parse::transport_layer::manufacturer::ManufacturerCode;
+
+/// Settings that change how strictly (or leniently) packets are parsed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseOptions {
+	/// Reject dates that only make sense if you're being generous, such as the
+	/// month 15 that some meters use
+	pub strict_dates: bool,
+	/// Reject records where the data field is wider than makes sense for the
+	/// value type, see [`crate::parse::application_layer::vib::ValueType::max_width`]
+	pub strict_widths: bool,
+	/// Reject manufacturer codes that aren't three uppercase letters, see
+	/// [`crate::parse::transport_layer::manufacturer::ManufacturerCode::is_standard`].
+	/// Turn this off to accept meters that send codes such as `@@@` or `KA[`
+	/// anyway.
+	pub strict_manufacturers: bool,
+	/// The most VIFEs that can follow a VIF before the record is treated as
+	/// corrupt
+	pub max_vifes: usize,
+	/// Keep messages with a reserved CI field as
+	/// [`crate::parse::transport_layer::MBusMessage::Unknown`] instead of
+	/// rejecting them, in case newer meters have started using it
+	pub preserve_unknown_ci: bool,
+	/// Read 32 bit real data fields as 64 bit doubles instead, for meters that
+	/// send 8 bytes of data after the real DIF. There's nothing in the frame
+	/// to say which a meter does, so this has to be turned on for them.
+	pub double_reals: bool,
+	/// The manufacturer of the device the records came from, so that any
+	/// registered manufacturer specific VIFE handlers can be used. Parsing a
+	/// whole message fills this in from its header, so it only needs setting
+	/// when parsing frames or records on their own.
+	pub manufacturer: Option<ManufacturerCode>,
+}
+
+impl Default for ParseOptions {
+	fn default() -> Self {
+		Self {
+			strict_dates: false,
+			strict_widths: false,
+			strict_manufacturers: true,
+			max_vifes: MAX_VIFES,
+			preserve_unknown_ci: false,
+			double_reals: false,
+			manufacturer: None,
+		}
+	}
+}
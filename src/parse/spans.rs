@@ -0,0 +1,259 @@
+// Copyright 2024 Lexi Robinson
+// Licensed under the EUPL-1.2
+use std::ops::Range;
+
+use winnow::binary::bits;
+use winnow::prelude::*;
+use winnow::Bytes;
+
+use super::application_layer::dib::DataInfoBlock;
+use super::application_layer::frame::{DeviceResponse, IDLE_FILLER};
+use super::application_layer::record::Record;
+use super::application_layer::vib::ValueInfoBlock;
+use super::error::MBusError;
+use super::link_layer::Packet;
+use super::options::ParseOptions;
+use super::transport_layer::header::TPLHeader;
+use super::transport_layer::MBusMessage;
+
+/// Where each part of a packet was in the bytes it was parsed from, for tools
+/// that want to show the raw data alongside what it means. All ranges are
+/// byte offsets from the start of the packet. See [`Packet::parse_with_spans`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Spans {
+	/// The start byte of a short frame, the `68 L L 68` of a long frame or the
+	/// whole of an ACK
+	pub start: Range<usize>,
+	pub control: Option<Range<usize>>,
+	pub address: Option<Range<usize>>,
+	pub ci: Option<Range<usize>>,
+	/// The transport layer header, if the CI field has one
+	pub header: Option<Range<usize>>,
+	/// Every record in a full frame, in the order they were sent
+	pub records: Vec<RecordSpans>,
+	/// The manufacturer specific data after the records, not including the
+	/// marker byte
+	pub manufacturer_specific: Option<Range<usize>>,
+	pub checksum: Option<Range<usize>>,
+	pub stop: Option<Range<usize>>,
+}
+
+/// Where each part of a record was in the packet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordSpans {
+	/// The DIF and any DIFEs
+	pub dib: Range<usize>,
+	/// The VIF and any VIFEs, including a plain text unit
+	pub vib: Range<usize>,
+	pub data: Range<usize>,
+}
+
+impl RecordSpans {
+	/// The entire record, not including any idle fillers before it
+	pub fn record(&self) -> Range<usize> {
+		self.dib.start..self.data.end
+	}
+
+	/// Re-parses the start of a record that's already been parsed to see
+	/// where the DIB and VIB end
+	fn find(options: ParseOptions, raw: &[u8], offset: usize, record: &Record) -> Option<Self> {
+		let mut input = Bytes::new(raw.get(..record.length)?);
+		let dib = bits::bits::<_, _, MBusError, _, _>(DataInfoBlock::parse)
+			.recognize()
+			.parse_next(&mut input)
+			.ok()?
+			.len();
+		let vib = bits::bits::<_, _, MBusError, _, _>(ValueInfoBlock::parse_with(options))
+			.recognize()
+			.parse_next(&mut input)
+			.ok()?
+			.len();
+		Some(Self {
+			dib: offset..offset + dib,
+			vib: offset + dib..offset + dib + vib,
+			data: offset + dib + vib..offset + record.length,
+		})
+	}
+}
+
+impl Spans {
+	/// Works out the spans of a packet that has already been parsed from
+	/// `raw`, which must be exactly the bytes the packet was parsed from.
+	/// Returns `None` if any of the records can't be found again.
+	pub(crate) fn for_packet(options: ParseOptions, raw: &[u8], packet: &Packet) -> Option<Self> {
+		let message = match packet {
+			Packet::Ack => {
+				return Some(Self {
+					start: 0..1,
+					..Self::default()
+				})
+			}
+			Packet::Short { .. } => {
+				return Some(Self {
+					start: 0..1,
+					control: Some(1..2),
+					address: Some(2..3),
+					checksum: Some(3..4),
+					stop: Some(4..5),
+					..Self::default()
+				})
+			}
+			Packet::Long { message, .. } => message,
+		};
+
+		let end = raw.len() - 2;
+		let header = match message.header() {
+			Some(TPLHeader::Short(_)) => Some(7..11),
+			Some(TPLHeader::Long(_)) => Some(7..19),
+			_ => None,
+		};
+		let mut spans = Self {
+			start: 0..4,
+			control: Some(4..5),
+			address: Some(5..6),
+			ci: Some(6..7),
+			header,
+			checksum: Some(end..end + 1),
+			stop: Some(end + 1..end + 2),
+			..Self::default()
+		};
+
+		if let MBusMessage::ResponseFromDevice(header, DeviceResponse::Full(frame)) = message {
+			let mut offset = spans.header.as_ref().map_or(7, |header| header.end);
+			let options = ParseOptions {
+				manufacturer: header.manufacturer().or(options.manufacturer),
+				..options
+			};
+			for record in &frame.records {
+				while raw.get(offset) == Some(&IDLE_FILLER) {
+					offset += 1;
+				}
+				let record_spans = RecordSpans::find(options, &raw[offset..], offset, record)?;
+				offset = record_spans.data.end;
+				spans.records.push(record_spans);
+			}
+			if !frame.manufacturer_specific.is_empty() {
+				spans.manufacturer_specific = Some(end - frame.manufacturer_specific.len()..end);
+			}
+		}
+
+		Some(spans)
+	}
+}
+
+#[cfg(test)]
+mod test_spans {
+	use winnow::prelude::*;
+	use winnow::Bytes;
+
+	use super::{RecordSpans, Spans};
+	use crate::parse::link_layer::{user_data_frame, Packet};
+	use crate::parse::options::ParseOptions;
+	use crate::utils::read_test_file;
+
+	fn parse(data: &[u8]) -> Spans {
+		let (_, spans) = Packet::parse_with_spans(ParseOptions::default())
+			.parse(Bytes::new(data))
+			.unwrap();
+		spans
+	}
+
+	#[test]
+	fn test_long_frame() {
+		let data = read_test_file("./libmbus_test_data/test-frames/kamstrup_382_005.hex")
+			.expect("test file must be valid");
+
+		let spans = parse(&data);
+
+		assert_eq!(spans.start, 0..4);
+		assert_eq!(spans.control, Some(4..5));
+		assert_eq!(spans.address, Some(5..6));
+		assert_eq!(spans.ci, Some(6..7));
+		assert_eq!(spans.header, Some(7..19));
+		assert_eq!(
+			spans.records[0],
+			RecordSpans {
+				dib: 19..20,
+				vib: 20..21,
+				data: 21..25,
+			}
+		);
+		assert_eq!(spans.records.len(), 6);
+		// 0x84 0x60 0x06 + 4 bytes of data
+		assert_eq!(spans.records[5].record(), 50..57);
+		// Everything after the 0x0F marker at 57
+		assert_eq!(spans.manufacturer_specific, Some(58..data.len() - 2));
+		assert_eq!(spans.checksum, Some(data.len() - 2..data.len() - 1));
+		assert_eq!(spans.stop, Some(data.len() - 1..data.len()));
+	}
+
+	#[test]
+	fn test_idle_filler() {
+		let data = read_test_file("./libmbus_test_data/test-frames/filler.hex")
+			.expect("test file must be valid");
+
+		let spans = parse(&data);
+
+		assert!(!spans.records.is_empty());
+		for record in &spans.records {
+			assert_ne!(data[record.dib.start], 0x2F, "{record:?}");
+		}
+		for pair in spans.records.windows(2) {
+			assert!(pair[0].data.end <= pair[1].dib.start);
+		}
+	}
+
+	#[test]
+	fn test_no_header() {
+		// A full frame with no transport layer header and one 1 byte record
+		let data = user_data_frame(0x01, &[0x78, 0x01, 0x13, 0x05]);
+
+		let spans = parse(&data);
+
+		assert_eq!(spans.ci, Some(6..7));
+		assert_eq!(spans.header, None);
+		assert_eq!(
+			spans.records,
+			[RecordSpans {
+				dib: 7..8,
+				vib: 8..9,
+				data: 9..10,
+			}]
+		);
+	}
+
+	#[test]
+	fn test_extended_link_layer() {
+		// Wired packets don't decode the ELL, so there's no header to report
+		let data = user_data_frame(0x01, &[0x8C, 0x20, 0x01]);
+
+		let spans = parse(&data);
+
+		assert_eq!(spans.ci, Some(6..7));
+		assert_eq!(spans.header, None);
+		assert_eq!(spans.records, []);
+	}
+
+	#[test]
+	fn test_short_frame() {
+		let spans = parse(&[0x10, 0x5B, 0x01, 0x5C, 0x16]);
+
+		assert_eq!(spans.start, 0..1);
+		assert_eq!(spans.control, Some(1..2));
+		assert_eq!(spans.address, Some(2..3));
+		assert_eq!(spans.ci, None);
+		assert_eq!(spans.checksum, Some(3..4));
+		assert_eq!(spans.stop, Some(4..5));
+	}
+
+	#[test]
+	fn test_ack() {
+		assert_eq!(
+			parse(&[0xE5]),
+			Spans {
+				start: 0..1,
+				..Spans::default()
+			}
+		);
+	}
+}
@@ -3,5 +3,6 @@
 pub mod control_info;
 pub mod header;
 pub mod manufacturer;
+pub mod selection;
 
 pub use control_info::MBusMessage;
@@ -1,21 +1,31 @@
 // Copyright 2024 Lexi Robinson
 // Licensed under the EUPL-1.2
+use std::collections::BTreeMap;
+use std::sync::{PoisonError, RwLock};
+
 use winnow::binary;
-use winnow::combinator::repeat;
+use winnow::combinator::{alt, eof, peek, repeat, terminated};
 use winnow::error::{AddContext, ErrMode, ErrorKind, ParserError, StrContext};
 use winnow::prelude::*;
 use winnow::stream::Stream;
 use winnow::Bytes;
 
 use crate::parse::application_layer::application::{ApplicationErrorMessage, ApplicationMessage};
-use crate::parse::application_layer::frame::Frame;
-use crate::parse::error::MBResult;
+use crate::parse::application_layer::frame::{CompactFrame, DeviceResponse, Frame};
+use crate::parse::application_layer::record::{parse_readout_selection, ReadoutSelector, Record};
+use crate::parse::error::{unsupported, MBResult, MBusError};
+use crate::parse::options::ParseOptions;
+use crate::parse::types::date::{TypeFDateTime, TypeIDateTime};
 
+use super::header::ExtendedLinkHeader;
 use super::header::LongHeader;
 use super::header::ShortHeader;
 use super::header::TPLHeader;
+use super::manufacturer::ManufacturerCode;
+use super::selection::SecondaryAddress;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum BaudRate {
 	Rate300,
 	Rate600,
@@ -27,7 +37,24 @@ pub enum BaudRate {
 	Rate38400,
 }
 
-#[derive(Debug)]
+impl BaudRate {
+	/// The CI field used to ask a device to switch to this baud rate
+	pub fn ci_field(&self) -> u8 {
+		match self {
+			Self::Rate300 => 0xB8,
+			Self::Rate600 => 0xB9,
+			Self::Rate1200 => 0xBA,
+			Self::Rate2400 => 0xBB,
+			Self::Rate4800 => 0xBC,
+			Self::Rate9600 => 0xBD,
+			Self::Rate19200 => 0xBE,
+			Self::Rate38400 => 0xBF,
+		}
+	}
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum MBusMessage {
 	// Application stuff
 	ApplicationReset(TPLHeader), // EN 13757–3:2018, Clause 7
@@ -35,16 +62,17 @@ pub enum MBusMessage {
 	SelectedApplicationRequest(TPLHeader), // EN 13757–3:2018, Clause 7
 	SelectedApplicationResponse(TPLHeader, ApplicationMessage), // EN 13757–3:2018, Clause 7
 	// Management Commands
-	SelectionOfDevice(Vec<u8>),                 // EN 13757-7:2018, Clause 8.4
-	SetBaudRate(BaudRate),                      // EN 13757-7:2018, Clause 8
-	SynchroniseAction,                          // EN 13757–3:2018, Clause 12
-	TimeAdjustmentToDevice(TPLHeader, Vec<u8>), // EN 13757–3:2018, Clause 8
-	TimeSyncToDevice(TPLHeader, Vec<u8>),       // EN 13757–3:2018, Clause 8
+	/// Followed by any extra records used for enhanced selection
+	SelectionOfDevice(SecondaryAddress, Vec<u8>), // EN 13757-7:2018, Clause 8.4
+	SetBaudRate(BaudRate), // EN 13757-7:2018, Clause 8
+	SynchroniseAction,     // EN 13757–3:2018, Clause 12
+	TimeAdjustmentToDevice(TPLHeader, TimeSyncPayload), // EN 13757–3:2018, Clause 8
+	TimeSyncToDevice(TPLHeader, TimeSyncPayload), // EN 13757–3:2018, Clause 8
 	// Data operations
 	AlarmFromDevice(TPLHeader, Vec<u8>), // EN 13757–3:2018, Clause 9
 	ApplicationErrorFromDevice(TPLHeader, ApplicationErrorMessage), // EN 13757–3:2018, Clause 10
 	CommandToDevice(TPLHeader, Vec<u8>), // EN 13757–3:2018, Clause 6
-	ResponseFromDevice(TPLHeader, Frame), // EN 13757–3:2018, Clause 6, Annex G
+	ResponseFromDevice(TPLHeader, DeviceResponse), // EN 13757–3:2018, Clause 6, Annex G
 	// Unsupported
 	AuthenticationAndFrgamentation(Vec<u8>), // EN 13757-7:2018, Clause 6
 	Dlms(u8, TPLHeader, Vec<u8>),            // TODO: Unsupported "see EN 13757–1"
@@ -53,68 +81,268 @@ pub enum MBusMessage {
 	SecurityTransfer(u8, TPLHeader, Vec<u8>), // TODO: Unsupported - EN 13757–3:2018, Annex A
 	SpecificUsage(u8, TPLHeader, Vec<u8>),   // "Used for specific national implementations"
 	Wireless(u8, TPLHeader),                 // TODO: Unsupported - EN 13757–4, EN 13757–5
+	ExtendedLinkLayer(ExtendedLinkHeader, ExtendedLinkPayload), // EN 13757-4:2019, Clause 13.2
+	/// A reserved CI field and everything after it, only returned when
+	/// [`ParseOptions::preserve_unknown_ci`] is set
+	Unknown(u8, Vec<u8>),
+}
+
+/// Turns the payload of a manufacturer specific message (CI fields 0xA0 to
+/// 0xB7) into records, for manufacturers who have documented their format.
+/// It's given the CI field and everything after it. Returning `None` leaves
+/// the payload as raw bytes.
+pub type ManufacturerPayloadHandler = fn(u8, &[u8]) -> Option<Vec<Record>>;
+
+static MANUFACTURER_PAYLOAD_HANDLERS: RwLock<
+	BTreeMap<ManufacturerCode, ManufacturerPayloadHandler>,
+> = RwLock::new(BTreeMap::new());
+
+/// Registers a handler that [`MBusMessage::manufacturer_records`] will use
+/// for manufacturer specific messages from the manufacturer `code`.
+///
+/// Registering a second handler for the same manufacturer replaces the first.
+pub fn register_manufacturer_payload(code: ManufacturerCode, handler: ManufacturerPayloadHandler) {
+	MANUFACTURER_PAYLOAD_HANDLERS
+		.write()
+		.unwrap_or_else(PoisonError::into_inner)
+		.insert(code, handler);
+}
+
+fn manufacturer_payload_handler(code: ManufacturerCode) -> Option<ManufacturerPayloadHandler> {
+	MANUFACTURER_PAYLOAD_HANDLERS
+		.read()
+		.unwrap_or_else(PoisonError::into_inner)
+		.get(&code)
+		.copied()
+}
+
+/// Parses the transport layer header that follows the CI field `ci`, which
+/// is `None` if the CI field is reserved
+pub(crate) fn parse_header(
+	options: ParseOptions,
+	ci: u8,
+	input: &mut &Bytes,
+) -> MBResult<Option<TPLHeader>> {
+	Ok(Some(match ci {
+		0x00..=0x1F
+		| 0x50..=0x52
+		| 0x54
+		| 0x5C
+		| 0x66
+		| 0x69
+		| 0x70..=0x71
+		| 0x78..=0x79
+		| 0x81
+		| 0x83
+		| 0x86
+		| 0x89
+		| 0x8C..=0x90
+		| 0xA0..=0xBF => TPLHeader::None,
+		0x5A | 0x61 | 0x65 | 0x67 | 0x6A | 0x6E | 0x74 | 0x7A | 0x7B | 0x7D | 0x8A | 0x88
+		| 0x9E | 0xC1 | 0xC4 => ShortHeader::parse
+			.context(StrContext::Label("short header"))
+			.parse_next(input)?,
+		0x53
+		| 0x55
+		| 0x5B
+		| 0x5F
+		| 0x60
+		| 0x64
+		| 0x68
+		| 0x6B..=0x6D
+		| 0x6F
+		| 0x72
+		| 0x73
+		| 0x75
+		| 0x7C
+		| 0x80
+		| 0x82
+		| 0x84
+		| 0x85
+		| 0x87
+		| 0x8B
+		| 0x9F
+		| 0xC0
+		| 0xC2
+		| 0xC3
+		| 0xC5 => LongHeader::parse_with(options)
+			.context(StrContext::Label("long header"))
+			.parse_next(input)?,
+		_ => return Ok(None),
+	}))
+}
+
+/// The time sent to a device to set or adjust its clock
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum TimeSyncPayload {
+	TypeF(TypeFDateTime),
+	TypeI(TypeIDateTime),
+	/// The payload wasn't a date/time this library understands
+	Unknown(Vec<u8>),
+}
+
+impl TimeSyncPayload {
+	fn parse(input: &mut &Bytes) -> MBResult<Self> {
+		alt((
+			terminated(TypeFDateTime::parse, eof).map(Self::TypeF),
+			terminated(TypeIDateTime::parse, eof).map(Self::TypeI),
+			repeat(0.., binary::u8).map(Self::Unknown),
+		))
+		.context(StrContext::Label("time sync payload"))
+		.parse_next(input)
+	}
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum ExtendedLinkPayload {
+	Message(Box<MBusMessage>),
+	Encrypted(Vec<u8>),
 }
 
 impl MBusMessage {
+	/// Whether the device is reporting an application error
+	pub fn is_error(&self) -> bool {
+		matches!(self.inner(), Self::ApplicationErrorFromDevice(..))
+	}
+
+	/// Whether the device is reporting an alarm
+	pub fn is_alarm(&self) -> bool {
+		matches!(self.inner(), Self::AlarmFromDevice(..))
+	}
+
+	/// Whether the device is responding with its data, either as a full or a
+	/// compact frame
+	pub fn is_data_response(&self) -> bool {
+		matches!(self.inner(), Self::ResponseFromDevice(..))
+	}
+
+	/// The transport layer header, if the CI field is one that has a header
+	pub fn header(&self) -> Option<&TPLHeader> {
+		match self {
+			Self::ApplicationReset(header)
+			| Self::ApplicationSelect(header, _)
+			| Self::SelectedApplicationRequest(header)
+			| Self::SelectedApplicationResponse(header, _)
+			| Self::TimeAdjustmentToDevice(header, _)
+			| Self::TimeSyncToDevice(header, _)
+			| Self::AlarmFromDevice(header, _)
+			| Self::ApplicationErrorFromDevice(header, _)
+			| Self::CommandToDevice(header, _)
+			| Self::ResponseFromDevice(header, _)
+			| Self::Dlms(_, header, _)
+			| Self::ImageTransfer(_, header, _)
+			| Self::SecurityTransfer(_, header, _)
+			| Self::SpecificUsage(_, header, _)
+			| Self::Wireless(_, header) => Some(header),
+			Self::SelectionOfDevice(..)
+			| Self::SetBaudRate(_)
+			| Self::SynchroniseAction
+			| Self::AuthenticationAndFrgamentation(_)
+			| Self::ManufacturerSpecific(..)
+			| Self::ExtendedLinkLayer(..)
+			| Self::Unknown(..) => None,
+		}
+	}
+
+	/// Decodes a manufacturer specific message with the handler registered
+	/// for `manufacturer`, see [`register_manufacturer_payload`]. These
+	/// messages don't have a transport layer header, so the manufacturer has
+	/// to come from somewhere else, such as the long header of an earlier
+	/// response from the same device.
+	pub fn manufacturer_records(&self, manufacturer: ManufacturerCode) -> Option<Vec<Record>> {
+		let Self::ManufacturerSpecific(ci, data) = self.inner() else {
+			return None;
+		};
+		manufacturer_payload_handler(manufacturer)?(*ci, data)
+	}
+
+	/// The message inside any extended link layer, as long as it's not
+	/// encrypted
+	fn inner(&self) -> &Self {
+		match self {
+			Self::ExtendedLinkLayer(_, ExtendedLinkPayload::Message(message)) => message.inner(),
+			_ => self,
+		}
+	}
+
+	/// The values a master has asked for, if this is a SND_UD requesting a
+	/// partial readout. See [`parse_readout_selection`].
+	pub fn readout_selection(&self) -> Option<Vec<ReadoutSelector>> {
+		let Self::CommandToDevice(_, data) = self else {
+			return None;
+		};
+		parse_readout_selection
+			.parse(Bytes::new(data))
+			.ok()
+			.filter(|selectors| !selectors.is_empty())
+	}
+
+	/// Parses a message received over wireless M-Bus, where the transport
+	/// layer can be preceded by an extended link layer header
+	pub fn parse_wireless(input: &mut &Bytes) -> MBResult<MBusMessage> {
+		Self::parse_wireless_with(ParseOptions::default()).parse_next(input)
+	}
+
+	pub fn parse_wireless_with<'a>(
+		options: ParseOptions,
+	) -> impl Parser<&'a Bytes, Self, MBusError> {
+		move |input: &mut &'a Bytes| Self::parse_wireless_message(options, input)
+	}
+
+	fn parse_wireless_message(options: ParseOptions, input: &mut &Bytes) -> MBResult<Self> {
+		let ci = peek(binary::u8)
+			.context(StrContext::Label("CI field"))
+			.parse_next(input)?;
+		if !matches!(ci, 0x8C..=0x8F) {
+			return Self::parse_message(options, input);
+		}
+		binary::u8.void().parse_next(input)?;
+
+		let header = ExtendedLinkHeader::parse(options, ci)
+			.context(StrContext::Label("extended link layer"))
+			.parse_next(input)?;
+		let payload = if header.is_encrypted() {
+			ExtendedLinkPayload::Encrypted(
+				repeat(0.., binary::u8)
+					.context(StrContext::Label("encrypted data"))
+					.parse_next(input)?,
+			)
+		} else {
+			ExtendedLinkPayload::Message(Box::new(Self::parse_wireless_message(options, input)?))
+		};
+		Ok(Self::ExtendedLinkLayer(header, payload))
+	}
+
 	pub fn parse(input: &mut &Bytes) -> MBResult<MBusMessage> {
+		Self::parse_with(ParseOptions::default()).parse_next(input)
+	}
+
+	pub fn parse_with<'a>(options: ParseOptions) -> impl Parser<&'a Bytes, Self, MBusError> {
+		move |input: &mut &'a Bytes| Self::parse_message(options, input)
+	}
+
+	fn parse_message(options: ParseOptions, input: &mut &Bytes) -> MBResult<MBusMessage> {
 		let ci_checkpoint = input.checkpoint();
 		let ci = binary::u8
 			.context(StrContext::Label("CI field"))
 			.parse_next(input)?;
 
-		let header = match ci {
-			0x00..=0x1F
-			| 0x54
-			| 0x5C
-			| 0x66
-			| 0x69
-			| 0x70..=0x71
-			| 0x78..=0x79
-			| 0x81
-			| 0x83
-			| 0x86
-			| 0x89
-			| 0x8C..=0x90
-			| 0xA0..=0xBF => TPLHeader::None,
-			0x5A | 0x61 | 0x65 | 0x67 | 0x6A | 0x6E | 0x74 | 0x7A | 0x7B | 0x7D | 0x8A | 0x88
-			| 0x9E | 0xC1 | 0xC4 => ShortHeader::parse
-				.context(StrContext::Label("short header"))
-				.parse_next(input)?,
-			0x53
-			| 0x55
-			| 0x5B
-			| 0x5F
-			| 0x60
-			| 0x64
-			| 0x68
-			| 0x6B..=0x6D
-			| 0x6F
-			| 0x72
-			| 0x73
-			| 0x75
-			| 0x7C
-			| 0x80
-			| 0x82
-			| 0x84
-			| 0x85
-			| 0x87
-			| 0x8B
-			| 0x9F
-			| 0xC0
-			| 0xC2
-			| 0xC3
-			| 0xC5 => LongHeader::parse
-				.context(StrContext::Label("long header"))
-				.parse_next(input)?,
-			_ => {
-				return Err(
-					ErrMode::from_error_kind(input, ErrorKind::Verify).add_context(
-						input,
-						&ci_checkpoint,
-						StrContext::Label("reserved CI field"),
-					),
-				);
+		let Some(header) = parse_header(options, ci, input)? else {
+			if options.preserve_unknown_ci {
+				return repeat(0.., binary::u8)
+					.map(|data| Self::Unknown(ci, data))
+					.context(StrContext::Label("unknown CI field data"))
+					.parse_next(input);
 			}
+			return Err(
+				ErrMode::from_error_kind(input, ErrorKind::Verify).add_context(
+					input,
+					&ci_checkpoint,
+					StrContext::Label("reserved CI field"),
+				),
+			);
 		};
 
 		let mut parse_remaining = repeat::<_, _, Vec<_>, _, _>(0.., binary::u8)
@@ -151,7 +379,10 @@ impl MBusMessage {
 					.verify_map(|x| x)
 					.parse_next(input)?,
 			),
-			0x52 => Self::SelectionOfDevice(parse_remaining.parse_next(input)?),
+			0x52 => Self::SelectionOfDevice(
+				SecondaryAddress::parse.parse_next(input)?,
+				parse_remaining.parse_next(input)?,
+			),
 			// Management Commands
 			0x5C => Self::SynchroniseAction,
 			0xB8..=0xBF => Self::SetBaudRate(match ci {
@@ -165,19 +396,530 @@ impl MBusMessage {
 				0xBF => BaudRate::Rate38400,
 				_ => unreachable!(),
 			}),
-			0x6C => Self::TimeSyncToDevice(header, parse_remaining.parse_next(input)?),
-			0x6D => Self::TimeAdjustmentToDevice(header, parse_remaining.parse_next(input)?),
+			0x6C => Self::TimeSyncToDevice(header, TimeSyncPayload::parse.parse_next(input)?),
+			0x6D => Self::TimeAdjustmentToDevice(header, TimeSyncPayload::parse.parse_next(input)?),
 			// Actual mbus
 			0x51 | 0x5A | 0x5B => Self::CommandToDevice(header, parse_remaining.parse_next(input)?),
-			0x69..=0x6B => todo!("format frame"),
+			0x69..=0x6B => return Err(unsupported(input, &ci_checkpoint, "format frames")),
 			0x6E..=0x70 => Self::ApplicationErrorFromDevice(
 				header,
 				ApplicationErrorMessage::parse.parse_next(input)?,
 			),
 			0x71 | 0x74 | 0x75 => Self::AlarmFromDevice(header, parse_remaining.parse_next(input)?),
-			0x72 | 0x78 | 0x7A => Self::ResponseFromDevice(header, Frame::parse.parse_next(input)?),
-			0x73 | 0x79 | 0x7B => todo!("compact frame"),
-			_ => unreachable!(),
+			0x72 | 0x78 | 0x7A => {
+				let frame = Frame::parse_with(ParseOptions {
+					manufacturer: header.manufacturer().or(options.manufacturer),
+					..options
+				})
+				.parse_next(input)?;
+				Self::ResponseFromDevice(header, DeviceResponse::Full(frame))
+			}
+			0x73 | 0x79 | 0x7B => Self::ResponseFromDevice(
+				header,
+				DeviceResponse::Compact(CompactFrame::parse.parse_next(input)?),
+			),
+			_ => {
+				return Err(unsupported(
+					input,
+					&ci_checkpoint,
+					"messages with this CI field",
+				))
+			}
 		})
 	}
 }
+
+#[cfg(test)]
+mod test_parse_wireless {
+	use winnow::prelude::*;
+	use winnow::Bytes;
+
+	use super::{DeviceResponse, ExtendedLinkPayload, MBusMessage};
+	use crate::parse::options::ParseOptions;
+	use crate::parse::transport_layer::header::{ExtendedLinkHeader, TPLHeader};
+	use crate::utils::read_test_file;
+
+	#[test]
+	fn test_wmbus_converted() {
+		let data = read_test_file("./libmbus_test_data/test-frames/wmbus-converted.hex").unwrap();
+		// Skip the wired link layer
+		let input = Bytes::new(&data[6..data.len() - 2]);
+
+		let result = MBusMessage::parse_wireless.parse(input).unwrap();
+
+		let MBusMessage::ResponseFromDevice(TPLHeader::Long(header), DeviceResponse::Full(frame)) =
+			result
+		else {
+			panic!("expected a long header response, got {result:?}");
+		};
+		assert_eq!(header.manufacturer.to_string(), "KAM");
+		assert_eq!(frame.records.len(), 1);
+	}
+
+	#[test]
+	fn test_ell_short_header() {
+		let input = Bytes::new(&[
+			0x8C, 0x20, 0x05, // ELL I
+			0x7A, 0x05, 0x00, 0x00, 0x00, // TPL short header
+			0x02, 0x13, 0x05, 0x00, // Record
+		]);
+
+		let result = MBusMessage::parse_wireless.parse(input).unwrap();
+
+		let MBusMessage::ExtendedLinkLayer(ell, ExtendedLinkPayload::Message(message)) = result
+		else {
+			panic!("expected an unencrypted ELL message, got {result:?}");
+		};
+		assert_eq!(ell.communication_control, 0x20);
+		assert_eq!(ell.access_number, 0x05);
+		assert!(ell.address.is_none());
+		assert!(matches!(
+			*message,
+			MBusMessage::ResponseFromDevice(TPLHeader::Short(_), _)
+		));
+	}
+
+	#[test]
+	fn test_ell_options() {
+		let input = [
+			0x8C, 0x20, 0x05, // ELL I
+			0x7A, 0x05, 0x00, 0x00, 0x00, // TPL short header
+			0x04, 0x6D, 0x00, 0x00, 0x01, 0x0F, // Type F date in month 15
+		];
+		let options = ParseOptions {
+			strict_dates: true,
+			..ParseOptions::default()
+		};
+
+		MBusMessage::parse_wireless
+			.parse(Bytes::new(&input))
+			.unwrap();
+		MBusMessage::parse_wireless_with(options)
+			.parse(Bytes::new(&input))
+			.unwrap_err();
+	}
+
+	#[test]
+	fn test_ell_address() {
+		let input = Bytes::new(&[
+			0x8E, 0x20, 0x05, // ELL III
+			0x2D, 0x2C, 0x78, 0x56, 0x34, 0x12, 0x01, 0x07, // Address
+			0x7A, 0x05, 0x00, 0x00, 0x00, // TPL short header
+		]);
+
+		let result = MBusMessage::parse_wireless.parse(input).unwrap();
+
+		let MBusMessage::ExtendedLinkLayer(ell, _) = result else {
+			panic!("expected an ELL message, got {result:?}");
+		};
+		let address = ell.address.unwrap();
+		assert_eq!(address.manufacturer.to_string(), "KAM");
+		assert_eq!(address.identifier, 12345678);
+	}
+
+	#[test]
+	fn test_ell_encrypted() {
+		let input = Bytes::new(&[
+			0x8D, 0x20, 0x05, // ELL II
+			0x01, 0x00, 0x00, 0x20, // Session number with encryption
+			0x12, 0x34, // Encrypted payload CRC
+			0xDE, 0xAD, 0xBE, 0xEF,
+		]);
+
+		let result = MBusMessage::parse_wireless.parse(input).unwrap();
+
+		let MBusMessage::ExtendedLinkLayer(ell, ExtendedLinkPayload::Encrypted(data)) = result
+		else {
+			panic!("expected an encrypted ELL message, got {result:?}");
+		};
+		assert!(ell.is_encrypted());
+		assert_eq!(ell.payload_crc, None);
+		assert_eq!(data, [0x12, 0x34, 0xDE, 0xAD, 0xBE, 0xEF]);
+	}
+
+	#[test]
+	fn test_ell_session() {
+		let input = Bytes::new(&[
+			0x8D, 0x20, 0x05, // ELL II
+			0x01, 0x00, 0x00, 0x00, // Session number without encryption
+			0x12, 0x34, // Payload CRC
+			0x7A, 0x05, 0x00, 0x00, 0x00, // TPL short header
+		]);
+
+		let result = MBusMessage::parse_wireless.parse(input).unwrap();
+
+		let MBusMessage::ExtendedLinkLayer(ell, ExtendedLinkPayload::Message(_)) = result else {
+			panic!("expected a plain ELL message, got {result:?}");
+		};
+		assert_eq!(ell.session_number, Some(1));
+		assert_eq!(ell.payload_crc, Some(0x3412));
+	}
+
+	#[test]
+	fn test_ell_wrong_ci() {
+		let input = Bytes::new(&[0x20, 0x05]);
+
+		ExtendedLinkHeader::parse(ParseOptions::default(), 0x7A)
+			.parse(input)
+			.unwrap_err();
+	}
+}
+
+#[cfg(test)]
+mod test_time_sync {
+	use winnow::prelude::*;
+	use winnow::Bytes;
+
+	use super::{MBusMessage, TimeSyncPayload};
+	use crate::parse::types::date::TypeFDateTime;
+
+	const LONG_HEADER: [u8; 12] = [
+		0x78, 0x56, 0x34, 0x12, 0x2D, 0x2C, 0x01, 0x07, 0x05, 0x00, 0x00, 0x00,
+	];
+
+	fn message(ci: u8, payload: &[u8]) -> Vec<u8> {
+		let mut data = vec![ci];
+		data.extend(LONG_HEADER);
+		data.extend(payload);
+		data
+	}
+
+	#[test]
+	fn test_type_f() {
+		let data = message(0x6C, &[0x1A, 0x2F, 0x65, 0x11]);
+
+		let result = MBusMessage::parse.parse(Bytes::new(&data)).unwrap();
+
+		let MBusMessage::TimeSyncToDevice(_, TimeSyncPayload::TypeF(date)) = result else {
+			panic!("expected a Type F time sync, got {result:?}");
+		};
+		assert_eq!(
+			date,
+			TypeFDateTime {
+				hundred_year: 1,
+				year: 11,
+				month: 1,
+				day: 5,
+				hour: 15,
+				minute: 26,
+				in_dst: false,
+			}
+		);
+	}
+
+	#[test]
+	fn test_type_i() {
+		let data = message(0x6D, &[0x00, 0x1A, 0x2F, 0x65, 0x11, 0x00]);
+
+		let result = MBusMessage::parse.parse(Bytes::new(&data)).unwrap();
+
+		assert!(
+			matches!(
+				result,
+				MBusMessage::TimeAdjustmentToDevice(_, TimeSyncPayload::TypeI(_))
+			),
+			"expected a Type I time adjustment, got {result:?}"
+		);
+	}
+
+	#[test]
+	fn test_unknown() {
+		let data = message(0x6C, &[0x01, 0x02, 0x03]);
+
+		let result = MBusMessage::parse.parse(Bytes::new(&data)).unwrap();
+
+		let MBusMessage::TimeSyncToDevice(_, TimeSyncPayload::Unknown(payload)) = result else {
+			panic!("expected an unknown time sync, got {result:?}");
+		};
+		assert_eq!(payload, [0x01, 0x02, 0x03]);
+	}
+}
+
+#[cfg(test)]
+mod test_application_reset {
+	use rstest::rstest;
+	use winnow::prelude::*;
+	use winnow::Bytes;
+
+	use super::MBusMessage;
+	use crate::parse::application_layer::application::MessageApplication;
+	use crate::parse::transport_layer::header::TPLHeader;
+
+	#[test]
+	fn test_without_subcode() {
+		let result = MBusMessage::parse.parse(Bytes::new(&[0x50])).unwrap();
+
+		assert!(
+			matches!(result, MBusMessage::ApplicationReset(TPLHeader::None)),
+			"expected an application reset, got {result:?}"
+		);
+	}
+
+	#[rstest]
+	#[case::all(0x00, MessageApplication::All)]
+	#[case::user_data(0x10, MessageApplication::UserData)]
+	#[case::simple_billing(0x20, MessageApplication::SimpleBilling)]
+	#[case::configuration_data(0xE0, MessageApplication::ConfigurationData)]
+	fn test_with_subcode(#[case] subcode: u8, #[case] expected: MessageApplication) {
+		let result = MBusMessage::parse
+			.parse(Bytes::new(&[0x50, subcode]))
+			.unwrap();
+
+		let MBusMessage::ApplicationSelect(TPLHeader::None, message) = result else {
+			panic!("expected an application select, got {result:?}");
+		};
+		assert_eq!(message.message_application, expected);
+		assert_eq!(message.block_number, 0);
+	}
+}
+
+#[cfg(test)]
+mod test_readout_selection {
+	use winnow::prelude::*;
+	use winnow::Bytes;
+
+	use super::MBusMessage;
+	use crate::encode::{ReadoutSelection, Request};
+	use crate::parse::application_layer::record::ReadoutSelector;
+	use crate::parse::application_layer::vib::ValueType;
+	use crate::parse::link_layer::Packet;
+
+	#[test]
+	fn test_two_selectors() {
+		let input = Bytes::new(&[
+			0x51, // SND_UD with no header
+			0x08, 0x13, // Current volume
+			0x48, 0x6D, // Date and time in storage 1
+		]);
+
+		let message = MBusMessage::parse.parse(input).unwrap();
+		let selection = message.readout_selection().unwrap();
+
+		assert_eq!(selection.len(), 2);
+		let ReadoutSelector::Value(dib, vib) = &selection[1] else {
+			panic!("expected a single value, got {:?}", selection[1]);
+		};
+		assert_eq!(dib.storage, 1);
+		assert_eq!(vib.value_type, ValueType::VariableDateTime);
+	}
+
+	#[test]
+	fn test_built_request() {
+		let request = Request::ReadoutSelection {
+			address: 0x01,
+			fcb: false,
+			selection: ReadoutSelection::new(vec![ReadoutSelector::All]).unwrap(),
+		};
+		let data = request.build();
+
+		let packet = Packet::parse.parse(Bytes::new(&data)).unwrap();
+		let Packet::Long { message, .. } = packet else {
+			panic!("expected a long frame, got {packet:?}");
+		};
+
+		assert_eq!(
+			message.readout_selection(),
+			Some(vec![ReadoutSelector::All])
+		);
+	}
+
+	#[test]
+	fn test_normal_command() {
+		let input = Bytes::new(&[0x51, 0x01, 0x7A, 0x05]);
+
+		let message = MBusMessage::parse.parse(input).unwrap();
+
+		assert_eq!(message.readout_selection(), None);
+	}
+}
+
+#[cfg(test)]
+mod test_device_response {
+	use winnow::prelude::*;
+	use winnow::Bytes;
+
+	use super::MBusMessage;
+	use crate::parse::application_layer::data::DataType;
+	use crate::parse::application_layer::frame::{CompactFrame, DeviceResponse};
+
+	const LONG_HEADER: [u8; 12] = [
+		0x78, 0x56, 0x34, 0x12, 0x2D, 0x2C, 0x01, 0x07, 0x2A, 0x00, 0x00, 0x00,
+	];
+
+	#[test]
+	fn test_full_frame() {
+		let mut data = vec![0x72];
+		data.extend(LONG_HEADER);
+		data.extend([0x02, 0x13, 0x05, 0x00]);
+
+		let message = MBusMessage::parse.parse(Bytes::new(&data)).unwrap();
+
+		let MBusMessage::ResponseFromDevice(_, DeviceResponse::Full(frame)) = message else {
+			panic!("expected a full frame, got {message:?}");
+		};
+		assert_eq!(frame.records[0].data, DataType::Signed(5));
+	}
+
+	#[test]
+	fn test_compact_frame() {
+		let data = [
+			0x79, // No header
+			0x34, 0x12, // Format signature
+			0xCD, 0xAB, // Full frame CRC
+			0x05, 0x00, // Data
+		];
+
+		let message = MBusMessage::parse.parse(Bytes::new(&data)).unwrap();
+
+		let MBusMessage::ResponseFromDevice(_, response) = message else {
+			panic!("expected a response, got {message:?}");
+		};
+		assert_eq!(response.frame(), None);
+		assert_eq!(
+			response,
+			DeviceResponse::Compact(CompactFrame {
+				format_signature: 0x1234,
+				full_frame_crc: 0xABCD,
+				data: vec![0x05, 0x00],
+			})
+		);
+	}
+}
+
+#[cfg(test)]
+mod test_unknown_ci {
+	use winnow::prelude::*;
+	use winnow::Bytes;
+
+	use super::MBusMessage;
+	use crate::parse::options::ParseOptions;
+
+	const INPUT: &[u8] = &[0x40, 0x01, 0x02, 0x03];
+
+	#[test]
+	fn test_reserved_ci_is_an_error() {
+		MBusMessage::parse.parse(Bytes::new(INPUT)).unwrap_err();
+	}
+
+	#[test]
+	fn test_preserve_unknown_ci() {
+		let options = ParseOptions {
+			preserve_unknown_ci: true,
+			..Default::default()
+		};
+
+		let result = MBusMessage::parse_with(options)
+			.parse(Bytes::new(INPUT))
+			.unwrap();
+
+		assert!(
+			matches!(&result, MBusMessage::Unknown(0x40, data) if data == &[0x01, 0x02, 0x03]),
+			"{result:?}"
+		);
+	}
+}
+
+#[cfg(test)]
+mod test_unsupported {
+	use rstest::rstest;
+	use winnow::prelude::*;
+	use winnow::Bytes;
+
+	use super::MBusMessage;
+	use crate::parse::error::UnsupportedFeature;
+
+	#[rstest]
+	#[case::format_frame(&[0x6A, 0x2A, 0x00, 0x00, 0x00, 0x12, 0x34], "format frames")]
+	#[case::encrypted(&[0x7A, 0x2A, 0x00, 0x10, 0x05, 0x12, 0x34], "encrypted packets")]
+	#[case::unhandled_ci(&[0x65, 0x2A, 0x00, 0x00, 0x00], "messages with this CI field")]
+	fn test_unsupported(#[case] input: &[u8], #[case] feature: &'static str) {
+		let error = MBusMessage::parse
+			.parse(Bytes::new(input))
+			.unwrap_err()
+			.into_inner();
+
+		assert_eq!(
+			error.unsupported_feature(),
+			Some(&UnsupportedFeature(feature))
+		);
+	}
+
+	#[test]
+	fn test_broken_packet_is_not_unsupported() {
+		let error = MBusMessage::parse
+			.parse(Bytes::new(&[0x7A, 0x2A, 0x00, 0x04, 0x00]))
+			.unwrap_err()
+			.into_inner();
+
+		assert_eq!(error.unsupported_feature(), None);
+	}
+}
+
+#[cfg(test)]
+mod test_manufacturer_payload {
+	use winnow::prelude::*;
+	use winnow::Bytes;
+
+	use super::{register_manufacturer_payload, MBusMessage};
+	use crate::parse::application_layer::frame::Frame;
+	use crate::parse::application_layer::record::Record;
+	use crate::parse::transport_layer::manufacturer::ManufacturerCode;
+
+	fn code(code: &str) -> ManufacturerCode {
+		code.parse().unwrap()
+	}
+
+	/// Pretends CI 0xA1 messages are just a list of records
+	fn dummy_handler(ci: u8, data: &[u8]) -> Option<Vec<Record>> {
+		match ci {
+			0xA1 => Frame::parse
+				.parse(Bytes::new(data))
+				.ok()
+				.map(|frame| frame.records),
+			_ => None,
+		}
+	}
+
+	fn parse(data: &[u8]) -> MBusMessage {
+		MBusMessage::parse.parse(Bytes::new(data)).unwrap()
+	}
+
+	#[test]
+	fn test_registered_handler() {
+		register_manufacturer_payload(code("ZZD"), dummy_handler);
+		let message = parse(&[0xA1, 0x04, 0x13, 0x2A, 0x00, 0x00, 0x00]);
+
+		let records = message.manufacturer_records(code("ZZD")).unwrap();
+
+		assert_eq!(records.len(), 1);
+		assert_eq!(records[0].scaled_value(), Some(0.042));
+		assert!(matches!(
+			message,
+			MBusMessage::ManufacturerSpecific(0xA1, ref data) if data.len() == 6
+		));
+	}
+
+	#[test]
+	fn test_handler_declines() {
+		register_manufacturer_payload(code("ZZE"), dummy_handler);
+		let message = parse(&[0xA2, 0x04, 0x13, 0x2A, 0x00, 0x00, 0x00]);
+
+		assert_eq!(message.manufacturer_records(code("ZZE")), None);
+	}
+
+	#[test]
+	fn test_other_manufacturer() {
+		register_manufacturer_payload(code("ZZF"), dummy_handler);
+		let message = parse(&[0xA1, 0x04, 0x13, 0x2A, 0x00, 0x00, 0x00]);
+
+		assert_eq!(message.manufacturer_records(code("ABB")), None);
+	}
+
+	#[test]
+	fn test_not_manufacturer_specific() {
+		register_manufacturer_payload(code("ZZG"), dummy_handler);
+		let message = parse(&[0x51, 0x04, 0x13, 0x2A, 0x00, 0x00, 0x00]);
+
+		assert_eq!(message.manufacturer_records(code("ZZG")), None);
+	}
+}
@@ -3,16 +3,19 @@
 #![allow(dead_code)]
 use winnow::binary;
 use winnow::combinator::peek;
-use winnow::error::StrContext;
+use winnow::error::{AddContext, ErrMode, ErrorKind, ParserError, StrContext};
 use winnow::prelude::*;
+use winnow::stream::Stream;
 use winnow::Bytes;
 
-use crate::parse::error::{MBResult, MBusError};
+use crate::parse::error::{unsupported, MBResult, MBusError};
+use crate::parse::options::ParseOptions;
 use crate::parse::types::number::parse_bcd;
 
-use super::manufacturer::{device_name, unpack_manufacturer_code};
+use super::manufacturer::{device_name, parse_manufacturer, ManufacturerCode};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum ApplicationError {
 	None,
 	Busy,
@@ -27,7 +30,8 @@ pub enum ApplicationError {
 
 // TODO: This is packed into a single byte so we should be able to use a
 // bitfield or something as opposed to 7 bytes
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct MeterStatus {
 	pub manufacturer_2: bool,
 	pub manufacturer_1: bool,
@@ -88,49 +92,99 @@ impl MeterStatus {
 
 /// This is a placeholder until I actually have some way to test security modes
 /// For more information see BS EN 13757-7:2018 7.6.2 and 7.6.3
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ExtraHeader;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum SecurityMode {
 	None,
 	/// Indicates that the packet is corrupted and should be discarded, unless
 	/// you're the libmbus test data that requires me to support this
 	Reserved(u16),
 }
+
+/// The bits of the configuration field that are shared between all security
+/// modes, which OMS uses to carry routing information.
+///
+/// For more information see BS EN 13757-7:2018 7.6.2
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RoutingInfo {
+	/// The meter is able to receive commands after sending this message
+	pub bidirectional: bool,
+	/// The meter is listening for a short while after sending this message
+	pub accessibility: bool,
+	/// The message was sent on a synchronous schedule
+	pub synchronous: bool,
+	/// How many times this message has been passed on by a repeater
+	pub hop_count: u8,
+}
+
+impl RoutingInfo {
+	/// Whether the message was passed on by at least one repeater rather than
+	/// coming directly from the meter
+	pub fn is_repeated(&self) -> bool {
+		self.hop_count > 0
+	}
+}
+
+const MASK_HOP_COUNT: u8 = 0b0000_0011;
+
 impl SecurityMode {
-	fn parse(input: &mut &Bytes) -> MBResult<SecurityMode> {
+	fn parse(input: &mut &Bytes) -> MBResult<(SecurityMode, RoutingInfo)> {
 		let raw_value = peek(binary::le_u16)
 			.context(StrContext::Label("Raw value peek"))
 			.parse_next(input)?;
-		(binary::bits::bits::<_, _, MBusError, _, _>((
+		let checkpoint = input.checkpoint();
+		let (info_low, bidirectional, accessibility, synchronous, security_mode): (
+			u8,
+			bool,
+			bool,
+			bool,
+			u8,
+		) = binary::bits::bits::<_, _, MBusError, _, _>((
 			binary::bits::take(8_usize).context(StrContext::Label("Security mode info low")),
+			binary::bits::bool.context(StrContext::Label("Bidirectional")),
+			binary::bits::bool.context(StrContext::Label("Accessibility")),
+			binary::bits::bool.context(StrContext::Label("Synchronous")),
 			binary::bits::take(5_usize).context(StrContext::Label("Security mode")),
-			binary::bits::take(3_usize).context(StrContext::Label("Security mode info high")),
-		)))
-		.verify_map(|(info_low, security_mode, info_high): (u8, u8, u8)| {
-			match security_mode {
-				0 => {
-					if info_high == 0 && info_low == 0 {
-						Some(SecurityMode::None)
-					} else {
-						None
-					}
-				}
-				// libmbus strikes again
-				6 | 11 | 12 | 14 | 16..=31 => Some(SecurityMode::Reserved(raw_value)),
-				_ => todo!("Packet encryption is not yet supported (mode {security_mode})"),
+		))
+		.parse_next(input)?;
+
+		let routing = RoutingInfo {
+			bidirectional,
+			accessibility,
+			synchronous,
+			hop_count: info_low & MASK_HOP_COUNT,
+		};
+		let mode = match security_mode {
+			0 if info_low & !MASK_HOP_COUNT == 0 => SecurityMode::None,
+			0 => {
+				return Err(
+					ErrMode::from_error_kind(input, ErrorKind::Verify).add_context(
+						input,
+						&checkpoint,
+						StrContext::Label("Security mode info"),
+					),
+				);
 			}
-		})
-		.parse_next(input)
+			// libmbus strikes again
+			6 | 11 | 12 | 14 | 16..=31 => SecurityMode::Reserved(raw_value),
+			_ => return Err(unsupported(input, &checkpoint, "encrypted packets")),
+		};
+		Ok((mode, routing))
 	}
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ShortHeader {
 	pub access_number: u8,
 	pub status: MeterStatus,
 	pub configuration_field: SecurityMode,
+	pub routing: RoutingInfo,
 	pub extra_header: Option<ExtraHeader>,
 }
 
@@ -145,20 +199,24 @@ impl ShortHeader {
 			MeterStatus::parse.context(StrContext::Label("status")),
 			SecurityMode::parse.context(StrContext::Label("tpl configuration field")),
 		)
-			.map(|(access_number, status, configuration_field)| ShortHeader {
-				access_number,
-				status,
-				configuration_field,
-				// This value is set by the contents of `configuration_field`
-				// which as established above is always 0 at this point which
-				// means "no extra headers"
-				extra_header: None,
-			})
+			.map(
+				|(access_number, status, (configuration_field, routing))| ShortHeader {
+					access_number,
+					status,
+					configuration_field,
+					routing,
+					// This value is set by the contents of `configuration_field`
+					// which as established above is always 0 at this point which
+					// means "no extra headers"
+					extra_header: None,
+				},
+			)
 			.parse_next(input)
 	}
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum WaterMeterType {
 	Potable,      // temperature unspecified
 	Irrigation,   // (unpotable)
@@ -169,7 +227,8 @@ pub enum WaterMeterType {
 	Waste,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum ThermalMeterType {
 	OutletHeat,
 	InletHeat,
@@ -178,7 +237,8 @@ pub enum ThermalMeterType {
 	Combined,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum DeviceType {
 	Other,
 	OilMeter,
@@ -222,7 +282,62 @@ pub enum DeviceType {
 }
 
 impl DeviceType {
-	fn parse(input: &mut &Bytes) -> MBResult<Self> {
+	/// Any kind of water meter, including waste water
+	pub fn is_water_meter(&self) -> bool {
+		matches!(self, Self::WaterMeter(_))
+	}
+
+	/// Thermal energy meters that measure heat, including combined heat and
+	/// cooling meters
+	pub fn is_heat_meter(&self) -> bool {
+		matches!(
+			self,
+			Self::ThermalEnergyMeter(
+				ThermalMeterType::OutletHeat
+					| ThermalMeterType::InletHeat
+					| ThermalMeterType::Combined
+			)
+		)
+	}
+
+	/// Thermal energy meters that measure cooling, including combined heat
+	/// and cooling meters
+	pub fn is_cooling_meter(&self) -> bool {
+		matches!(
+			self,
+			Self::ThermalEnergyMeter(
+				ThermalMeterType::OutletCooling
+					| ThermalMeterType::InletCooling
+					| ThermalMeterType::Combined
+			)
+		)
+	}
+
+	pub fn is_electricity_meter(&self) -> bool {
+		matches!(self, Self::ElectricityMeter)
+	}
+
+	/// Only actual gas meters, not gas converters or data loggers
+	pub fn is_gas_meter(&self) -> bool {
+		matches!(self, Self::GasMeter)
+	}
+
+	pub fn is_repeater(&self) -> bool {
+		matches!(
+			self,
+			Self::UnidirectionalRepeater | Self::BidirectionalRepeater
+		)
+	}
+
+	/// Anything from the sensor block of the device type table (0x1A to 0x1F)
+	pub fn is_sensor(&self) -> bool {
+		matches!(
+			self,
+			Self::SmokeDetector | Self::RoomSensor | Self::GasDetector | Self::ReservedSensor
+		)
+	}
+
+	pub(crate) fn parse(input: &mut &Bytes) -> MBResult<Self> {
 		binary::u8
 			.map(|v| match v {
 				0x00 => Self::Other,
@@ -280,34 +395,89 @@ impl DeviceType {
 	}
 }
 
-#[derive(Debug, Clone)]
+impl std::fmt::Display for DeviceType {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.write_str(match self {
+			Self::Other => "other",
+			Self::OilMeter => "oil meter",
+			Self::ElectricityMeter => "electricity meter",
+			Self::GasMeter => "gas meter",
+			Self::ThermalEnergyMeter(ThermalMeterType::OutletHeat) => "heat meter (outlet)",
+			Self::ThermalEnergyMeter(ThermalMeterType::InletHeat) => "heat meter (inlet)",
+			Self::ThermalEnergyMeter(ThermalMeterType::OutletCooling) => "cooling meter (outlet)",
+			Self::ThermalEnergyMeter(ThermalMeterType::InletCooling) => "cooling meter (inlet)",
+			Self::ThermalEnergyMeter(ThermalMeterType::Combined) => "combined heat/cooling meter",
+			Self::SteamMeter => "steam meter",
+			Self::WaterMeter(WaterMeterType::Potable) => "water meter",
+			Self::WaterMeter(WaterMeterType::Irrigation) => "irrigation water meter",
+			Self::WaterMeter(WaterMeterType::Cold) => "cold water meter",
+			Self::WaterMeter(WaterMeterType::Warm) => "warm water meter",
+			Self::WaterMeter(WaterMeterType::Hot) => "hot water meter",
+			Self::WaterMeter(WaterMeterType::DualRegister) => "dual register water meter",
+			Self::WaterMeter(WaterMeterType::Waste) => "waste water meter",
+			Self::HeatCostAllocator => "heat cost allocator",
+			Self::CompressedAir => "compressed air",
+			Self::BusOrSystemComponent => "bus/system component",
+			Self::Unknown => "unknown medium",
+			Self::WaterDataLogger => "water data logger",
+			Self::GasDataLogger => "gas data logger",
+			Self::GasConverter => "gas converter",
+			Self::CalorificValue => "calorific value",
+			Self::PressureMeter => "pressure meter",
+			Self::ADConverter => "A/D converter",
+			Self::SmokeDetector => "smoke detector",
+			Self::RoomSensor => "room sensor",
+			Self::GasDetector => "gas detector",
+			Self::ReservedSensor => "reserved sensor",
+			Self::ElectricalBreaker => "electrical breaker",
+			Self::Valve => "valve",
+			Self::ReservedSwitchingDevice => "reserved switching device",
+			Self::CustomerUnit => "customer unit",
+			Self::ReservedCustomerUnit => "reserved customer unit",
+			Self::Garbage => "garbage",
+			Self::ReservedCO2 => "reserved carbon dioxide meter",
+			Self::ReservedEnvironmental => "reserved environmental meter",
+			Self::ServiceTool => "service tool",
+			Self::CommunicationController => "communication controller",
+			Self::UnidirectionalRepeater => "unidirectional repeater",
+			Self::BidirectionalRepeater => "bidirectional repeater",
+			Self::ReservedSystemDevice => "reserved system device",
+			Self::RadioConverterSystemSide => "radio converter (system side)",
+			Self::RadioConverterMeterSide => "radio converter (meter side)",
+			Self::BusConverterMeterSide => "bus converter (meter side)",
+			Self::Reserved => "reserved",
+			Self::Wildcard => "wildcard",
+		})
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct LongHeader {
 	pub identifier: u32,
-	pub manufacturer: String,
+	pub manufacturer: ManufacturerCode,
 	pub device_name: Option<&'static str>,
 	pub version: u8,
 	pub device_type: DeviceType,
 	pub access_number: u8,
 	pub status: MeterStatus,
 	pub configuration_field: SecurityMode,
+	pub routing: RoutingInfo,
 	pub extra_header: Option<ExtraHeader>,
 }
 
 impl LongHeader {
 	pub fn parse(input: &mut &Bytes) -> MBResult<TPLHeader> {
+		Self::parse_with(ParseOptions::default()).parse_next(input)
+	}
+
+	pub fn parse_with<'a>(options: ParseOptions) -> impl Parser<&'a Bytes, TPLHeader, MBusError> {
 		(
 			parse_bcd(4)
 				.try_map(u32::try_from)
 				.with_recognized()
 				.context(StrContext::Label("device identifier")),
-			binary::le_u16
-				.verify_map(|raw| {
-					unpack_manufacturer_code(raw)
-						.ok()
-						.filter(|parsed| parsed.chars().all(|c| c.is_ascii_uppercase()))
-						.map(|parsed| (parsed, raw))
-				})
-				.context(StrContext::Label("manufacturer")),
+			parse_manufacturer(options.strict_manufacturers),
 			binary::u8.context(StrContext::Label("version")),
 			DeviceType::parse.context(StrContext::Label("device type")),
 			// The rest of the long header is simply the short header, so use that parser
@@ -316,7 +486,7 @@ impl LongHeader {
 			.map(
 				|(
 					(identifier, raw_identifier),
-					(manufacturer, raw_manufacturer),
+					manufacturer,
 					version,
 					device_type,
 					short_header,
@@ -325,7 +495,7 @@ impl LongHeader {
 					manufacturer,
 					device_name: device_name(
 						raw_identifier,
-						raw_manufacturer,
+						manufacturer.as_u16(),
 						version,
 						device_type,
 					),
@@ -334,17 +504,448 @@ impl LongHeader {
 					access_number: short_header.access_number,
 					status: short_header.status,
 					configuration_field: short_header.configuration_field,
+					routing: short_header.routing,
 					extra_header: short_header.extra_header,
 				},
 			)
 			.map(TPLHeader::Long)
-			.parse_next(input)
 	}
 }
 
-#[derive(Debug, Clone)]
+/// The address of a wireless meter, which is the same as the identity in a
+/// long header but with the manufacturer first
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct WirelessAddress {
+	pub manufacturer: ManufacturerCode,
+	pub identifier: u32,
+	pub version: u8,
+	pub device_type: DeviceType,
+}
+
+impl WirelessAddress {
+	fn parser<'a>(options: ParseOptions) -> impl Parser<&'a Bytes, Self, MBusError> {
+		(
+			parse_manufacturer(options.strict_manufacturers),
+			parse_bcd(4)
+				.try_map(u32::try_from)
+				.context(StrContext::Label("device identifier")),
+			binary::u8.context(StrContext::Label("version")),
+			DeviceType::parse.context(StrContext::Label("device type")),
+		)
+			.map(
+				|(manufacturer, identifier, version, device_type)| WirelessAddress {
+					manufacturer,
+					identifier,
+					version,
+					device_type,
+				},
+			)
+	}
+}
+
+const MASK_SESSION_ENCRYPTION: u32 = 0b1110_0000_0000_0000_0000_0000_0000_0000;
+
+/// EN 13757-4:2019 Clause 13.2 - The extended link layer which can precede the
+/// transport layer in wireless M-Bus messages
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ExtendedLinkHeader {
+	pub communication_control: u8,
+	pub access_number: u8,
+	/// Only present in ELL III and ELL IV
+	pub address: Option<WirelessAddress>,
+	/// Only present in ELL II and ELL IV
+	pub session_number: Option<u32>,
+	/// Only present in ELL II and ELL IV, and only readable if the message
+	/// isn't encrypted
+	pub payload_crc: Option<u16>,
+}
+
+impl ExtendedLinkHeader {
+	/// Parses the header that follows the CI fields 0x8C to 0x8F, failing for
+	/// any other CI field.
+	///
+	/// If the session number says the message is encrypted then so is the
+	/// payload CRC, which is left in the encrypted data for whoever decrypts it
+	/// and `payload_crc` is `None`.
+	pub fn parse<'a>(options: ParseOptions, ci: u8) -> impl Parser<&'a Bytes, Self, MBusError> {
+		move |input: &mut &'a Bytes| {
+			let (has_address, has_session) = match ci {
+				0x8C => (false, false),
+				0x8D => (false, true),
+				0x8E => (true, false),
+				0x8F => (true, true),
+				_ => {
+					return Err(
+						ErrMode::from_error_kind(input, ErrorKind::Verify).add_context(
+							input,
+							&input.checkpoint(),
+							StrContext::Label("ELL CI field"),
+						),
+					);
+				}
+			};
+			let (communication_control, access_number) = (
+				binary::u8.context(StrContext::Label("communication control")),
+				binary::u8.context(StrContext::Label("access number")),
+			)
+				.parse_next(input)?;
+			let address = if has_address {
+				Some(
+					WirelessAddress::parser(options)
+						.context(StrContext::Label("ELL address"))
+						.parse_next(input)?,
+				)
+			} else {
+				None
+			};
+			let (session_number, payload_crc) = if has_session {
+				let session_number = binary::le_u32
+					.context(StrContext::Label("session number"))
+					.parse_next(input)?;
+				let payload_crc = if session_number & MASK_SESSION_ENCRYPTION == 0 {
+					Some(
+						binary::le_u16
+							.context(StrContext::Label("payload CRC"))
+							.parse_next(input)?,
+					)
+				} else {
+					None
+				};
+				(Some(session_number), payload_crc)
+			} else {
+				(None, None)
+			};
+			Ok(Self {
+				communication_control,
+				access_number,
+				address,
+				session_number,
+				payload_crc,
+			})
+		}
+	}
+
+	/// Whether the session number says the rest of the message is encrypted
+	pub fn is_encrypted(&self) -> bool {
+		self.session_number
+			.is_some_and(|sn| sn & MASK_SESSION_ENCRYPTION != 0)
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum TPLHeader {
 	None,
 	Short(ShortHeader),
 	Long(LongHeader),
 }
+
+impl TPLHeader {
+	/// The manufacturer code of the device, if the header has one
+	pub fn manufacturer(&self) -> Option<ManufacturerCode> {
+		match self {
+			Self::Long(header) => Some(header.manufacturer),
+			_ => None,
+		}
+	}
+
+	/// The access number of the message, if the header has one
+	pub fn access_number(&self) -> Option<u8> {
+		match self {
+			Self::None => None,
+			Self::Short(header) => Some(header.access_number),
+			Self::Long(header) => Some(header.access_number),
+		}
+	}
+
+	/// The routing information from the configuration field, if the header
+	/// has one
+	pub fn routing(&self) -> Option<RoutingInfo> {
+		match self {
+			Self::None => None,
+			Self::Short(header) => Some(header.routing),
+			Self::Long(header) => Some(header.routing),
+		}
+	}
+}
+
+#[cfg(test)]
+mod test_device_type {
+	use rstest::rstest;
+	use winnow::prelude::*;
+	use winnow::Bytes;
+
+	use super::{DeviceType, ThermalMeterType, WaterMeterType};
+
+	fn device_type(value: u8) -> DeviceType {
+		DeviceType::parse.parse(Bytes::new(&[value])).unwrap()
+	}
+
+	fn is_reserved(device_type: &DeviceType) -> bool {
+		matches!(
+			device_type,
+			DeviceType::ReservedSensor
+				| DeviceType::ReservedSwitchingDevice
+				| DeviceType::ReservedCustomerUnit
+				| DeviceType::ReservedCO2
+				| DeviceType::ReservedEnvironmental
+				| DeviceType::ReservedSystemDevice
+				| DeviceType::Reserved
+		)
+	}
+
+	#[rstest]
+	#[case::other(0x00, DeviceType::Other)]
+	#[case::oil(0x01, DeviceType::OilMeter)]
+	#[case::electricity(0x02, DeviceType::ElectricityMeter)]
+	#[case::gas(0x03, DeviceType::GasMeter)]
+	#[case::outlet_heat(0x04, DeviceType::ThermalEnergyMeter(ThermalMeterType::OutletHeat))]
+	#[case::steam(0x05, DeviceType::SteamMeter)]
+	#[case::warm_water(0x06, DeviceType::WaterMeter(WaterMeterType::Warm))]
+	#[case::potable_water(0x07, DeviceType::WaterMeter(WaterMeterType::Potable))]
+	#[case::heat_cost_allocator(0x08, DeviceType::HeatCostAllocator)]
+	#[case::compressed_air(0x09, DeviceType::CompressedAir)]
+	#[case::outlet_cooling(0x0A, DeviceType::ThermalEnergyMeter(ThermalMeterType::OutletCooling))]
+	#[case::inlet_cooling(0x0B, DeviceType::ThermalEnergyMeter(ThermalMeterType::InletCooling))]
+	#[case::inlet_heat(0x0C, DeviceType::ThermalEnergyMeter(ThermalMeterType::InletHeat))]
+	#[case::combined(0x0D, DeviceType::ThermalEnergyMeter(ThermalMeterType::Combined))]
+	#[case::bus_component(0x0E, DeviceType::BusOrSystemComponent)]
+	#[case::unknown(0x0F, DeviceType::Unknown)]
+	#[case::irrigation_water(0x10, DeviceType::WaterMeter(WaterMeterType::Irrigation))]
+	#[case::water_data_logger(0x11, DeviceType::WaterDataLogger)]
+	#[case::gas_data_logger(0x12, DeviceType::GasDataLogger)]
+	#[case::gas_converter(0x13, DeviceType::GasConverter)]
+	#[case::calorific_value(0x14, DeviceType::CalorificValue)]
+	#[case::hot_water(0x15, DeviceType::WaterMeter(WaterMeterType::Hot))]
+	#[case::cold_water(0x16, DeviceType::WaterMeter(WaterMeterType::Cold))]
+	#[case::dual_register_water(0x17, DeviceType::WaterMeter(WaterMeterType::DualRegister))]
+	#[case::pressure(0x18, DeviceType::PressureMeter)]
+	#[case::ad_converter(0x19, DeviceType::ADConverter)]
+	#[case::smoke_detector(0x1A, DeviceType::SmokeDetector)]
+	#[case::room_sensor(0x1B, DeviceType::RoomSensor)]
+	#[case::gas_detector(0x1C, DeviceType::GasDetector)]
+	#[case::breaker(0x20, DeviceType::ElectricalBreaker)]
+	#[case::valve(0x21, DeviceType::Valve)]
+	#[case::customer_unit(0x25, DeviceType::CustomerUnit)]
+	#[case::waste_water(0x28, DeviceType::WaterMeter(WaterMeterType::Waste))]
+	#[case::garbage(0x29, DeviceType::Garbage)]
+	#[case::service_tool(0x30, DeviceType::ServiceTool)]
+	#[case::communication_controller(0x31, DeviceType::CommunicationController)]
+	#[case::unidirectional_repeater(0x32, DeviceType::UnidirectionalRepeater)]
+	#[case::bidirectional_repeater(0x33, DeviceType::BidirectionalRepeater)]
+	#[case::radio_converter_system(0x36, DeviceType::RadioConverterSystemSide)]
+	#[case::radio_converter_meter(0x37, DeviceType::RadioConverterMeterSide)]
+	#[case::bus_converter_meter(0x38, DeviceType::BusConverterMeterSide)]
+	#[case::wildcard(0xFF, DeviceType::Wildcard)]
+	fn test_specific_codes(#[case] value: u8, #[case] expected: DeviceType) {
+		assert_eq!(device_type(value), expected);
+	}
+
+	#[rstest]
+	#[case::sensor(0x1D..=0x1F, DeviceType::ReservedSensor)]
+	#[case::switching_device(0x22..=0x24, DeviceType::ReservedSwitchingDevice)]
+	#[case::customer_unit(0x26..=0x27, DeviceType::ReservedCustomerUnit)]
+	#[case::co2(0x2A..=0x2A, DeviceType::ReservedCO2)]
+	#[case::environmental(0x2B..=0x2F, DeviceType::ReservedEnvironmental)]
+	#[case::system_device(0x34..=0x35, DeviceType::ReservedSystemDevice)]
+	#[case::more_system_devices(0x39..=0x3F, DeviceType::ReservedSystemDevice)]
+	#[case::general(0x40..=0xFE, DeviceType::Reserved)]
+	fn test_reserved_ranges(
+		#[case] values: std::ops::RangeInclusive<u8>,
+		#[case] expected: DeviceType,
+	) {
+		for value in values {
+			assert_eq!(device_type(value), expected, "{value:#04X}");
+		}
+	}
+
+	#[test]
+	fn test_every_byte() {
+		let all: Vec<_> = (0..=u8::MAX).map(device_type).collect();
+
+		// Every code that isn't reserved means something different, so a
+		// range boundary moving would show up as a duplicate here
+		for (value, device_type) in all.iter().enumerate() {
+			if is_reserved(device_type) {
+				continue;
+			}
+			let count = all.iter().filter(|other| *other == device_type).count();
+			assert_eq!(count, 1, "{value:#04X} {device_type:?}");
+		}
+		assert_eq!(all.iter().filter(|t| !is_reserved(t)).count(), 42);
+	}
+
+	#[test]
+	fn test_display_is_unique() {
+		let mut names: Vec<_> = (0..=u8::MAX)
+			.map(device_type)
+			.filter(|device_type| !is_reserved(device_type))
+			.map(|device_type| device_type.to_string())
+			.collect();
+		names.sort();
+		names.dedup();
+
+		assert_eq!(names.len(), 42);
+	}
+
+	#[rstest]
+	#[case::warm(0x06)]
+	#[case::potable(0x07)]
+	#[case::irrigation(0x10)]
+	#[case::hot(0x15)]
+	#[case::cold(0x16)]
+	#[case::dual_register(0x17)]
+	#[case::waste(0x28)]
+	fn test_water_meters(#[case] value: u8) {
+		let device_type = device_type(value);
+
+		assert!(device_type.is_water_meter());
+		assert!(!device_type.is_heat_meter());
+		assert!(!device_type.is_cooling_meter());
+	}
+
+	#[rstest]
+	#[case::outlet_heat(0x04, true, false)]
+	#[case::outlet_cooling(0x0A, false, true)]
+	#[case::inlet_cooling(0x0B, false, true)]
+	#[case::inlet_heat(0x0C, true, false)]
+	#[case::combined(0x0D, true, true)]
+	fn test_thermal_meters(#[case] value: u8, #[case] heat: bool, #[case] cooling: bool) {
+		let device_type = device_type(value);
+
+		assert_eq!(device_type.is_heat_meter(), heat);
+		assert_eq!(device_type.is_cooling_meter(), cooling);
+		assert!(!device_type.is_water_meter());
+	}
+
+	#[rstest]
+	#[case::water_data_logger(0x11)]
+	#[case::steam(0x05)]
+	#[case::gas_converter(0x13)]
+	fn test_not_water_or_heat(#[case] value: u8) {
+		let device_type = device_type(value);
+
+		assert!(!device_type.is_water_meter());
+		assert!(!device_type.is_heat_meter());
+		assert!(!device_type.is_gas_meter());
+	}
+
+	#[test]
+	fn test_other_predicates() {
+		assert!(device_type(0x02).is_electricity_meter());
+		assert!(device_type(0x03).is_gas_meter());
+		assert!(device_type(0x32).is_repeater());
+		assert!(device_type(0x33).is_repeater());
+		assert!(!device_type(0x31).is_repeater());
+		assert!(device_type(0x1A).is_sensor());
+		assert!(device_type(0x1B).is_sensor());
+		assert!(device_type(0x1F).is_sensor());
+		assert!(!device_type(0x20).is_sensor());
+	}
+}
+
+#[cfg(test)]
+mod test_routing_info {
+	use rstest::rstest;
+	use winnow::prelude::*;
+	use winnow::Bytes;
+
+	use super::{RoutingInfo, SecurityMode, ShortHeader, TPLHeader};
+	use crate::parse::link_layer::Packet;
+	use crate::parse::transport_layer::MBusMessage;
+	use crate::utils::read_test_file;
+
+	#[rstest]
+	#[case::oms_frame1("oms_frame1.hex")]
+	#[case::oms_frame2("oms_frame2.hex")]
+	#[case::oms_frame3("oms_frame3.hex")]
+	fn test_oms_frame(#[case] filename: &str) {
+		let data = read_test_file(&format!("./libmbus_test_data/test-frames/{filename}")).unwrap();
+		let packet = Packet::parse.parse(Bytes::new(&data)).unwrap();
+
+		let Packet::Long {
+			message: MBusMessage::ResponseFromDevice(header, _),
+			..
+		} = packet
+		else {
+			panic!("Expected a response from device, got {packet:?}");
+		};
+		let TPLHeader::Long(header) = header else {
+			panic!("Expected a long header, got {header:?}");
+		};
+		assert_eq!(header.configuration_field, SecurityMode::None);
+		assert_eq!(header.routing, RoutingInfo::default());
+		assert!(!header.routing.is_repeated());
+	}
+
+	#[rstest]
+	#[case::nothing(0x00, 0x00, RoutingInfo::default())]
+	#[case::hop_count(0x02, 0x00, RoutingInfo { hop_count: 2, ..Default::default() })]
+	#[case::bidirectional(0x00, 0x80, RoutingInfo { bidirectional: true, ..Default::default() })]
+	#[case::accessibility(0x00, 0x40, RoutingInfo { accessibility: true, ..Default::default() })]
+	#[case::synchronous(0x00, 0x20, RoutingInfo { synchronous: true, ..Default::default() })]
+	#[case::everything(
+		0x03,
+		0xE0,
+		RoutingInfo { bidirectional: true, accessibility: true, synchronous: true, hop_count: 3 },
+	)]
+	fn test_unencrypted(#[case] low: u8, #[case] high: u8, #[case] expected: RoutingInfo) {
+		let data = [0x2A, 0x00, low, high];
+		let result = ShortHeader::parse.parse(Bytes::new(&data)).unwrap();
+
+		assert_eq!(result.routing(), Some(expected));
+		let TPLHeader::Short(header) = result else {
+			panic!("Expected a short header, got {result:?}");
+		};
+		assert_eq!(header.configuration_field, SecurityMode::None);
+		assert_eq!(header.routing, expected);
+	}
+
+	#[test]
+	fn test_unencrypted_reserved_bits() {
+		let data = [0x2A, 0x00, 0x04, 0x00];
+
+		ShortHeader::parse.parse(Bytes::new(&data)).unwrap_err();
+	}
+}
+
+#[cfg(test)]
+mod test_long_header {
+	use winnow::prelude::*;
+	use winnow::Bytes;
+
+	use super::{LongHeader, TPLHeader};
+	use crate::parse::options::ParseOptions;
+
+	// "KA[" isn't a valid manufacturer code but it still has to be packed
+	const NON_STANDARD: [u8; 12] = [
+		0x78, 0x56, 0x34, 0x12, 0x3B, 0x2C, 0x01, 0x07, 0x2A, 0x00, 0x00, 0x00,
+	];
+
+	#[test]
+	fn test_non_standard_manufacturer_lenient() {
+		let options = ParseOptions {
+			strict_manufacturers: false,
+			..Default::default()
+		};
+
+		let result = LongHeader::parse_with(options)
+			.parse(Bytes::new(&NON_STANDARD))
+			.unwrap();
+
+		let TPLHeader::Long(header) = result else {
+			panic!("Expected a long header, got {result:?}");
+		};
+		assert!(!header.manufacturer.is_standard());
+		assert_eq!(header.manufacturer.to_string(), "KA[");
+	}
+
+	#[test]
+	fn test_non_standard_manufacturer_strict_by_default() {
+		LongHeader::parse
+			.parse(Bytes::new(&NON_STANDARD))
+			.unwrap_err();
+	}
+}
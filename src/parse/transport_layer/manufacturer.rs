@@ -4,6 +4,13 @@
 // Much of the code in this file is based on code from the rSCADA/libmbus
 // project by Raditex Control AB (c) 2010-2012
 
+use winnow::binary;
+use winnow::error::StrContext;
+use winnow::prelude::*;
+use winnow::Bytes;
+
+use crate::parse::error::MBusError;
+
 use super::header::{DeviceType, WaterMeterType};
 
 const fn characterise(c: u16) -> u8 {
@@ -18,6 +25,103 @@ pub fn unpack_manufacturer_code(packed: u16) -> Result<String, std::string::From
 	])
 }
 
+/// A manufacturer's three letter FLAG code, packed into two bytes the way
+/// it's sent over the wire
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ManufacturerCode(u16);
+
+impl ManufacturerCode {
+	/// Wraps a packed code, returning `None` if it doesn't unpack into three
+	/// uppercase letters
+	pub fn from_u16(packed: u16) -> Option<Self> {
+		let code = Self(packed);
+		code.is_standard().then_some(code)
+	}
+
+	/// Wraps a packed code even if it doesn't unpack into three uppercase
+	/// letters, see [`ManufacturerCode::is_standard`]
+	pub const fn from_u16_lenient(packed: u16) -> Self {
+		Self(packed)
+	}
+
+	/// Returns false if the code unpacks into something other than three
+	/// uppercase letters. The packing only has room for `@`, `A` to `Z` and
+	/// `[\]^_` so these codes can't be registered, but some meters send
+	/// them anyway.
+	pub fn is_standard(self) -> bool {
+		self.letters().iter().all(u8::is_ascii_uppercase)
+	}
+
+	pub const fn as_u16(self) -> u16 {
+		self.0
+	}
+
+	fn letters(self) -> [u8; 3] {
+		[
+			characterise(self.0 >> 10),
+			characterise(self.0 >> 5),
+			characterise(self.0),
+		]
+	}
+}
+
+impl std::fmt::Display for ManufacturerCode {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		for c in self.letters() {
+			write!(f, "{}", c as char)?;
+		}
+		Ok(())
+	}
+}
+
+/// Serialised as the three letter code rather than the packed number
+#[cfg(feature = "serde")]
+impl serde::Serialize for ManufacturerCode {
+	fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.collect_str(self)
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidManufacturerCode;
+
+impl std::fmt::Display for InvalidManufacturerCode {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "manufacturer codes must be 3 uppercase letters")
+	}
+}
+
+impl std::error::Error for InvalidManufacturerCode {}
+
+impl std::str::FromStr for ManufacturerCode {
+	type Err = InvalidManufacturerCode;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match *s.as_bytes() {
+			[a, b, c] if [a, b, c].iter().all(u8::is_ascii_uppercase) => Ok(Self(
+				(u16::from(a) - 64) << 10 | (u16::from(b) - 64) << 5 | (u16::from(c) - 64),
+			)),
+			_ => Err(InvalidManufacturerCode),
+		}
+	}
+}
+
+/// Parses a packed manufacturer code, rejecting codes that aren't three
+/// uppercase letters if `strict` is set
+pub(crate) fn parse_manufacturer<'a>(
+	strict: bool,
+) -> impl Parser<&'a Bytes, ManufacturerCode, MBusError> {
+	binary::le_u16
+		.verify_map(move |packed| {
+			if strict {
+				ManufacturerCode::from_u16(packed)
+			} else {
+				Some(ManufacturerCode::from_u16_lenient(packed))
+			}
+		})
+		.context(StrContext::Label("manufacturer"))
+}
+
 const fn pack_manufacturer_code(code: &'static str) -> u16 {
 	let code = code.as_bytes();
 	let [a, b, c] = *code else {
@@ -183,3 +287,73 @@ pub fn device_name(
 		_ => None,
 	}
 }
+
+#[cfg(test)]
+mod test_manufacturer_code {
+	use rstest::rstest;
+	use winnow::prelude::*;
+	use winnow::Bytes;
+
+	use super::{
+		pack_manufacturer_code, parse_manufacturer, InvalidManufacturerCode, ManufacturerCode,
+	};
+
+	#[rstest]
+	#[case::kamstrup("KAM", 0x2C2D)]
+	#[case::abb("ABB", 0x0442)]
+	#[case::relay("REL", pack_manufacturer_code("REL"))]
+	#[case::zenner("ZRM", pack_manufacturer_code("ZRM"))]
+	fn test_round_trip(#[case] code: &str, #[case] packed: u16) {
+		let parsed: ManufacturerCode = code.parse().unwrap();
+
+		assert_eq!(parsed.as_u16(), packed);
+		assert_eq!(ManufacturerCode::from_u16(packed), Some(parsed));
+		assert_eq!(parsed.to_string(), code);
+	}
+
+	#[rstest]
+	#[case::empty("")]
+	#[case::short("KA")]
+	#[case::long("KAMS")]
+	#[case::lowercase("kam")]
+	#[case::digits("K4M")]
+	#[case::non_ascii("KÄM")]
+	fn test_invalid(#[case] code: &str) {
+		assert_eq!(
+			code.parse::<ManufacturerCode>(),
+			Err(InvalidManufacturerCode)
+		);
+	}
+
+	#[test]
+	fn test_invalid_packed() {
+		// 0 unpacks as "@@@"
+		assert_eq!(ManufacturerCode::from_u16(0), None);
+	}
+
+	#[rstest]
+	#[case::at_sign(0x0000, "@@@")]
+	#[case::bracket(0x2C3B, "KA[")]
+	#[case::underscore(0x7C21, "_AA")]
+	fn test_lenient(#[case] packed: u16, #[case] expected: &str) {
+		let code = ManufacturerCode::from_u16_lenient(packed);
+
+		assert!(!code.is_standard());
+		assert_eq!(code.as_u16(), packed);
+		assert_eq!(code.to_string(), expected);
+	}
+
+	#[rstest]
+	#[case::strict(true, None)]
+	#[case::lenient(false, Some("KA["))]
+	fn test_parse_manufacturer(#[case] strict: bool, #[case] expected: Option<&str>) {
+		let packed = [0x3B, 0x2C];
+
+		let result = parse_manufacturer(strict).parse(Bytes::new(&packed));
+
+		assert_eq!(
+			result.ok().map(|code| code.to_string()).as_deref(),
+			expected
+		);
+	}
+}
@@ -0,0 +1,165 @@
+// Copyright 2024 Lexi Robinson
+// Licensed under the EUPL-1.2
+use winnow::binary;
+use winnow::error::StrContext;
+use winnow::prelude::*;
+use winnow::token::take;
+use winnow::Bytes;
+
+use crate::parse::error::MBResult;
+
+use super::header::{DeviceType, LongHeader};
+use super::manufacturer::ManufacturerCode;
+
+/// The secondary address sent with CI 0x52 to select a device, where any
+/// part can be a wildcard that matches everything
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SecondaryAddress {
+	/// The digits of the identification number, most significant first, with
+	/// `None` for any digit that was sent as the wildcard nibble `F`
+	pub identifier: [Option<u8>; 8],
+	/// `None` if the manufacturer was sent as the wildcard `0xFFFF`
+	pub manufacturer: Option<ManufacturerCode>,
+	/// `None` if the version was sent as the wildcard `0xFF`
+	pub version: Option<u8>,
+	pub device_type: DeviceType,
+}
+
+impl SecondaryAddress {
+	pub fn parse(input: &mut &Bytes) -> MBResult<Self> {
+		(
+			take(4_usize)
+				.verify_map(parse_identifier)
+				.context(StrContext::Label("identifier")),
+			binary::le_u16
+				.map(|packed| {
+					(packed != 0xFFFF).then_some(ManufacturerCode::from_u16_lenient(packed))
+				})
+				.context(StrContext::Label("manufacturer")),
+			binary::u8
+				.map(|version| (version != 0xFF).then_some(version))
+				.context(StrContext::Label("version")),
+			DeviceType::parse.context(StrContext::Label("device type")),
+		)
+			.map(
+				|(identifier, manufacturer, version, device_type)| SecondaryAddress {
+					identifier,
+					manufacturer,
+					version,
+					device_type,
+				},
+			)
+			.context(StrContext::Label("secondary address"))
+			.parse_next(input)
+	}
+
+	/// Whether the device with this long header should respond to the
+	/// selection
+	pub fn matches(&self, header: &LongHeader) -> bool {
+		self.matches_identifier(header.identifier)
+			&& self
+				.manufacturer
+				.is_none_or(|manufacturer| manufacturer == header.manufacturer)
+			&& self.version.is_none_or(|version| version == header.version)
+			&& (self.device_type == DeviceType::Wildcard || self.device_type == header.device_type)
+	}
+
+	fn matches_identifier(&self, identifier: u32) -> bool {
+		let mut remaining = identifier;
+		let mut digits = [0; 8];
+		for digit in digits.iter_mut().rev() {
+			*digit = (remaining % 10) as u8;
+			remaining /= 10;
+		}
+		self.identifier
+			.iter()
+			.zip(digits)
+			.all(|(wanted, digit)| wanted.is_none_or(|wanted| wanted == digit))
+	}
+}
+
+/// Splits the BCD identifier into digits, rejecting any nibble that's neither
+/// a digit nor the wildcard
+fn parse_identifier(data: &[u8]) -> Option<[Option<u8>; 8]> {
+	let mut digits = [None; 8];
+	let nibbles = data.iter().rev().flat_map(|byte| [byte >> 4, byte & 0x0F]);
+	for (digit, nibble) in digits.iter_mut().zip(nibbles) {
+		*digit = match nibble {
+			0..=9 => Some(nibble),
+			0xF => None,
+			_ => return None,
+		};
+	}
+	Some(digits)
+}
+
+#[cfg(test)]
+mod test_secondary_address {
+	use winnow::prelude::*;
+	use winnow::Bytes;
+
+	use super::SecondaryAddress;
+	use crate::parse::transport_layer::header::{DeviceType, LongHeader, TPLHeader};
+	use crate::parse::transport_layer::MBusMessage;
+
+	fn kamstrup_header() -> LongHeader {
+		let input = Bytes::new(&[
+			0x17, 0x58, 0x85, 0x06, 0x2D, 0x2C, 0x08, 0x04, 0x04, 0x00, 0x00, 0x00,
+		]);
+		let TPLHeader::Long(header) = LongHeader::parse.parse(input).unwrap() else {
+			panic!("expected a long header");
+		};
+		header
+	}
+
+	#[test]
+	fn test_wildcard_identifier() {
+		let input = Bytes::new(&[0x52, 0xFF, 0xFF, 0x85, 0x06, 0xFF, 0xFF, 0xFF, 0xFF]);
+
+		let MBusMessage::SelectionOfDevice(address, extra) =
+			MBusMessage::parse.parse(input).unwrap()
+		else {
+			panic!("expected a selection");
+		};
+
+		assert_eq!(
+			address,
+			SecondaryAddress {
+				identifier: [Some(0), Some(6), Some(8), Some(5), None, None, None, None],
+				manufacturer: None,
+				version: None,
+				device_type: DeviceType::Wildcard,
+			}
+		);
+		assert!(extra.is_empty());
+		assert!(address.matches(&kamstrup_header()));
+	}
+
+	#[test]
+	fn test_no_match() {
+		let header = kamstrup_header();
+		let mut address = SecondaryAddress::parse
+			.parse(Bytes::new(&[
+				0x17, 0x58, 0x85, 0x06, 0x2D, 0x2C, 0x08, 0x04,
+			]))
+			.unwrap();
+		assert!(address.matches(&header));
+
+		address.identifier[7] = Some(8);
+		assert!(!address.matches(&header));
+
+		address.identifier[7] = None;
+		address.device_type = DeviceType::GasMeter;
+		assert!(!address.matches(&header));
+	}
+
+	#[test]
+	fn test_invalid_identifier() {
+		SecondaryAddress::parse
+			.parse(Bytes::new(&[
+				0xFF, 0xFF, 0xA5, 0x06, 0xFF, 0xFF, 0xFF, 0xFF,
+			]))
+			.unwrap_err();
+	}
+}
@@ -12,7 +12,10 @@ use crate::parse::error::{MBResult, MBusError};
 
 use super::BitsInput;
 
-fn parse_dmy(input: &mut BitsInput<'_>) -> MBResult<(u8, u8, u8)> {
+/// The month value some meters send despite it not being a valid month
+const NONCONFORMANT_MONTH: u8 = 15;
+
+fn parse_dmy<'a>(strict: bool) -> impl Parser<BitsInput<'a>, (u8, u8, u8), MBusError> {
 	(
 		peek(bits::take::<_, u16, _, _>(16_usize))
 			.verify(|v| *v != 0xFFFF)
@@ -28,24 +31,30 @@ fn parse_dmy(input: &mut BitsInput<'_>) -> MBResult<(u8, u8, u8)> {
 		bits::take(4_usize).context(StrContext::Label("year (lower)")),
 		// month
 		bits::take(4_usize)
-			.verify(|v| {
-				matches!(
-					v,
-					// NOTE: This should be 1..=12 but the libmbus test data has
-					// invalid dates in the following files:
-					// ACW_Itron-BM-plus-m.hex
-					// itron_bm_+m.hex
-					// siemens_water.hex
-					// siemens_wfh21.hex
-					0..=12 | 15
-				)
+			.verify(move |v| {
+				// NOTE: This should be 1..=12 but the libmbus test data has
+				// invalid dates in the following files:
+				// ACW_Itron-BM-plus-m.hex
+				// itron_bm_+m.hex
+				// siemens_water.hex
+				// siemens_wfh21.hex
+				// so unless we're being strict month 15 is let through and
+				// the date is marked as not conforming to the standard
+				matches!(v, 0..=12) || (!strict && *v == NONCONFORMANT_MONTH)
 			})
 			.context(StrContext::Label("month")),
 	)
 		.map(|(_, yu, day, yl, month): ((), u8, u8, u8, u8)| (day, month, yu + (yl << 3)))
 		.verify(|(_, _, y)| matches!(y, 0..=99 | 127))
 		.context(StrContext::Label("year"))
-		.parse_next(input)
+}
+
+/// The inverse of `parse_dmy`
+fn encode_dmy(day: u8, month: u8, year: u8) -> [u8; 2] {
+	[
+		(year << 5) & MASK_YEAR_B1 | day & MASK_DAY,
+		(year << 1) & MASK_YEAR_B2 | month & MASK_MONTH,
+	]
 }
 
 const MASK_SECOND: u8 = 0b0011_1111;
@@ -57,19 +66,115 @@ const MASK_YEAR_B1: u8 = 0b1110_0000;
 const MASK_YEAR_B2: u8 = 0b1111_0000;
 const MASK_INVALID: u8 = 0b1000_0000;
 
-#[derive(Debug, PartialEq, Eq)]
+/// A day of the week, numbered from Monday = 1 as in ISO 8601
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Weekday {
+	Monday = 1,
+	Tuesday,
+	Wednesday,
+	Thursday,
+	Friday,
+	Saturday,
+	Sunday,
+}
+
+impl Weekday {
+	/// Converts a day number into a weekday, returning `None` for 0 ("not
+	/// specified") and anything above 7
+	pub fn from_number(day: u8) -> Option<Self> {
+		Some(match day {
+			1 => Self::Monday,
+			2 => Self::Tuesday,
+			3 => Self::Wednesday,
+			4 => Self::Thursday,
+			5 => Self::Friday,
+			6 => Self::Saturday,
+			7 => Self::Sunday,
+			_ => return None,
+		})
+	}
+}
+
+/// Works out the day of the week of a date in the Gregorian calendar, or
+/// `None` if there's no such date
+fn weekday_of(year: u16, month: u8, day: u8) -> Option<Weekday> {
+	let leap_year =
+		year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400));
+	let days_in_month = match month {
+		1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+		4 | 6 | 9 | 11 => 30,
+		2 if leap_year => 29,
+		2 => 28,
+		_ => return None,
+	};
+	if !(1..=days_in_month).contains(&day) {
+		return None;
+	}
+	// Sakamoto's method, which counts from Sunday = 0
+	const MONTH_OFFSETS: [u16; 12] = [0, 3, 2, 5, 0, 3, 5, 1, 4, 6, 2, 4];
+	let year = if month < 3 { year - 1 } else { year };
+	let day = (year + year / 4 - year / 100
+		+ year / 400
+		+ MONTH_OFFSETS[usize::from(month - 1)]
+		+ u16::from(day))
+		% 7;
+	Weekday::from_number(if day == 0 { 7 } else { day as u8 })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct TypeFDateTime {
 	pub minute: u8,
 	pub hour: u8,
 	pub day: u8,
 	pub month: u8,
 	pub year: u8,
+	/// Centuries since the base year of 1900, see [`TypeFDateTime::full_year`]
 	pub hundred_year: u8,
 	pub in_dst: bool,
 }
 
+const BASE_YEAR: u16 = 1900;
+
 impl TypeFDateTime {
+	/// Parses the date leniently, see [`TypeFDateTime::is_conformant`]
 	pub fn parse(input: &mut &Bytes) -> MBResult<Self> {
+		Self::parser(false).parse_next(input)
+	}
+
+	/// Parses the date, rejecting the non-standard month 15
+	pub fn parse_strict(input: &mut &Bytes) -> MBResult<Self> {
+		Self::parser(true).parse_next(input)
+	}
+
+	/// Returns false if the date was only accepted because it was parsed
+	/// leniently
+	pub fn is_conformant(&self) -> bool {
+		self.month != NONCONFORMANT_MONTH
+	}
+
+	/// The full four digit year, counting `hundred_year` centuries on from
+	/// 1900. Meters that don't set the hundred year have their years 00 to 80
+	/// treated as 2000 to 2080. Returns `None` if the date is for every year.
+	pub fn full_year(&self) -> Option<u16> {
+		full_year(self.hundred_year, self.year)
+	}
+
+	/// The inverse of [`TypeFDateTime::parse`]. Meters that don't set the
+	/// hundred year will have it set after a round trip, see
+	/// [`TypeFDateTime::full_year`].
+	pub fn encode(&self) -> [u8; 4] {
+		let [date_low, date_high] = encode_dmy(self.day, self.month, self.year);
+		[
+			self.minute & MASK_MINUTE,
+			u8::from(self.in_dst) << 7 | (self.hundred_year & 0b11) << 5 | self.hour & MASK_HOUR,
+			date_low,
+			date_high,
+		]
+	}
+
+	pub(crate) fn parser<'a>(strict: bool) -> impl Parser<&'a Bytes, Self, MBusError> {
 		bits::bits((
 			bits::bool
 				.verify(|v| !v)
@@ -87,7 +192,7 @@ impl TypeFDateTime {
 			bits::take(5_usize)
 				.verify(|v| matches!(v, 0..=23 | 31))
 				.context(StrContext::Label("hour")),
-			parse_dmy,
+			parse_dmy(strict),
 		))
 		.map(
 			|(
@@ -95,18 +200,12 @@ impl TypeFDateTime {
 				_,
 				minute,
 				in_dst,
-				mut hundred_year,
+				hundred_year,
 				hour,
 				(day, month, year),
 				//
 			)| {
-				// EN 13757-3:2018 Annex A table A.5 footnote a:
-				// "For compatibility with old meters with a circular two digit
-				// date it is recommended to consider in any master software the
-				// years “00” to “80” as the years 2000 to 2080.""
-				if hundred_year == 0 && year <= 80 {
-					hundred_year = 1;
-				}
+				let hundred_year = normalise_hundred_year(hundred_year, year);
 				TypeFDateTime {
 					minute,
 					in_dst,
@@ -118,7 +217,57 @@ impl TypeFDateTime {
 				}
 			},
 		)
-		.parse_next(input)
+	}
+}
+
+impl std::fmt::Display for TypeFDateTime {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(
+			f,
+			"{}-{:02}-{:02} {:02}:{:02}",
+			YearDisplay(self.full_year()),
+			self.month,
+			self.day,
+			self.hour,
+			self.minute
+		)
+	}
+}
+
+/// The year value that means the date applies to every year
+pub(crate) const EVERY_YEAR: u8 = 127;
+
+/// Works out the full year from the two digit year and the number of
+/// centuries since 1900, or `None` for [`EVERY_YEAR`]
+fn full_year(hundred_year: u8, year: u8) -> Option<u16> {
+	if year == EVERY_YEAR {
+		return None;
+	}
+	let hundred_year = normalise_hundred_year(hundred_year, year);
+	Some(BASE_YEAR + u16::from(hundred_year) * 100 + u16::from(year))
+}
+
+/// Shows a full year with four digits, or `****` for every year
+struct YearDisplay(Option<u16>);
+
+impl std::fmt::Display for YearDisplay {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self.0 {
+			Some(year) => write!(f, "{year:04}"),
+			None => write!(f, "****"),
+		}
+	}
+}
+
+fn normalise_hundred_year(hundred_year: u8, year: u8) -> u8 {
+	// EN 13757-3:2018 Annex A table A.5 footnote a:
+	// "For compatibility with old meters with a circular two digit
+	// date it is recommended to consider in any master software the
+	// years “00” to “80” as the years 2000 to 2080.""
+	if hundred_year == 0 && year <= 80 {
+		1
+	} else {
+		hundred_year
 	}
 }
 
@@ -187,9 +336,76 @@ mod test_type_f_date_time {
 		assert_eq!(err.kind(), ErrorKind::Verify);
 		assert_eq!(err.context().next(), Some(&StrContext::Label(context)));
 	}
+
+	#[test]
+	fn test_month_15() {
+		let input = [0x00, 0x00, 0x01, 0x0F];
+
+		let result = TypeFDateTime::parse.parse(Bytes::new(&input)).unwrap();
+		assert!(!result.is_conformant());
+
+		let result = TypeFDateTime::parse_strict
+			.parse(Bytes::new(&input))
+			.unwrap_err();
+		assert_eq!(
+			result.inner().context().next(),
+			Some(&StrContext::Label("month"))
+		);
+	}
+
+	#[rstest]
+	#[case::old_meter_1981(0, 81, 1981)]
+	#[case::old_meter_1999(0, 99, 1999)]
+	#[case::old_meter_2000(0, 0, 2000)]
+	#[case::old_meter_2080(0, 80, 2080)]
+	#[case::y2000(1, 0, 2000)]
+	#[case::y2099(1, 99, 2099)]
+	#[case::y2100(2, 0, 2100)]
+	#[case::y2299(3, 99, 2299)]
+	fn test_full_year(#[case] hundred_year: u8, #[case] year: u8, #[case] expected: u16) {
+		let date = TypeFDateTime {
+			minute: 0,
+			hour: 0,
+			day: 1,
+			month: 1,
+			year,
+			hundred_year,
+			in_dst: false,
+		};
+
+		assert_eq!(date.full_year(), Some(expected));
+	}
+
+	#[rstest]
+	fn test_every_year(#[values(0, 1, 2, 3)] hundred_year: u8) {
+		let date = TypeFDateTime {
+			minute: 0,
+			hour: 0,
+			day: 24,
+			month: 12,
+			year: 127,
+			hundred_year,
+			in_dst: false,
+		};
+
+		assert_eq!(date.full_year(), None);
+		assert_eq!(date.to_string(), "****-12-24 00:00");
+	}
+
+	#[rstest]
+	#[case::ACW_Itron_BM_plus_m__0([0x0B, 0x0B, 0xCD, 0x13], 2014)]
+	#[case::amt_calec_mb([0x10, 0x09, 0x05, 0xC5], 1996)]
+	#[case::no_hundred_year([0x00, 0x00, 0x01, 0x01], 2000)]
+	#[allow(non_snake_case)]
+	fn test_parsed_full_year(#[case] input: [u8; 4], #[case] expected: u16) {
+		let result = TypeFDateTime::parse.parse(Bytes::new(&input)).unwrap();
+
+		assert_eq!(result.full_year(), Some(expected));
+	}
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct TypeGDate {
 	pub day: u8,
 	pub month: u8,
@@ -197,10 +413,48 @@ pub struct TypeGDate {
 }
 
 impl TypeGDate {
+	/// Parses the date leniently, see [`TypeGDate::is_conformant`]
 	pub fn parse(input: &mut &Bytes) -> MBResult<Self> {
-		bits::bits(parse_dmy)
-			.map(|(day, month, year)| TypeGDate { day, month, year })
-			.parse_next(input)
+		Self::parser(false).parse_next(input)
+	}
+
+	/// Parses the date, rejecting the non-standard month 15
+	pub fn parse_strict(input: &mut &Bytes) -> MBResult<Self> {
+		Self::parser(true).parse_next(input)
+	}
+
+	/// Returns false if the date was only accepted because it was parsed
+	/// leniently
+	pub fn is_conformant(&self) -> bool {
+		self.month != NONCONFORMANT_MONTH
+	}
+
+	/// The full four digit year, with 00 to 80 treated as 2000 to 2080 and
+	/// anything after that as the 1900s. Returns `None` if the date is for
+	/// every year.
+	pub fn full_year(&self) -> Option<u16> {
+		full_year(0, self.year)
+	}
+
+	/// The inverse of [`TypeGDate::parse`]
+	pub fn encode(&self) -> [u8; 2] {
+		encode_dmy(self.day, self.month, self.year)
+	}
+
+	pub(crate) fn parser<'a>(strict: bool) -> impl Parser<&'a Bytes, Self, MBusError> {
+		bits::bits(parse_dmy(strict)).map(|(day, month, year)| TypeGDate { day, month, year })
+	}
+}
+
+impl std::fmt::Display for TypeGDate {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(
+			f,
+			"{}-{:02}-{:02}",
+			YearDisplay(self.full_year()),
+			self.month,
+			self.day
+		)
 	}
 }
 
@@ -253,6 +507,58 @@ mod test_type_g_date {
 		);
 	}
 
+	#[test]
+	fn test_month_15_lenient() {
+		let input = Bytes::new(&[0x01, 0x0F]);
+
+		let result = TypeGDate::parse.parse(input).unwrap();
+
+		assert_eq!(result.month, 15);
+		assert!(!result.is_conformant());
+	}
+
+	#[test]
+	fn test_month_15_strict() {
+		let input = Bytes::new(&[0x01, 0x0F]);
+
+		let result = TypeGDate::parse_strict.parse(input).unwrap_err();
+
+		let err = result.inner();
+		assert_eq!(err.kind(), ErrorKind::Verify);
+		assert_eq!(err.context().next(), Some(&StrContext::Label("month")));
+	}
+
+	#[test]
+	fn test_strict_conformant() {
+		let input = Bytes::new(&[0x8C, 0x11]);
+
+		let result = TypeGDate::parse_strict.parse(input).unwrap();
+
+		assert!(result.is_conformant());
+	}
+
+	#[rstest]
+	#[case::old_meter_1999([0x7F, 0xCC], Some(1999))]
+	#[case::y2000([0x1F, 0x0C], Some(2000))]
+	#[case::y2080([0x1F, 0xAC], Some(2080))]
+	#[case::every_year([0xFF, 0xFC], None)]
+	fn test_full_year(#[case] input: [u8; 2], #[case] expected: Option<u16>) {
+		let result = TypeGDate::parse.parse(Bytes::new(&input)).unwrap();
+
+		assert_eq!(result.full_year(), expected);
+	}
+
+	#[test]
+	fn test_every_year_display() {
+		let date = TypeGDate {
+			day: 24,
+			month: 12,
+			year: 127,
+		};
+
+		assert_eq!(date.to_string(), "****-12-24");
+	}
+
 	#[rstest]
 	#[case::month_13([0b111_00001, 0b0000_1101], "month")]
 	#[case::month_14([0b111_00001, 0b0000_1110], "month")]
@@ -269,7 +575,8 @@ mod test_type_g_date {
 	}
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct TypeIDateTime {
 	pub second: u8,
 	pub minute: u8,
@@ -285,7 +592,56 @@ pub struct TypeIDateTime {
 }
 
 impl TypeIDateTime {
+	/// Parses the date leniently, see [`TypeIDateTime::is_conformant`]
 	pub fn parse(input: &mut &Bytes) -> MBResult<Self> {
+		Self::parser(false).parse_next(input)
+	}
+
+	/// Parses the date, rejecting the non-standard month 15
+	pub fn parse_strict(input: &mut &Bytes) -> MBResult<Self> {
+		Self::parser(true).parse_next(input)
+	}
+
+	/// Returns false if the date was only accepted because it was parsed
+	/// leniently
+	pub fn is_conformant(&self) -> bool {
+		self.month != NONCONFORMANT_MONTH
+	}
+
+	/// The day of the week, if the meter specified it
+	pub fn weekday(&self) -> Option<Weekday> {
+		Weekday::from_number(self.day_of_week)
+	}
+
+	/// The full four digit year, see [`TypeGDate::full_year`]
+	pub fn full_year(&self) -> Option<u16> {
+		full_year(0, self.year)
+	}
+
+	/// Whether the day of the week the meter sent is the right one for the
+	/// date, which can catch frames that have been corrupted. Returns `None`
+	/// if the meter didn't specify the day of the week, or if the date isn't
+	/// a single real day (such as day 0, month 15 or the "every year" 127).
+	pub fn day_of_week_consistent(&self) -> Option<bool> {
+		let sent = self.weekday()?;
+		let actual = weekday_of(self.full_year()?, self.month, self.day)?;
+		Some(sent == actual)
+	}
+
+	/// The inverse of [`TypeIDateTime::parse`]
+	pub fn encode(&self) -> [u8; 6] {
+		let [date_low, date_high] = encode_dmy(self.day, self.month, self.year);
+		[
+			u8::from(self.leap_year) << 7 | u8::from(self.in_dst) << 6 | self.second & MASK_SECOND,
+			u8::from(self.dst_offset >= 0) << 6 | self.minute & MASK_MINUTE,
+			(self.day_of_week & 0b111) << 5 | self.hour & MASK_HOUR,
+			date_low,
+			date_high,
+			(self.dst_offset.unsigned_abs() & 0b11) << 6 | self.week & 0b0011_1111,
+		]
+	}
+
+	pub(crate) fn parser<'a>(strict: bool) -> impl Parser<&'a Bytes, Self, MBusError> {
 		bits::bits((
 			bits::bool.context(StrContext::Label("leap year")),
 			bits::bool.context(StrContext::Label("in dst")),
@@ -304,7 +660,7 @@ impl TypeIDateTime {
 			bits::take(5_usize)
 				.verify(|v| matches!(v, 0..=23 | 31))
 				.context(StrContext::Label("hour")),
-			parse_dmy,
+			parse_dmy(strict),
 			bits::take(2_usize)
 				.try_map(|v: u8| v.try_into())
 				.context(StrContext::Label("dst offset")),
@@ -339,11 +695,26 @@ impl TypeIDateTime {
 				dst_offset: if dst_plus { dst_offset } else { -dst_offset },
 			},
 		)
-		.parse_next(input)
 	}
 }
 
-#[derive(Debug, PartialEq, Eq)]
+impl std::fmt::Display for TypeIDateTime {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(
+			f,
+			"{}-{:02}-{:02} {:02}:{:02}:{:02}",
+			YearDisplay(self.full_year()),
+			self.month,
+			self.day,
+			self.hour,
+			self.minute,
+			self.second
+		)
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct TypeJTime {
 	pub second: u8,
 	pub minute: u8,
@@ -351,6 +722,15 @@ pub struct TypeJTime {
 }
 
 impl TypeJTime {
+	/// The inverse of [`TypeJTime::parse`]
+	pub fn encode(&self) -> [u8; 3] {
+		[
+			self.second & MASK_SECOND,
+			self.minute & MASK_MINUTE,
+			self.hour & MASK_HOUR,
+		]
+	}
+
 	pub fn parse(input: &mut &Bytes) -> MBResult<Self> {
 		bits::bits::<_, _, MBusError, _, _>((
 			peek(bits::take::<_, u32, _, _>(24_usize))
@@ -388,6 +768,60 @@ impl TypeJTime {
 	}
 }
 
+impl std::fmt::Display for TypeJTime {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{:02}:{:02}:{:02}", self.hour, self.minute, self.second)
+	}
+}
+
+#[cfg(test)]
+mod test_type_i_day_of_week {
+	use rstest::rstest;
+
+	use super::TypeIDateTime;
+
+	fn date(day: u8, month: u8, year: u8, day_of_week: u8) -> TypeIDateTime {
+		TypeIDateTime {
+			second: 0,
+			minute: 0,
+			hour: 12,
+			day,
+			month,
+			year,
+			day_of_week,
+			week: 0,
+			in_dst: false,
+			leap_year: false,
+			dst_offset: 0,
+		}
+	}
+
+	#[rstest]
+	#[case::friday(date(15, 3, 24, 5), Some(true))]
+	#[case::wrong_day(date(15, 3, 24, 1), Some(false))]
+	#[case::leap_day(date(29, 2, 0, 2), Some(true))]
+	#[case::new_year(date(1, 1, 23, 7), Some(true))]
+	#[case::end_of_year(date(31, 12, 99, 5), Some(true))]
+	#[case::unspecified_weekday(date(15, 3, 24, 0), None)]
+	#[case::unspecified_day(date(0, 3, 24, 5), None)]
+	#[case::unspecified_month(date(15, 0, 24, 5), None)]
+	#[case::nonconformant_month(date(15, 15, 24, 5), None)]
+	#[case::every_year(date(15, 3, 127, 5), None)]
+	#[case::no_such_day(date(30, 2, 24, 5), None)]
+	#[case::not_a_leap_year(date(29, 2, 23, 3), None)]
+	fn test_consistency(#[case] date: TypeIDateTime, #[case] expected: Option<bool>) {
+		assert_eq!(date.day_of_week_consistent(), expected);
+	}
+
+	#[rstest]
+	#[case::old_meter_1981(date(1, 1, 81, 0), Some(1981))]
+	#[case::y2080(date(1, 1, 80, 0), Some(2080))]
+	#[case::every_year(date(1, 1, 127, 0), None)]
+	fn test_full_year(#[case] date: TypeIDateTime, #[case] expected: Option<u16>) {
+		assert_eq!(date.full_year(), expected);
+	}
+}
+
 #[cfg(test)]
 mod test_type_j_time {
 	use rstest::rstest;
@@ -450,7 +884,8 @@ mod test_type_j_time {
 	}
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct TypeKDST {
 	pub starts_hour: u8,
 	pub starts_day: u8,
@@ -523,3 +958,80 @@ impl TypeKDST {
 		.parse_next(input)
 	}
 }
+
+impl std::fmt::Display for TypeKDST {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(
+			f,
+			"{:02}-{:02} {:02}:00 to {:02}-{:02} ({:+}h)",
+			self.starts_month,
+			self.starts_day,
+			self.starts_hour,
+			self.ends_month,
+			self.ends_day,
+			self.dst_deviation
+		)?;
+		if !self.enable {
+			write!(f, " disabled")?;
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod test_encode_dates {
+	use rstest::rstest;
+	use winnow::prelude::*;
+	use winnow::Bytes;
+
+	use super::{TypeFDateTime, TypeGDate, TypeIDateTime, TypeJTime};
+
+	#[rstest]
+	#[case::amt_calec_mb([0x10, 0x09, 0x05, 0xC5])]
+	#[case::kamstrup_multical_601([0x1A, 0x2F, 0x65, 0x11])]
+	#[case::in_dst([0x00, 0xA0, 0x01, 0x01])]
+	fn test_type_f(#[case] input: [u8; 4]) {
+		let date = TypeFDateTime::parse.parse(Bytes::new(&input)).unwrap();
+
+		assert_eq!(date.encode(), input);
+	}
+
+	#[test]
+	fn test_type_f_sets_hundred_year() {
+		// From ACW_Itron-BM-plus-m.hex
+		let date = TypeFDateTime::parse
+			.parse(Bytes::new(&[0x0B, 0x0B, 0xCD, 0x13]))
+			.unwrap();
+
+		assert_eq!(date.encode(), [0x0B, 0x2B, 0xCD, 0x13]);
+	}
+
+	#[rstest]
+	#[case::allmess_cf50([0x8C, 0x11])]
+	#[case::EFE_Engelmann_WaterStar([0xBF, 0x1C])]
+	#[case::rel_padpuls2([0x1F, 0x0C])]
+	#[case::month_15([0x01, 0x0F])]
+	#[allow(non_snake_case)]
+	fn test_type_g(#[case] input: [u8; 2]) {
+		let date = TypeGDate::parse.parse(Bytes::new(&input)).unwrap();
+
+		assert_eq!(date.encode(), input);
+	}
+
+	#[rstest]
+	#[case::basic([0x3B, 0x42, 0x2C, 0x8C, 0x11, 0x4A])]
+	#[case::dst([0xC0, 0x40, 0x00, 0x01, 0x01, 0x81])]
+	fn test_type_i(#[case] input: [u8; 6]) {
+		let date = TypeIDateTime::parse.parse(Bytes::new(&input)).unwrap();
+
+		assert_eq!(date.encode(), input);
+	}
+
+	#[test]
+	fn test_type_j() {
+		let input = [0x3B, 0x1E, 0x17];
+		let time = TypeJTime::parse.parse(Bytes::new(&input)).unwrap();
+
+		assert_eq!(time.encode(), input);
+	}
+}
@@ -0,0 +1,46 @@
+// Copyright 2024 Lexi Robinson
+// Licensed under the EUPL-1.2
+use winnow::binary;
+use winnow::error::StrContext;
+use winnow::prelude::*;
+use winnow::Bytes;
+
+use crate::parse::error::MBusError;
+use crate::parse::options::ParseOptions;
+use crate::parse::transport_layer::header::DeviceType;
+use crate::parse::transport_layer::manufacturer::{parse_manufacturer, ManufacturerCode};
+
+use super::number::parse_bcd;
+
+/// EN 13757-3:2018 Table 10 footnote: When sent as a 64 bit integer the
+/// enhanced identification contains the full identity of the device in the
+/// same layout as the long TPL header
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct EnhancedIdentification {
+	pub identifier: u32,
+	pub manufacturer: ManufacturerCode,
+	pub version: u8,
+	pub device_type: DeviceType,
+}
+
+impl EnhancedIdentification {
+	pub(crate) fn parser<'a>(options: ParseOptions) -> impl Parser<&'a Bytes, Self, MBusError> {
+		(
+			parse_bcd(4)
+				.try_map(u32::try_from)
+				.context(StrContext::Label("identifier")),
+			parse_manufacturer(options.strict_manufacturers),
+			binary::u8.context(StrContext::Label("version")),
+			DeviceType::parse.context(StrContext::Label("device type")),
+		)
+			.map(
+				|(identifier, manufacturer, version, device_type)| EnhancedIdentification {
+					identifier,
+					manufacturer,
+					version,
+					device_type,
+				},
+			)
+	}
+}
@@ -63,6 +63,48 @@ pub fn parse_bcd<'a>(bytes: usize) -> impl Parser<&'a Bytes, i64, MBusError> {
 	binary::bits::bits(parser).context(StrContext::Label("signed BCD number"))
 }
 
+/// Parses a BCD number that has been explicitly marked as positive, such as
+/// the LVAR values 0xC0 to 0xC9. A sign nibble makes no sense here so values
+/// that have one are rejected.
+pub fn parse_positive_bcd<'a>(bytes: usize) -> impl Parser<&'a Bytes, i64, MBusError> {
+	parse_bcd(bytes)
+		.verify(|v| *v >= 0)
+		.context(StrContext::Label("positive BCD number"))
+}
+
+/// Parses a BCD number that has been explicitly marked as negative, such as
+/// the LVAR values 0xD0 to 0xD9. The digits are the magnitude of the number,
+/// so a redundant sign nibble doesn't flip it back to being positive.
+pub fn parse_negative_bcd<'a>(bytes: usize) -> impl Parser<&'a Bytes, i64, MBusError> {
+	parse_bcd(bytes)
+		.map(|v| -v.abs())
+		.context(StrContext::Label("negative BCD number"))
+}
+
+/// The inverse of [`parse_bcd`], returning `None` if the value doesn't fit.
+/// Negative values use the top nibble as a sign, so have one less digit.
+pub fn encode_bcd(value: i64, bytes: usize) -> Option<Vec<u8>> {
+	let mut digits = value.unsigned_abs();
+	let mut result = Vec::with_capacity(bytes);
+	for _ in 0..bytes {
+		let low = digits % 10;
+		let high = (digits / 10) % 10;
+		digits /= 100;
+		result.push((high << 4 | low) as u8);
+	}
+	if digits != 0 {
+		return None;
+	}
+	if value < 0 {
+		let last = result.last_mut()?;
+		if *last >> 4 != 0 {
+			return None;
+		}
+		*last |= 0xF0;
+	}
+	Some(result)
+}
+
 #[cfg(test)]
 mod test_parse_bcd {
 	use winnow::error::ErrorKind;
@@ -225,6 +267,30 @@ pub fn parse_invalid_bcd<'a>(bytes: usize) -> impl Parser<&'a Bytes, String, MBu
 	binary::bits::bits(parser).context(StrContext::Label("signed BCD number"))
 }
 
+/// The inverse of [`parse_invalid_bcd`], returning `None` if the string isn't
+/// the right length or contains anything other than hex digits and a leading
+/// `-`
+pub fn encode_invalid_bcd(value: &str, bytes: usize) -> Option<Vec<u8>> {
+	let nibbles = value
+		.chars()
+		.enumerate()
+		.map(|(i, c)| match c {
+			'-' if i == 0 => Some(0x0F),
+			c => c.to_digit(16).map(|n| n as u8),
+		})
+		.collect::<Option<Vec<_>>>()?;
+	if nibbles.len() != bytes * 2 {
+		return None;
+	}
+	Some(
+		nibbles
+			.chunks(2)
+			.rev()
+			.map(|pair| pair[0] << 4 | pair[1])
+			.collect(),
+	)
+}
+
 #[cfg(test)]
 mod test_parse_invalid_bcd {
 	use winnow::error::ErrorKind;
@@ -352,9 +418,23 @@ pub fn parse_binary_signed<'a>(bytes: usize) -> impl Parser<&'a Bytes, i64, MBus
 	}
 }
 
+/// The inverse of [`parse_binary_signed`], returning `None` if the value
+/// doesn't fit
+pub fn encode_binary_signed(value: i64, bytes: usize) -> Option<Vec<u8>> {
+	let bits = bytes * 8;
+	if bytes == 0
+		|| bytes > 8
+		|| (bits < 64 && !(-(1 << (bits - 1))..(1 << (bits - 1))).contains(&value))
+	{
+		return None;
+	}
+	Some(value.to_le_bytes()[..bytes].to_vec())
+}
+
 #[cfg(test)]
 mod test_parse_binary_signed {
-	use super::parse_binary_signed;
+	use super::{parse_binary_signed, parse_binary_unsigned};
+	use rstest::rstest;
 	use winnow::error::ErrorKind;
 	use winnow::{Bytes, Parser};
 
@@ -451,6 +531,54 @@ mod test_parse_binary_signed {
 		}
 	}
 
+	/// Every power of two (and the values either side of it) that fits in a
+	/// signed number `bytes` wide, both positive and negative
+	fn sweep(bytes: usize) -> Vec<i64> {
+		let bits = bytes as u32 * 8;
+		let min = -(1_i64 << (bits - 1));
+		let max = (1_i64 << (bits - 1)) - 1;
+		let mut ret = vec![min, min + 1, -1, 0, 1, max - 1, max];
+		for shift in 0..bits - 1 {
+			let value = 1_i64 << shift;
+			ret.extend([value - 1, value, value + 1]);
+			ret.extend([-value - 1, -value, -value + 1]);
+		}
+		ret
+	}
+
+	#[rstest]
+	#[case::i24(3)]
+	#[case::i40(5)]
+	#[case::i48(6)]
+	#[case::i56(7)]
+	fn test_odd_widths(#[case] bytes: usize) {
+		for i in sweep(bytes) {
+			let raw_bytes = i.to_le_bytes();
+			let data = &raw_bytes[..bytes];
+			let input = Bytes::new(data);
+			let result = parse_binary_signed(bytes).parse(input).unwrap();
+			assert_eq!(
+				result, i,
+				"Should be able to parse {i} from bytes {data:x?}",
+			);
+		}
+	}
+
+	#[rstest]
+	#[case::i24(3)]
+	#[case::i40(5)]
+	#[case::i48(6)]
+	#[case::i56(7)]
+	fn test_odd_widths_unsigned_agrees(#[case] bytes: usize) {
+		let mask = (1_u64 << (bytes * 8)) - 1;
+		for i in sweep(bytes) {
+			let raw_bytes = i.to_le_bytes();
+			let input = Bytes::new(&raw_bytes[..bytes]);
+			let result = parse_binary_unsigned(bytes).parse(input).unwrap();
+			assert_eq!(result, i as u64 & mask);
+		}
+	}
+
 	#[test]
 	fn test_parse_zero() {
 		let input = Bytes::new(&[]);
@@ -514,6 +642,36 @@ pub fn parse_binary_unsigned<'a>(bytes: usize) -> impl Parser<&'a Bytes, u64, MB
 	}
 }
 
+/// The inverse of [`parse_binary_unsigned`], returning `None` if the value
+/// doesn't fit
+pub fn encode_binary_unsigned(value: u64, bytes: usize) -> Option<Vec<u8>> {
+	let bits = bytes * 8;
+	if bytes == 0 || bytes > 8 || (bits < 64 && value >> bits != 0) {
+		return None;
+	}
+	Some(value.to_le_bytes()[..bytes].to_vec())
+}
+
+/// Decodes a little endian signed number of any width, such as the larger
+/// LVAR numbers that are kept as raw bytes. Returns `None` if the value
+/// doesn't fit in an `i128`, which is only possible with more than 16 bytes.
+pub fn decode_le_signed(bytes: &[u8]) -> Option<i128> {
+	let negative = bytes.last().is_some_and(|byte| byte & 0x80 != 0);
+	bytes
+		.iter()
+		.rev()
+		.try_fold(if negative { -1_i128 } else { 0 }, |value, byte| {
+			value.checked_mul(0x100)?.checked_add(i128::from(*byte))
+		})
+}
+
+/// The unsigned version of [`decode_le_signed`]
+pub fn decode_le_unsigned(bytes: &[u8]) -> Option<u128> {
+	bytes.iter().rev().try_fold(0_u128, |value, byte| {
+		value.checked_mul(0x100)?.checked_add(u128::from(*byte))
+	})
+}
+
 #[cfg(test)]
 mod test_parse_binary_unsigned {
 	use super::parse_binary_unsigned;
@@ -639,3 +797,208 @@ mod test_parse_real {
 		}
 	}
 }
+
+pub fn parse_double(input: &mut &Bytes) -> MBResult<f64> {
+	binary::le_f64.parse_next(input)
+}
+
+#[cfg(test)]
+mod test_parse_double {
+	use super::parse_double;
+	use winnow::{Bytes, Parser};
+
+	#[test]
+	fn test_works() {
+		for i in [
+			f64::NEG_INFINITY,
+			f64::MIN,
+			0.0,
+			1.5,
+			f64::MAX,
+			f64::INFINITY,
+		] {
+			let bytes = i.to_le_bytes();
+			let input = Bytes::new(&bytes);
+			let result = parse_double.parse(input).unwrap();
+			assert_eq!(result, i);
+		}
+	}
+}
+
+#[cfg(test)]
+mod test_parse_sign_marked_bcd {
+	use rstest::rstest;
+	use winnow::{Bytes, Parser};
+
+	use super::{parse_bcd, parse_negative_bcd, parse_positive_bcd};
+
+	#[rstest]
+	#[case::empty(&[], 0)]
+	#[case::zero(&[0x00], 0)]
+	#[case::multi_byte(&[0x34, 0x12], 1234)]
+	fn test_positive(#[case] input: &[u8], #[case] expected: i64) {
+		let result = parse_positive_bcd(input.len())
+			.parse(Bytes::new(input))
+			.unwrap();
+
+		assert_eq!(result, expected);
+		assert_eq!(
+			result,
+			parse_bcd(input.len()).parse(Bytes::new(input)).unwrap()
+		);
+	}
+
+	#[test]
+	fn test_positive_rejects_sign() {
+		let input = Bytes::new(&[0x34, 0xF2]);
+
+		parse_positive_bcd(2).parse(input).unwrap_err();
+	}
+
+	#[rstest]
+	#[case::empty(&[], 0)]
+	#[case::zero(&[0x00], 0)]
+	#[case::multi_byte(&[0x34, 0x12], -1234)]
+	#[case::redundant_sign(&[0x34, 0xF2], -234)]
+	fn test_negative(#[case] input: &[u8], #[case] expected: i64) {
+		let result = parse_negative_bcd(input.len())
+			.parse(Bytes::new(input))
+			.unwrap();
+
+		assert_eq!(result, expected);
+		assert_eq!(
+			result,
+			-parse_bcd(input.len())
+				.parse(Bytes::new(input))
+				.unwrap()
+				.abs()
+		);
+	}
+}
+
+#[cfg(test)]
+mod test_encode_numbers {
+	use rstest::rstest;
+	use winnow::prelude::*;
+	use winnow::Bytes;
+
+	use super::{
+		encode_bcd, encode_binary_signed, encode_binary_unsigned, encode_invalid_bcd, parse_bcd,
+		parse_binary_signed, parse_binary_unsigned, parse_invalid_bcd,
+	};
+
+	#[rstest]
+	#[case::zero(0, 1, Some(vec![0x00]))]
+	#[case::positive(1234, 2, Some(vec![0x34, 0x12]))]
+	#[case::padded(1234, 4, Some(vec![0x34, 0x12, 0x00, 0x00]))]
+	#[case::negative(-123, 2, Some(vec![0x23, 0xF1]))]
+	#[case::too_big(12345, 2, None)]
+	#[case::too_small(-1234, 2, None)]
+	fn test_encode_bcd(
+		#[case] value: i64,
+		#[case] bytes: usize,
+		#[case] expected: Option<Vec<u8>>,
+	) {
+		let result = encode_bcd(value, bytes);
+
+		assert_eq!(result, expected);
+		if let Some(encoded) = result {
+			assert_eq!(parse_bcd(bytes).parse(Bytes::new(&encoded)), Ok(value));
+		}
+	}
+
+	#[rstest]
+	#[case::hex("12AB", 2, Some(vec![0xAB, 0x12]))]
+	#[case::negative("-1AB", 2, Some(vec![0xAB, 0xF1]))]
+	#[case::wrong_length("12AB", 1, None)]
+	#[case::not_hex("12XB", 2, None)]
+	fn test_encode_invalid_bcd(
+		#[case] value: &str,
+		#[case] bytes: usize,
+		#[case] expected: Option<Vec<u8>>,
+	) {
+		let result = encode_invalid_bcd(value, bytes);
+
+		assert_eq!(result, expected);
+		if let Some(encoded) = result {
+			let parsed = parse_invalid_bcd(bytes).parse(Bytes::new(&encoded));
+			assert_eq!(parsed.as_deref(), Ok(value));
+		}
+	}
+
+	#[rstest]
+	fn test_encode_binary_signed(
+		#[values(1, 2, 3, 4, 6, 8)] bytes: usize,
+		#[values(0, 1, -1, 127, -128, 32767, -32768, i64::MAX, i64::MIN)] value: i64,
+	) {
+		let limit = 1_i128 << (bytes * 8 - 1);
+		let fits = (-limit..limit).contains(&i128::from(value));
+
+		let result = encode_binary_signed(value, bytes);
+
+		assert_eq!(result.is_some(), fits, "{value} in {bytes} bytes");
+		if let Some(encoded) = result {
+			assert_eq!(
+				parse_binary_signed(bytes).parse(Bytes::new(&encoded)),
+				Ok(value)
+			);
+		}
+	}
+
+	#[rstest]
+	fn test_encode_binary_unsigned(
+		#[values(1, 2, 3, 4, 6, 8)] bytes: usize,
+		#[values(0, 1, 255, 256, 65535, 65536, u64::MAX)] value: u64,
+	) {
+		let fits = u128::from(value) < 1 << (bytes * 8);
+
+		let result = encode_binary_unsigned(value, bytes);
+
+		assert_eq!(result.is_some(), fits, "{value} in {bytes} bytes");
+		if let Some(encoded) = result {
+			assert_eq!(
+				parse_binary_unsigned(bytes).parse(Bytes::new(&encoded)),
+				Ok(value)
+			);
+		}
+	}
+}
+
+#[cfg(test)]
+mod test_decode_le {
+	use rstest::rstest;
+
+	use super::{decode_le_signed, decode_le_unsigned};
+
+	#[rstest]
+	#[case::empty(&[], Some(0), Some(0))]
+	#[case::one(&[0x01], Some(1), Some(1))]
+	#[case::minus_one(&[0xFF], Some(-1), Some(0xFF))]
+	#[case::byte_order(&[0x34, 0x12], Some(0x1234), Some(0x1234))]
+	#[case::nine_bytes(
+		&[0, 0, 0, 0, 0, 0, 0, 0, 0x01],
+		Some(1 << 64),
+		Some(1 << 64),
+	)]
+	#[case::negative_nine_bytes(
+		&[0, 0, 0, 0, 0, 0, 0, 0, 0xFF],
+		Some(-1 << 64),
+		Some(0xFF << 64),
+	)]
+	#[case::sixteen_bytes(&[0xFF; 16], Some(-1), Some(u128::MAX))]
+	// Wider numbers still fit as long as the extra bytes are just the sign
+	#[case::sign_extended(&[0xFF; 17], Some(-1), None)]
+	#[case::too_wide(
+		&[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x01],
+		None,
+		None,
+	)]
+	fn test_decode(
+		#[case] bytes: &[u8],
+		#[case] signed: Option<i128>,
+		#[case] unsigned: Option<u128>,
+	) {
+		assert_eq!(decode_le_signed(bytes), signed, "signed");
+		assert_eq!(decode_le_unsigned(bytes), unsigned, "unsigned");
+	}
+}
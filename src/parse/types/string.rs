@@ -10,15 +10,22 @@ use winnow::stream::Bytes;
 
 use crate::parse::error::{MBResult, MBusError};
 
-pub fn parse_length_prefix_ascii(input: &mut &Bytes) -> MBResult<String> {
+/// Parses a string with a one byte length prefix, as sent by the plain text
+/// VIF. This is decoded the same way as [`parse_latin1`] since meters
+/// sometimes put characters like `°` in their units.
+pub fn parse_length_prefix_latin1(input: &mut &Bytes) -> MBResult<String> {
 	binary::length_take(binary::u8)
-		.try_map(convert_ascii_string)
-		.context(StrContext::Label("length prefixed ASCII string"))
+		.map(decode_latin1)
+		.context(StrContext::Label("length prefixed latin-1 string"))
 		.parse_next(input)
 }
 
-fn convert_ascii_string(data: &[u8]) -> core::result::Result<String, std::str::Utf8Error> {
-	Ok(std::str::from_utf8(data)?.chars().rev().collect())
+/// Strings are sent with the last character first, the same as every other
+/// multi-byte value, so the bytes have to be reversed after decoding.
+/// Anything that isn't valid Latin-1 is decoded as Windows-1252, which is a
+/// superset that never fails.
+fn decode_latin1(data: &[u8]) -> String {
+	WINDOWS_1252.decode(data).0.chars().rev().collect()
 }
 
 pub fn parse_latin1<'a>(num_bytes: usize) -> impl Parser<&'a Bytes, String, MBusError> {
@@ -29,8 +36,16 @@ pub fn parse_latin1<'a>(num_bytes: usize) -> impl Parser<&'a Bytes, String, MBus
 			repeat::<_, _, (), _, _>(num_bytes, binary::u8)
 				.context(StrContext::Label("latin-1 string"))
 				.recognize()
-				.map(|data| WINDOWS_1252.decode(data).0.chars().rev().collect())
+				.map(decode_latin1)
 				.parse_next(input)
 		}
 	}
 }
+
+/// The inverse of [`parse_latin1`], returning `None` if the string contains
+/// characters that can't be encoded
+pub fn encode_latin1(value: &str) -> Option<Vec<u8>> {
+	let reversed: String = value.chars().rev().collect();
+	let (data, _, had_errors) = WINDOWS_1252.encode(&reversed);
+	(!had_errors).then(|| data.into_owned())
+}
@@ -0,0 +1,191 @@
+// Copyright 2024 Lexi Robinson
+// Licensed under the EUPL-1.2
+use std::ops::Range;
+
+use super::application_layer::data::DataType;
+use super::application_layer::frame::DeviceResponse;
+use super::application_layer::record::Record;
+use super::link_layer::Packet;
+use super::spans::{RecordSpans, Spans};
+use super::transport_layer::MBusMessage;
+
+/// Something wrong with a packet that was let through because it was parsed
+/// leniently, and which the matching strict option in
+/// [`crate::parse::options::ParseOptions`] would have rejected. See
+/// [`Packet::parse_with_warnings`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ParseWarning {
+	/// The bytes the warning is about, as offsets from the start of the
+	/// packet. This is `None` if the bytes can't be found, such as for records
+	/// inside a container which don't have spans of their own.
+	pub location: Option<Range<usize>>,
+	pub reason: WarningReason,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum WarningReason {
+	/// A date with the non-standard month 15, see
+	/// [`crate::parse::options::ParseOptions::strict_dates`]
+	NonconformantDate,
+	/// A data field wider than makes sense for the record's value type, see
+	/// [`crate::parse::options::ParseOptions::strict_widths`]
+	WidthMismatch,
+	/// A manufacturer code that isn't three uppercase letters, see
+	/// [`crate::parse::options::ParseOptions::strict_manufacturers`]
+	NonstandardManufacturer,
+}
+
+impl std::fmt::Display for WarningReason {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::NonconformantDate => write!(f, "date has a non-standard month"),
+			Self::WidthMismatch => write!(f, "data field is wider than the value type allows"),
+			Self::NonstandardManufacturer => {
+				write!(f, "manufacturer code is not three uppercase letters")
+			}
+		}
+	}
+}
+
+impl std::fmt::Display for ParseWarning {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match &self.location {
+			Some(location) => write!(
+				f,
+				"{} at bytes {}..{}",
+				self.reason, location.start, location.end
+			),
+			None => write!(f, "{} in a container record", self.reason),
+		}
+	}
+}
+
+impl ParseWarning {
+	/// Looks through a packet that has already been parsed for anything that
+	/// was only accepted because it was parsed leniently
+	pub(crate) fn for_packet(packet: &Packet, spans: &Spans) -> Vec<Self> {
+		let Packet::Long { message, .. } = packet else {
+			return Vec::new();
+		};
+		let mut warnings = Vec::new();
+
+		if let Some(manufacturer) = message.header().and_then(|header| header.manufacturer()) {
+			if !manufacturer.is_standard() {
+				warnings.push(Self {
+					// After the 4 byte identification number
+					location: spans
+						.header
+						.as_ref()
+						.map(|header| header.start + 4..header.start + 6),
+					reason: WarningReason::NonstandardManufacturer,
+				});
+			}
+		}
+
+		if let MBusMessage::ResponseFromDevice(_, DeviceResponse::Full(frame)) = message {
+			for (i, record) in frame.records.iter().enumerate() {
+				Self::for_record(record, spans.records.get(i), &mut warnings);
+			}
+		}
+
+		warnings
+	}
+
+	/// Checks a record and anything inside it. `spans` is `None` for records
+	/// in a container.
+	fn for_record(record: &Record, spans: Option<&RecordSpans>, warnings: &mut Vec<Self>) {
+		if record.has_nonconformant_date() {
+			warnings.push(Self {
+				location: spans.map(|spans| spans.data.clone()),
+				reason: WarningReason::NonconformantDate,
+			});
+		}
+		if record.has_width_mismatch() {
+			warnings.push(Self {
+				location: spans.map(|spans| spans.dib.clone()),
+				reason: WarningReason::WidthMismatch,
+			});
+		}
+		match &record.data {
+			DataType::EnhancedIdentification(id) if !id.manufacturer.is_standard() => {
+				warnings.push(Self {
+					location: spans.map(|spans| spans.data.start + 4..spans.data.start + 6),
+					reason: WarningReason::NonstandardManufacturer,
+				});
+			}
+			DataType::Container(records) => {
+				for record in records {
+					Self::for_record(record, None, warnings);
+				}
+			}
+			_ => {}
+		}
+	}
+}
+
+#[cfg(test)]
+mod test_warnings {
+	use winnow::prelude::*;
+	use winnow::Bytes;
+
+	use super::{ParseWarning, WarningReason};
+	use crate::parse::link_layer::{user_data_frame, Packet};
+	use crate::parse::options::ParseOptions;
+	use crate::utils::read_test_file;
+
+	fn parse(data: &[u8]) -> Vec<ParseWarning> {
+		let (_, warnings) = Packet::parse_with_warnings(ParseOptions::default())
+			.parse(Bytes::new(data))
+			.unwrap();
+		warnings
+	}
+
+	#[test]
+	fn test_month_15() {
+		let frame = user_data_frame(
+			0x01,
+			&[
+				0x7A, 0x05, 0x00, 0x00, 0x00, // Short header
+				0x04, 0x6D, 0x00, 0x00, 0x01, 0x0F, // Type F date in month 15
+			],
+		);
+
+		assert_eq!(
+			parse(&frame),
+			[ParseWarning {
+				location: Some(13..17),
+				reason: WarningReason::NonconformantDate,
+			}]
+		);
+	}
+
+	#[test]
+	fn test_container() {
+		let frame = user_data_frame(
+			0x01,
+			&[
+				0x78, // No header
+				0x0D, 0xFD, 0x3B, 0x06, // Wireless container with 6 bytes of records
+				0x04, 0x6D, 0x00, 0x00, 0x01, 0x0F, // Type F date in month 15
+			],
+		);
+
+		assert_eq!(
+			parse(&frame),
+			[ParseWarning {
+				location: None,
+				reason: WarningReason::NonconformantDate,
+			}]
+		);
+	}
+
+	#[test]
+	fn test_no_warnings() {
+		let data = read_test_file("./libmbus_test_data/test-frames/kamstrup_382_005.hex")
+			.expect("test file must be valid");
+
+		assert_eq!(parse(&data), []);
+	}
+}
@@ -0,0 +1,150 @@
+// Copyright 2024 Lexi Robinson
+// Licensed under the EUPL-1.2
+use winnow::binary::bits;
+use winnow::combinator::repeat;
+use winnow::error::StrContext;
+use winnow::prelude::*;
+use winnow::Bytes;
+
+use crate::parse::error::{MBResult, MBusError};
+use crate::parse::types::BitsInput;
+
+/// Converts data received over wireless M-Bus mode T from the meter back into
+/// the bytes it represents.
+///
+/// EN 13757-4:2019 9.2.2: Each nibble is sent as a 6 bit symbol with exactly
+/// three bits set, most significant nibble first. Any bits left over at the
+/// end that don't make up a whole byte are the postamble and are ignored.
+pub fn decode_3of6(data: &[u8]) -> MBResult<Vec<u8>> {
+	let num_bytes = data.len() * 8 / 12;
+	bits::bits::<_, _, MBusError, _, _>(repeat(num_bytes, decode_byte))
+		.context(StrContext::Label("3 out of 6 encoded data"))
+		.parse_next(&mut Bytes::new(data))
+}
+
+/// The CRC used by wireless M-Bus blocks, and for the format signature of
+/// compact frames.
+///
+/// EN 13757-4:2019 9.2.4: The polynomial is
+/// x^16 + x^13 + x^12 + x^11 + x^10 + x^8 + x^6 + x^5 + x^2 + 1, starting
+/// from zero with the result complemented.
+pub fn crc16(data: &[u8]) -> u16 {
+	const POLYNOMIAL: u16 = 0x3D65;
+	let crc = data.iter().fold(0_u16, |crc, byte| {
+		(0..8).fold(crc ^ u16::from(*byte) << 8, |crc, _| {
+			if crc & 0x8000 != 0 {
+				crc << 1 ^ POLYNOMIAL
+			} else {
+				crc << 1
+			}
+		})
+	});
+	!crc
+}
+
+fn decode_byte(input: &mut BitsInput<'_>) -> MBResult<u8> {
+	(decode_symbol, decode_symbol)
+		.map(|(high, low)| high << 4 | low)
+		.parse_next(input)
+}
+
+fn decode_symbol(input: &mut BitsInput<'_>) -> MBResult<u8> {
+	bits::take(6_usize)
+		.verify_map(|symbol: u8| {
+			Some(match symbol {
+				0b010110 => 0x0,
+				0b001101 => 0x1,
+				0b001110 => 0x2,
+				0b001011 => 0x3,
+				0b011100 => 0x4,
+				0b011001 => 0x5,
+				0b011010 => 0x6,
+				0b010011 => 0x7,
+				0b101100 => 0x8,
+				0b100101 => 0x9,
+				0b100110 => 0xA,
+				0b100011 => 0xB,
+				0b110100 => 0xC,
+				0b110001 => 0xD,
+				0b110010 => 0xE,
+				0b101001 => 0xF,
+				_ => return None,
+			})
+		})
+		.context(StrContext::Label("3 out of 6 symbol"))
+		.parse_next(input)
+}
+
+#[cfg(test)]
+mod test_decode_3of6 {
+	use rstest::rstest;
+
+	use super::decode_3of6;
+
+	#[rstest]
+	#[case::nibble_0(0b010110, 0x0)]
+	#[case::nibble_1(0b001101, 0x1)]
+	#[case::nibble_2(0b001110, 0x2)]
+	#[case::nibble_3(0b001011, 0x3)]
+	#[case::nibble_4(0b011100, 0x4)]
+	#[case::nibble_5(0b011001, 0x5)]
+	#[case::nibble_6(0b011010, 0x6)]
+	#[case::nibble_7(0b010011, 0x7)]
+	#[case::nibble_8(0b101100, 0x8)]
+	#[case::nibble_9(0b100101, 0x9)]
+	#[case::nibble_a(0b100110, 0xA)]
+	#[case::nibble_b(0b100011, 0xB)]
+	#[case::nibble_c(0b110100, 0xC)]
+	#[case::nibble_d(0b110001, 0xD)]
+	#[case::nibble_e(0b110010, 0xE)]
+	#[case::nibble_f(0b101001, 0xF)]
+	fn test_symbol_table(#[case] symbol: u16, #[case] nibble: u8) {
+		// The same symbol twice, followed by a 4 bit postamble
+		let encoded = (symbol << 10 | symbol << 4).to_be_bytes();
+
+		let result = decode_3of6(&encoded).unwrap();
+
+		assert_eq!(result, [nibble << 4 | nibble]);
+	}
+
+	#[test]
+	fn test_even_length() {
+		// 0x12 0x34 is the symbols 1, 2, 3 and 4 back to back
+		let result = decode_3of6(&[0x34, 0xE2, 0xDC]).unwrap();
+
+		assert_eq!(result, [0x12, 0x34]);
+	}
+
+	#[test]
+	fn test_postamble() {
+		let result = decode_3of6(&[0x71, 0xC5]).unwrap();
+
+		assert_eq!(result, [0x44]);
+	}
+
+	#[test]
+	fn test_invalid_symbol() {
+		decode_3of6(&[0x00, 0x00, 0x00]).unwrap_err();
+	}
+
+	#[test]
+	fn test_empty() {
+		assert_eq!(decode_3of6(&[]).unwrap(), []);
+	}
+}
+
+#[cfg(test)]
+mod test_crc16 {
+	use super::crc16;
+
+	#[test]
+	fn test_check_value() {
+		// The standard check value for CRC-16/EN-13757
+		assert_eq!(crc16(b"123456789"), 0xC2B7);
+	}
+
+	#[test]
+	fn test_empty() {
+		assert_eq!(crc16(&[]), 0xFFFF);
+	}
+}
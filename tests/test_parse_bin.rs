@@ -0,0 +1,47 @@
+// Copyright 2024 Lexi Robinson
+// Licensed under the EUPL-1.2
+use std::process::Command;
+
+const KAMSTRUP: &str = "./libmbus_test_data/test-frames/kamstrup_multical_601.hex";
+
+fn test_parse() -> Command {
+	Command::new(env!("CARGO_BIN_EXE_test_parse"))
+}
+
+#[test]
+fn test_debug_output() {
+	let output = test_parse().arg(KAMSTRUP).output().unwrap();
+
+	assert!(output.status.success());
+	let stdout = String::from_utf8(output.stdout).unwrap();
+	assert!(stdout.starts_with("File "));
+	assert!(stdout.contains("ResponseFromDevice"));
+}
+
+#[test]
+fn test_unknown_option() {
+	let output = test_parse().args(["--yaml", KAMSTRUP]).output().unwrap();
+
+	assert_eq!(output.status.code(), Some(2));
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_json_output() {
+	let output = test_parse().args(["--json", KAMSTRUP]).output().unwrap();
+
+	assert!(output.status.success());
+	let packet: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+	let header = &packet["Long"]["message"]["ResponseFromDevice"][0]["Long"];
+	assert_eq!(header["manufacturer"], "KAM");
+	assert_eq!(header["identifier"], 6855817);
+}
+
+#[cfg(not(feature = "json"))]
+#[test]
+fn test_json_needs_feature() {
+	let output = test_parse().args(["--json", KAMSTRUP]).output().unwrap();
+
+	assert_eq!(output.status.code(), Some(2));
+	assert!(output.stdout.is_empty());
+}